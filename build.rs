@@ -1,4 +1,25 @@
+use std::{env, fs, path::PathBuf};
+
 fn main() {
+    // embassy-stm32 is built for the C8 either way, the CB only has more flash
+    let flash_kb = if env::var_os("CARGO_FEATURE_STM32F103CB").is_some() {
+        128
+    } else {
+        64
+    };
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    let memory = format!(
+        "MEMORY
+{{
+    FLASH : ORIGIN = 0x08000000, LENGTH = {flash_kb}K
+    RAM : ORIGIN = 0x20000000, LENGTH = 20K
+}}
+"
+    );
+    fs::write(out.join("memory.x"), memory).unwrap();
+    println!("cargo:rustc-link-search={}", out.display());
+    println!("cargo:rerun-if-changed=build.rs");
+
     println!("cargo:rustc-link-arg-bins=--nmagic");
     println!("cargo:rustc-link-arg-bins=-Tlink.x");
     println!("cargo:rustc-link-arg-bins=-Tdefmt.x");
@@ -0,0 +1,262 @@
+//! Asset bundle stored in the external SPI flash.
+//!
+//! The bundle is built by `boe-tools pack` and written to the W25Qxx at
+//! [`ASSET_BASE`]. All integers are little endian. It starts with a header:
+//!
+//! | Offset | Size | Content                              |
+//! |--------|------|--------------------------------------|
+//! | 0      | 4    | magic `BOEA`                         |
+//! | 4      | 2    | version, currently 1                 |
+//! | 6      | 2    | number of entries                    |
+//! | 8      | 4    | CRC-32 of the table of contents      |
+//! | 12     | 4    | total bundle size                    |
+//!
+//! followed by the table of contents, one 24 byte entry per asset:
+//!
+//! | Offset | Size | Content                                  |
+//! |--------|------|------------------------------------------|
+//! | 0      | 4    | FNV-1a hash of the name                  |
+//! | 4      | 1    | [`AssetKind`]                            |
+//! | 5      | 3    | reserved                                 |
+//! | 8      | 4    | offset of the data from the bundle start |
+//! | 12     | 4    | length of the data                       |
+//! | 16     | 4    | CRC-32 of the data                       |
+//! | 20     | 2    | width, for images                        |
+//! | 22     | 2    | height, for images                       |
+
+use crate::w25qxx::W25Qxx;
+use num_enum::TryFromPrimitive;
+
+/// Where the bundle lives in the external flash, clear of the sector the demo
+/// program uses for its read/write test.
+pub const ASSET_BASE: u32 = 0x01_0000;
+
+pub const ASSET_MAGIC: &[u8; 4] = b"BOEA";
+pub const ASSET_VERSION: u16 = 1;
+pub const ASSET_HEADER_SIZE: u32 = 16;
+pub const ASSET_ENTRY_SIZE: u32 = 24;
+
+/// Bytes read from flash at once while verifying checksums.
+const CHUNK_SIZE: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum AssetError {
+    /// No bundle at the base address
+    BadMagic,
+    UnsupportedVersion,
+    /// The table of contents does not match its checksum, or an entry or
+    /// the table itself reaches past the total bundle size
+    CorruptToc,
+    /// The asset data does not match its checksum
+    CorruptAsset,
+    UnknownKind,
+    NotFound,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, defmt::Format)]
+#[repr(u8)]
+pub enum AssetKind {
+    /// Opaque bytes
+    Raw = 0,
+    /// Big endian RGB565 pixels, as expected by `LCD::show_picture`
+    Rgb565 = 1,
+    Bmp = 2,
+    Qoi = 3,
+    Sprite = 4,
+    /// See `lcd::animation`
+    Animation = 5,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub struct AssetEntry {
+    pub hash: u32,
+    pub kind: AssetKind,
+    /// Absolute flash address of the data
+    pub addr: u32,
+    pub len: u32,
+    pub crc: u32,
+    pub width: u16,
+    pub height: u16,
+}
+
+/// 32-bit FNV-1a, used to look up assets by name.
+pub const fn name_hash(name: &str) -> u32 {
+    let bytes = name.as_bytes();
+    let mut hash = 0x811c_9dc5u32;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+        i += 1;
+    }
+    hash
+}
+
+/// Incremental CRC-32 (IEEE 802.3), start with `0` and feed the data in
+/// pieces.
+pub fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    const TABLE: [u32; 16] = [
+        0x00000000, 0x1db71064, 0x3b6e20c8, 0x26d930ac, 0x76dc4190, 0x6b6b51f4, 0x4db26158,
+        0x5005713c, 0xedb88320, 0xf00f9344, 0xd6d6a3e8, 0xcb61b38c, 0x9b64c2b0, 0x86d3d2d4,
+        0xa00ae278, 0xbdbdf21c,
+    ];
+
+    let mut crc = !crc;
+    for &byte in data {
+        crc = TABLE[((crc ^ byte as u32) & 0x0f) as usize] ^ (crc >> 4);
+        crc = TABLE[((crc ^ (byte as u32 >> 4)) & 0x0f) as usize] ^ (crc >> 4);
+    }
+    !crc
+}
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+pub struct AssetStore {
+    base: u32,
+    count: u16,
+    /// Total bundle size from the header
+    size: u32,
+}
+
+impl AssetStore {
+    /// Reads the bundle header at `base` and checks the table of contents.
+    pub async fn open(flash: &mut W25Qxx, base: u32) -> Result<Self, AssetError> {
+        let mut header = [0u8; ASSET_HEADER_SIZE as usize];
+        flash.buffer_read(&mut header, base).await;
+        if &header[..4] != ASSET_MAGIC {
+            return Err(AssetError::BadMagic);
+        }
+        if u16_at(&header, 4) != ASSET_VERSION {
+            return Err(AssetError::UnsupportedVersion);
+        }
+        let count = u16_at(&header, 6);
+        let toc_crc = u32_at(&header, 8);
+        let size = u32_at(&header, 12);
+
+        let toc_len = u32::from(count) * ASSET_ENTRY_SIZE;
+        if ASSET_HEADER_SIZE + toc_len > size || base.checked_add(size).is_none() {
+            return Err(AssetError::CorruptToc);
+        }
+        let crc = checksum(flash, base + ASSET_HEADER_SIZE, toc_len).await;
+        if crc != toc_crc {
+            return Err(AssetError::CorruptToc);
+        }
+
+        Ok(Self { base, count, size })
+    }
+
+    pub fn len(&self) -> u16 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub async fn entry(&self, flash: &mut W25Qxx, index: u16) -> Result<AssetEntry, AssetError> {
+        if index >= self.count {
+            return Err(AssetError::NotFound);
+        }
+        let mut raw = [0u8; ASSET_ENTRY_SIZE as usize];
+        let addr = self.base + ASSET_HEADER_SIZE + u32::from(index) * ASSET_ENTRY_SIZE;
+        flash.buffer_read(&mut raw, addr).await;
+
+        // The data has to lie after the table of contents and within the
+        // bundle
+        let (offset, len) = (u32_at(&raw, 8), u32_at(&raw, 12));
+        let toc_end = ASSET_HEADER_SIZE + u32::from(self.count) * ASSET_ENTRY_SIZE;
+        match offset.checked_add(len) {
+            Some(end) if offset >= toc_end && end <= self.size => {}
+            _ => return Err(AssetError::CorruptToc),
+        }
+
+        Ok(AssetEntry {
+            hash: u32_at(&raw, 0),
+            kind: AssetKind::try_from_primitive(raw[4]).map_err(|_| AssetError::UnknownKind)?,
+            addr: self.base + offset,
+            len,
+            crc: u32_at(&raw, 16),
+            width: u16_at(&raw, 20),
+            height: u16_at(&raw, 22),
+        })
+    }
+
+    pub async fn find(&self, flash: &mut W25Qxx, name: &str) -> Result<AssetEntry, AssetError> {
+        let hash = name_hash(name);
+        for index in 0..self.count {
+            let entry = self.entry(flash, index).await?;
+            if entry.hash == hash {
+                return Ok(entry);
+            }
+        }
+        Err(AssetError::NotFound)
+    }
+
+    /// Checks the data of `entry` against its checksum.
+    pub async fn verify(&self, flash: &mut W25Qxx, entry: &AssetEntry) -> Result<(), AssetError> {
+        if checksum(flash, entry.addr, entry.len).await == entry.crc {
+            Ok(())
+        } else {
+            Err(AssetError::CorruptAsset)
+        }
+    }
+}
+
+async fn checksum(flash: &mut W25Qxx, addr: u32, len: u32) -> u32 {
+    let mut reader = AssetReader {
+        addr,
+        remaining: len,
+    };
+    let mut buf = [0u8; CHUNK_SIZE];
+    let mut crc = 0;
+    loop {
+        let n = reader.read(flash, &mut buf).await;
+        if n == 0 {
+            return crc;
+        }
+        crc = crc32_update(crc, &buf[..n]);
+    }
+}
+
+/// Sequential reader over the data of one asset.
+pub struct AssetReader {
+    addr: u32,
+    remaining: u32,
+}
+
+impl AssetReader {
+    pub fn new(entry: &AssetEntry) -> Self {
+        Self {
+            addr: entry.addr,
+            remaining: entry.len,
+        }
+    }
+
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+
+    /// Reads the next bytes of the asset into `buf`, returning how many were
+    /// read; 0 once the end is reached.
+    pub async fn read(&mut self, flash: &mut W25Qxx, buf: &mut [u8]) -> usize {
+        let n = buf.len().min(self.remaining as usize);
+        if n == 0 {
+            return 0;
+        }
+        flash.buffer_read(&mut buf[..n], self.addr).await;
+        self.addr += n as u32;
+        self.remaining -= n as u32;
+        n
+    }
+}
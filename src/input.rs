@@ -0,0 +1,220 @@
+//! Buttons and a rotary encoder turned into a stream of [`InputEvent`]s.
+//!
+//! Every input runs in a task of its own and sends its events to one
+//! [`InputChannel`], which UI tasks receive from:
+//!
+//! ```ignore
+//! static INPUT: InputChannel = InputChannel::new();
+//!
+//! #[embassy_executor::task]
+//! async fn key_task(mut key: DebouncedButton<'static>) {
+//!     key.run(INPUT.sender()).await
+//! }
+//! ```
+
+use embassy_futures::select::{Either, select};
+use embassy_stm32::{exti::ExtiInput, timer, timer::qei::Qei};
+use embassy_sync::{
+    blocking_mutex::raw::CriticalSectionRawMutex,
+    channel::{Channel, Sender},
+};
+use embassy_time::{Duration, Instant, Ticker, Timer};
+
+/// Events buffered before senders wait for the UI to catch up.
+pub const INPUT_QUEUE_LEN: usize = 8;
+
+/// How often the encoder count is read. The 16-bit counter must not move by
+/// half its range in between.
+const ENCODER_POLL_MS: u64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum InputEvent {
+    /// Button `id` went down
+    Pressed(u8),
+    /// Button `id` went up, after every press
+    Released(u8),
+    /// Button `id` was held down for [`ButtonConfig::long_press_ms`], sent
+    /// while it is still down
+    LongPress(u8),
+    /// Button `id` was clicked twice within
+    /// [`ButtonConfig::double_click_ms`], sent on the second release. Both
+    /// clicks are reported on their own as well.
+    DoubleClick(u8),
+    /// The encoder turned by this many detents, positive when its counter
+    /// counts up
+    Rotated(i16),
+}
+
+pub type InputChannel = Channel<CriticalSectionRawMutex, InputEvent, INPUT_QUEUE_LEN>;
+pub type InputSender = Sender<'static, CriticalSectionRawMutex, InputEvent, INPUT_QUEUE_LEN>;
+
+#[derive(Clone, Copy)]
+pub struct ButtonConfig {
+    /// How long the contacts may bounce, in milliseconds.
+    pub debounce_ms: u64,
+    pub long_press_ms: u64,
+    /// Longest time from the first press to the second one of a double click,
+    /// in milliseconds.
+    pub double_click_ms: u64,
+    /// The pin reads low while the button is down, as with a button to
+    /// ground and a pull-up.
+    pub active_low: bool,
+}
+
+impl Default for ButtonConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ButtonConfig {
+    pub const fn new() -> Self {
+        Self {
+            debounce_ms: 20,
+            long_press_ms: 800,
+            double_click_ms: 400,
+            active_low: true,
+        }
+    }
+
+    pub const fn debounce_ms(mut self, debounce_ms: u64) -> Self {
+        self.debounce_ms = debounce_ms;
+        self
+    }
+
+    pub const fn long_press_ms(mut self, long_press_ms: u64) -> Self {
+        self.long_press_ms = long_press_ms;
+        self
+    }
+
+    pub const fn double_click_ms(mut self, double_click_ms: u64) -> Self {
+        self.double_click_ms = double_click_ms;
+        self
+    }
+
+    pub const fn active_low(mut self, active_low: bool) -> Self {
+        self.active_low = active_low;
+        self
+    }
+}
+
+/// Push button on an EXTI line, waiting for edges instead of polling.
+pub struct DebouncedButton<'d> {
+    id: u8,
+    pin: ExtiInput<'d>,
+    config: ButtonConfig,
+}
+
+impl<'d> DebouncedButton<'d> {
+    /// `id` tells the buttons apart in [`InputEvent`]s.
+    pub fn new(id: u8, pin: ExtiInput<'d>, config: ButtonConfig) -> Self {
+        Self { id, pin, config }
+    }
+
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// Whether the pin reads as the button being down right now.
+    pub fn is_pressed(&self) -> bool {
+        self.pin.is_low() == self.config.active_low
+    }
+
+    async fn wait_for(&mut self, pressed: bool) {
+        if pressed == self.config.active_low {
+            self.pin.wait_for_low().await;
+        } else {
+            self.pin.wait_for_high().await;
+        }
+    }
+
+    /// Waits out the bounce after an edge and tells whether the button
+    /// settled `pressed`.
+    async fn settle(&mut self, pressed: bool) -> bool {
+        Timer::after_millis(self.config.debounce_ms).await;
+        self.is_pressed() == pressed
+    }
+
+    /// Sends the events of this button forever.
+    pub async fn run(&mut self, sender: InputSender) -> ! {
+        let long_press = Duration::from_millis(self.config.long_press_ms);
+        let double_click = Duration::from_millis(self.config.double_click_ms);
+        // Press of the last click that may start a double click
+        let mut last_click: Option<Instant> = None;
+
+        loop {
+            self.wait_for(true).await;
+            if !self.settle(true).await {
+                continue;
+            }
+            let pressed_at = Instant::now();
+            sender.send(InputEvent::Pressed(self.id)).await;
+
+            let held = match select(self.wait_for(false), Timer::at(pressed_at + long_press)).await
+            {
+                Either::First(()) => false,
+                Either::Second(()) => {
+                    sender.send(InputEvent::LongPress(self.id)).await;
+                    self.wait_for(false).await;
+                    true
+                }
+            };
+            // Bouncing back down is still the same press
+            while !self.settle(false).await {
+                self.wait_for(false).await;
+            }
+            sender.send(InputEvent::Released(self.id)).await;
+
+            last_click = match last_click {
+                _ if held => None,
+                Some(at) if pressed_at - at <= double_click => {
+                    sender.send(InputEvent::DoubleClick(self.id)).await;
+                    None
+                }
+                _ => Some(pressed_at),
+            };
+        }
+    }
+}
+
+/// Quadrature rotary encoder on the CH1 and CH2 pins of a timer in encoder
+/// mode, which counts the steps in hardware without interrupts.
+pub struct RotaryEncoder<'d, T: timer::GeneralInstance4Channel> {
+    qei: Qei<'d, T>,
+    counts_per_detent: i32,
+    last: u16,
+    /// Counts not yet making up a whole detent
+    remainder: i32,
+}
+
+impl<'d, T: timer::GeneralInstance4Channel> RotaryEncoder<'d, T> {
+    /// `counts_per_detent` is how far the counter moves from one click of the
+    /// knob to the next, 4 for most mechanical encoders.
+    pub fn new(qei: Qei<'d, T>, counts_per_detent: u16) -> Self {
+        let last = qei.count();
+        Self {
+            qei,
+            counts_per_detent: i32::from(counts_per_detent.max(1)),
+            last,
+            remainder: 0,
+        }
+    }
+
+    /// Sends the rotation of the encoder forever.
+    pub async fn run(&mut self, sender: InputSender) -> ! {
+        let mut ticker = Ticker::every(Duration::from_millis(ENCODER_POLL_MS));
+        loop {
+            ticker.next().await;
+            let count = self.qei.count();
+            self.remainder += i32::from(count.wrapping_sub(self.last) as i16);
+            self.last = count;
+
+            let detents = self.remainder / self.counts_per_detent;
+            if detents != 0 {
+                self.remainder -= detents * self.counts_per_detent;
+                let detents = detents.clamp(i16::MIN.into(), i16::MAX.into()) as i16;
+                sender.send(InputEvent::Rotated(detents)).await;
+            }
+        }
+    }
+}
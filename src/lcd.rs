@@ -1,8 +1,22 @@
+pub mod animation;
+pub mod barcode;
+pub mod bmp;
+pub mod console;
 pub mod font;
+pub mod layout;
+pub mod number;
+pub mod paint;
 pub mod pic;
-
-use as_what::{AsU16, AsUsize};
+pub mod qoi;
+pub mod qr;
+pub mod shared;
+pub mod sprite;
+pub mod style;
+
+use crate::w25qxx::W25Qxx;
+use as_what::{AsU16, AsU32, AsUsize};
 use core::cmp::{Ordering, max};
+use embassy_futures::join::join;
 use embassy_stm32::{
     gpio::Output,
     mode::Async,
@@ -10,7 +24,12 @@ use embassy_stm32::{
     timer::{self, simple_pwm::SimplePwmChannel},
 };
 use embassy_time::Timer;
-use font::{ASCII_1206, ASCII_1608, ASCII_2412, ASCII_3216, ChineseFontSize, FontSize};
+use font::{AlphaFont, ChineseFontSize, FontSize};
+use number::{NumberFormat, Pad};
+use style::GlyphStyle;
+
+/// Widest glyph supported by [`LCD::show_alpha_char`].
+pub const ALPHA_FONT_MAX_WIDTH: usize = 32;
 
 pub const DIRECTION: Direction = Direction::Horizontal0;
 
@@ -24,6 +43,35 @@ pub const HEIGHT: u16 = match DIRECTION {
     Direction::Horizontal0 | Direction::Horizontal1 => 80,
 };
 
+/// Bytes per buffer when streaming pictures from the external flash, two of
+/// them are in use at a time.
+pub const FLASH_CHUNK_SIZE: usize = 512;
+
+/// Lines of frame memory the controller can scroll through.
+pub const FRAME_LINES: u16 = 162;
+
+/// Axis of [`DIRECTION`] along which the controller's vertical scroll moves
+/// the picture, `None` where the frame memory rows are mirrored.
+pub const HW_SCROLL: Option<ScrollAxis> = match DIRECTION {
+    Direction::Vertical0 => Some(ScrollAxis::Y),
+    Direction::Horizontal0 => Some(ScrollAxis::X),
+    Direction::Vertical1 | Direction::Horizontal1 => None,
+};
+
+/// Whether the controller's vertical scroll moves the picture along the
+/// y axis of [`DIRECTION`], see [`HW_SCROLL`].
+pub const HW_VSCROLL: bool = matches!(HW_SCROLL, Some(ScrollAxis::Y));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum ScrollAxis {
+    /// Frame memory rows are panel columns, the scroll start moves the
+    /// picture left
+    X,
+    /// Frame memory rows are panel rows, the scroll start moves the picture
+    /// up
+    Y,
+}
+
 #[repr(u8)]
 pub enum Direction {
     Vertical0 = 0,
@@ -55,6 +103,59 @@ pub enum Color {
     Lbblue = 0x2B12,
 }
 
+/// Packs 8-bit red, green and blue channels into RGB565.
+pub const fn rgb565(r: u8, g: u8, b: u8) -> u16 {
+    ((r as u16 & 0xF8) << 8) | ((g as u16 & 0xFC) << 3) | (b as u16 >> 3)
+}
+
+/// Mixes two RGB565 colors, `alpha` being the weight of `fg` out of 255.
+pub const fn blend(fg: u16, bg: u16, alpha: u8) -> u16 {
+    const fn mix(fg: u16, bg: u16, alpha: u8, shift: u32, mask: u16) -> u16 {
+        let f = ((fg >> shift) & mask) as u32;
+        let b = ((bg >> shift) & mask) as u32;
+        let a = alpha as u32;
+        (((f * a + b * (255 - a) + 127) / 255) as u16) << shift
+    }
+
+    mix(fg, bg, alpha, 11, 0x1f) | mix(fg, bg, alpha, 5, 0x3f) | mix(fg, bg, alpha, 0, 0x1f)
+}
+
+/// Axis-aligned rectangle in panel coordinates.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub w: u16,
+    pub h: u16,
+}
+
+impl Rect {
+    pub const fn new(x: u16, y: u16, w: u16, h: u16) -> Self {
+        Self { x, y, w, h }
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.w == 0 || self.h == 0
+    }
+
+    pub const fn right(&self) -> u16 {
+        self.x + self.w
+    }
+
+    pub const fn bottom(&self) -> u16 {
+        self.y + self.h
+    }
+
+    /// Overlap of the two rectangles, empty if they do not overlap.
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        Rect::new(x, y, right.saturating_sub(x), bottom.saturating_sub(y))
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum CharMode {
     NonOverlay,
@@ -67,6 +168,8 @@ pub struct LCD<C: timer::GeneralInstance4Channel> {
     res: Output<'static>,
     blk: SimplePwmChannel<'static, C>,
     dc: Output<'static>,
+    /// Backlight percent last set
+    brightness: u8,
 }
 
 impl<C: timer::GeneralInstance4Channel> LCD<C> {
@@ -80,6 +183,7 @@ impl<C: timer::GeneralInstance4Channel> LCD<C> {
             res,
             blk,
             dc,
+            brightness: 100,
         }
     }
 
@@ -90,7 +194,7 @@ impl<C: timer::GeneralInstance4Channel> LCD<C> {
         Timer::after_millis(100).await;
 
         // 打开背光
-        self.blk.set_duty_cycle_percent(100);
+        self.set_brightness(100);
         self.blk.enable();
         Timer::after_millis(100).await;
 
@@ -171,11 +275,55 @@ impl<C: timer::GeneralInstance4Channel> LCD<C> {
         }
     }
 
+    pub async fn fill_rect(&mut self, rect: Rect, color: u16) {
+        if !rect.is_empty() {
+            self.fill(rect.x, rect.y, rect.right(), rect.bottom(), color)
+                .await;
+        }
+    }
+
     pub async fn draw_point(&mut self, x: u16, y: u16, color: u16) {
         self.set_address(x, y, x, y).await;
         self.write_data(&[color]).await;
     }
 
+    /// Fills a `w x h` window at `(x, y)` with `color`, clipped to the
+    /// panel, sending a row of pixels per transfer.
+    async fn fill_window(&mut self, (x, y): (u16, u16), (w, h): (u16, u16), color: u16) {
+        if w == 0 || h == 0 || x >= WIDTH || y >= HEIGHT {
+            return;
+        }
+        let w = w.min(WIDTH - x);
+        let h = h.min(HEIGHT - y);
+        self.set_address(x, y, x + w - 1, y + h - 1).await;
+
+        let row = [color; WIDTH as usize];
+        for _ in 0..h {
+            self.write_data(&row[..w.as_usize()]).await;
+        }
+    }
+
+    /// Writes one row of pixels starting at `(x, y)`, clipped to the panel.
+    pub async fn draw_span(&mut self, (x, y): (u16, u16), pixels: &[u16]) {
+        if pixels.is_empty() || x >= WIDTH || y >= HEIGHT {
+            return;
+        }
+        let len = pixels.len().min((WIDTH - x).as_usize());
+        self.set_address(x, y, x + len.as_u16() - 1, y).await;
+        self.write_data(&pixels[..len]).await;
+    }
+
+    /// Writes one column of pixels downwards from `(x, y)`, clipped to the
+    /// panel.
+    pub async fn draw_column(&mut self, (x, y): (u16, u16), pixels: &[u16]) {
+        if pixels.is_empty() || x >= WIDTH || y >= HEIGHT {
+            return;
+        }
+        let len = pixels.len().min((HEIGHT - y).as_usize());
+        self.set_address(x, y, x, y + len.as_u16() - 1).await;
+        self.write_data(&pixels[..len]).await;
+    }
+
     async fn write_reg(&mut self, data: &[u8]) {
         self.dc.set_low(); // write command
         self.write_bus(data).await;
@@ -317,341 +465,146 @@ impl<C: timer::GeneralInstance4Channel> LCD<C> {
         }
     }
 
+    /// Draws an ASCII character in `style` and returns its width. A
+    /// character the font lacks is left blank.
     pub async fn show_char(
-        &mut self, (mut x, mut y): (u16, u16), ch: char, fc: u16, bc: u16, size: FontSize,
-        mode: CharMode,
-    ) {
-        let size_y = size.y();
-        let size_x = size.x();
-        let typeface_num = (size_x / 8 + if size_x % 8 != 0 { 1 } else { 0 }) * size_y;
-        let num = ch.as_usize() - ' '.as_usize();
-
-        let x0 = x;
-        let mut m = 0;
-
-        // Set address range for this character
-        self.set_address(x, y, x + size_x.as_u16() - 1, y + size_y.as_u16() - 1)
-            .await;
-
-        // Select appropriate font based on size
-        for i in 0..typeface_num.as_usize() {
-            // In a real implementation, we would access font data here
-            // For this example, we'll just use a placeholder
-            let temp = match size {
-                FontSize::_6x12 => ASCII_1206[num][i],
-                FontSize::_8x16 => ASCII_1608[num][i],
-                FontSize::_12x24 => ASCII_2412[num][i],
-                FontSize::_16x32 => ASCII_3216[num][i],
-            };
-
-            for t in 0..8 {
-                match mode {
-                    CharMode::NonOverlay => {
-                        if (temp & (0x01 << t)) != 0 {
-                            self.write_data(&[fc]).await;
-                        } else {
-                            self.write_data(&[bc]).await;
-                        }
-
-                        m += 1;
-                        if m % size_x == 0 {
-                            m = 0;
-                            break;
-                        }
-                    }
-                    CharMode::Overlay => {
-                        if (temp & (0x01 << t)) != 0 {
-                            self.draw_point(x, y, fc).await;
-                        }
-
-                        x += 1;
-                        if (x - x0) == size_x as u16 {
-                            x = x0;
-                            y += 1;
-                            break;
-                        }
-                    }
-                }
-            }
+        &mut self, (x, y): (u16, u16), ch: char, size: FontSize, style: &GlyphStyle,
+    ) -> u16 {
+        let (w, h) = (size.x().as_u16(), size.y().as_u16());
+        match font::ascii_glyph(ch, size) {
+            Some(msk) => self.draw_glyph((x, y), msk, (w, h), style).await,
+            None => style.glyph_size(w, h).0,
         }
     }
 
     pub async fn show_string(
-        &mut self, (mut x, y): (u16, u16), s: &str, fc: u16, bc: u16, size: FontSize,
-        mode: CharMode,
+        &mut self, (mut x, y): (u16, u16), s: &str, size: FontSize, style: &GlyphStyle,
     ) {
-        let size_y = size.y();
         for c in s.chars() {
-            self.show_char((x, y), c, fc, bc, size, mode).await;
-            x += size_y.as_u16() / 2;
+            x += self.show_char((x, y), c, size, style).await;
         }
     }
 
+    /// Draws `num` right aligned in `len` characters, padded with spaces.
     pub async fn show_int_num(
-        &mut self, (x, y): (u16, u16), num: u16, len: u8, fc: u16, bc: u16, size: FontSize,
+        &mut self, (x, y): (u16, u16), num: u32, len: u8, fc: u16, bc: u16, size: FontSize,
     ) {
-        let mut enshow = false;
-        let size_x = size.x();
-
-        for t in 0..len {
-            let temp = ((num / 10u16.pow((len - t - 1) as u32)) % 10) as u8;
-
-            if !enshow && t < (len - 1) {
-                if temp == 0 {
-                    self.show_char(
-                        (x + t as u16 * size_x as u16, y),
-                        ' ',
-                        fc,
-                        bc,
-                        size,
-                        CharMode::NonOverlay,
-                    )
-                    .await;
-                    continue;
-                } else {
-                    enshow = true;
-                }
-            }
-
-            self.show_char(
-                (x + t as u16 * size_x as u16, y),
-                (temp + 48) as char,
-                fc,
-                bc,
-                size,
-                CharMode::NonOverlay,
-            )
-            .await;
-        }
+        let format = NumberFormat::new().width(len).pad(Pad::Space);
+        self.show_number((x, y), num, &format, fc, bc, size).await;
     }
 
+    /// Draws `num` with two decimals and `len` digits in total, padded with
+    /// zeros.
     pub async fn show_float_num(
-        &mut self, (x, y): (u16, u16), num: f32, mut len: u8, fc: u16, bc: u16, size: FontSize,
+        &mut self, (x, y): (u16, u16), num: f32, len: u8, fc: u16, bc: u16, size: FontSize,
     ) {
-        let size_x = size.x();
-        let num1 = (num * 100.0) as u16;
-
-        let mut t = 0;
-
-        loop {
-            let temp = ((num1 / 10u16.pow((len - t - 1) as u32)) % 10) as u8;
-
-            if t == (len - 2) {
-                self.show_char(
-                    (x + (len - 2) as u16 * size_x as u16, y),
-                    '.',
-                    fc,
-                    bc,
-                    size,
-                    CharMode::NonOverlay,
-                )
-                .await;
-                t += 1;
-                len += 1;
-            }
-
-            self.show_char(
-                (x + t as u16 * size_x as u16, y),
-                (temp + 48) as char,
-                fc,
-                bc,
-                size,
-                CharMode::NonOverlay,
-            )
-            .await;
-
-            if t >= len {
-                break;
-            }
-
-            t += 1;
-        }
+        let format = NumberFormat::new()
+            .decimals(2)
+            .width(len.saturating_add(1))
+            .pad(Pad::Zero);
+        self.show_number((x, y), num, &format, fc, bc, size).await;
     }
 
     pub async fn show_chinese(
-        &mut self, (mut x, y): (u16, u16), s: &str, fc: u16, bc: u16, size: ChineseFontSize,
-        mode: CharMode,
+        &mut self, (mut x, y): (u16, u16), s: &str, size: ChineseFontSize, style: &GlyphStyle,
     ) {
         for ch in s.chars() {
-            self.show_chinese_char((x, y), ch, fc, bc, size, mode).await;
-            x += size.y() as u16;
+            x += self.show_chinese_char((x, y), ch, size, style).await;
         }
     }
 
+    /// Draws one character of a Chinese font in `style` and returns its
+    /// width. A character the font lacks is left blank.
     async fn show_chinese_char(
-        &mut self, (mut x, mut y): (u16, u16), ch: char, fc: u16, bc: u16, size: ChineseFontSize,
-        mode: CharMode,
-    ) {
-        let size_y = size.y() as usize;
-        let size_x = size.x() as usize;
-        let typeface_num = (size_x / 8 + if size_x % 8 != 0 { 1 } else { 0 }) * size_y;
-
-        let x0 = x;
-        let mut m = 0;
+        &mut self, (x, y): (u16, u16), ch: char, size: ChineseFontSize, style: &GlyphStyle,
+    ) -> u16 {
+        let (w, h) = (size.x().as_u16(), size.y().as_u16());
+        match font::chinese_glyph(ch, size) {
+            Some(msk) => self.draw_glyph((x, y), msk, (w, h), style).await,
+            None => style.glyph_size(w, h).0,
+        }
+    }
 
-        self.set_address(x, y, x + size_y as u16 - 1, y + size_y as u16 - 1)
-            .await;
+    /// Draws `ch` from an anti-aliased font, blending partially covered
+    /// pixels between `fc` and `bc`.
+    ///
+    /// The panel can not be read back, so in [`CharMode::Overlay`] the edges
+    /// are still blended against `bc` and only fully transparent pixels are
+    /// left untouched.
+    pub async fn show_alpha_char(
+        &mut self, (x, y): (u16, u16), ch: char, fc: u16, bc: u16, font: &AlphaFont, mode: CharMode,
+    ) {
+        let Some(glyph) = font.glyph(ch) else {
+            return;
+        };
+
+        let max_alpha = font.bpp.max();
+        let color = |alpha: u8| match alpha {
+            0 => bc,
+            a if a == max_alpha => fc,
+            a => blend(fc, bc, (a as u16 * 255 / max_alpha as u16) as u8),
+        };
+        if x >= WIDTH || y >= HEIGHT {
+            return;
+        }
+        let mut row = [0u16; ALPHA_FONT_MAX_WIDTH];
+        let width = font
+            .width
+            .as_u16()
+            .min(WIDTH - x)
+            .as_usize()
+            .min(ALPHA_FONT_MAX_WIDTH);
+        // At most `font.height`, so it still fits a `u8`
+        let rows = font.height.as_u16().min(HEIGHT - y) as u8;
+        if width == 0 || rows == 0 {
+            return;
+        }
 
-        match size {
-            ChineseFontSize::_12x12 => {
-                // Search for character in TFONT12
-                for font in font::TFONT12.iter() {
-                    if font.index == ch {
-                        for i in 0..typeface_num {
-                            for j in 0..8 {
-                                match mode {
-                                    CharMode::NonOverlay => {
-                                        if i < font.msk.len() && font.msk[i] & (0x01 << j) != 0 {
-                                            self.write_data(&[fc]).await;
-                                        } else {
-                                            self.write_data(&[bc]).await;
-                                        }
-
-                                        m += 1;
-                                        if m % size_y == 0 {
-                                            m = 0;
-                                            break;
-                                        }
-                                    }
-                                    CharMode::Overlay => {
-                                        if i < font.msk.len() && font.msk[i] & (0x01 << j) != 0 {
-                                            self.draw_point(x, y, fc).await;
-                                        }
-
-                                        x += 1;
-                                        if (x - x0) == size_y as u16 {
-                                            x = x0;
-                                            y += 1;
-                                            break;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        return; // Found the character, exit the function
+        match mode {
+            CharMode::NonOverlay => {
+                self.set_address(x, y, x + width.as_u16() - 1, y + rows.as_u16() - 1)
+                    .await;
+                for j in 0..rows {
+                    for (i, pixel) in row[..width].iter_mut().enumerate() {
+                        *pixel = color(font.alpha(glyph, i as u8, j));
                     }
+                    self.write_data(&row[..width]).await;
                 }
             }
-            ChineseFontSize::_16x16 => {
-                // Search for character in TFONT16
-                for font in font::TFONT16.iter() {
-                    if font.index == ch {
-                        for i in 0..typeface_num {
-                            for j in 0..8 {
-                                match mode {
-                                    CharMode::NonOverlay => {
-                                        if i < font.msk.len() && font.msk[i] & (0x01 << j) != 0 {
-                                            self.write_data(&[fc]).await;
-                                        } else {
-                                            self.write_data(&[bc]).await;
-                                        }
-
-                                        m += 1;
-                                        if m % size_y == 0 {
-                                            m = 0;
-                                            break;
-                                        }
-                                    }
-                                    CharMode::Overlay => {
-                                        if i < font.msk.len() && font.msk[i] & (0x01 << j) != 0 {
-                                            self.draw_point(x, y, fc).await;
-                                        }
-
-                                        x += 1;
-                                        if (x - x0) == size_y as u16 {
-                                            x = x0;
-                                            y += 1;
-                                            break;
-                                        }
-                                    }
-                                }
-                            }
+            CharMode::Overlay => {
+                // One address window per run of visible pixels
+                for j in 0..rows {
+                    let y = y + j.as_u16();
+                    let mut i = 0;
+                    while i < width {
+                        if font.alpha(glyph, i as u8, j) == 0 {
+                            i += 1;
+                            continue;
                         }
-                        return; // Found the character, exit the function
-                    }
-                }
-            }
-            ChineseFontSize::_24x24 => {
-                // Search for character in TFONT24
-                for font in font::TFONT24.iter() {
-                    if font.index == ch {
-                        for i in 0..typeface_num {
-                            for j in 0..8 {
-                                match mode {
-                                    CharMode::NonOverlay => {
-                                        if i < font.msk.len() && font.msk[i] & (0x01 << j) != 0 {
-                                            self.write_data(&[fc]).await;
-                                        } else {
-                                            self.write_data(&[bc]).await;
-                                        }
-
-                                        m += 1;
-                                        if m % size_y == 0 {
-                                            m = 0;
-                                            break;
-                                        }
-                                    }
-                                    CharMode::Overlay => {
-                                        if i < font.msk.len() && font.msk[i] & (0x01 << j) != 0 {
-                                            self.draw_point(x, y, fc).await;
-                                        }
-
-                                        x += 1;
-                                        if (x - x0) == size_y as u16 {
-                                            x = x0;
-                                            y += 1;
-                                            break;
-                                        }
-                                    }
-                                }
-                            }
+                        let start = i;
+                        while i < width && font.alpha(glyph, i as u8, j) != 0 {
+                            row[i] = color(font.alpha(glyph, i as u8, j));
+                            i += 1;
                         }
-                        return; // Found the character, exit the function
-                    }
-                }
-            }
-            ChineseFontSize::_32x32 => {
-                // Search for character in TFONT32
-                for font in font::TFONT32.iter() {
-                    if font.index == ch {
-                        for i in 0..typeface_num {
-                            for j in 0..8 {
-                                match mode {
-                                    CharMode::NonOverlay => {
-                                        if i < font.msk.len() && font.msk[i] & (0x01 << j) != 0 {
-                                            self.write_data(&[fc]).await;
-                                        } else {
-                                            self.write_data(&[bc]).await;
-                                        }
-
-                                        m += 1;
-                                        if m % size_y == 0 {
-                                            m = 0;
-                                            break;
-                                        }
-                                    }
-                                    CharMode::Overlay => {
-                                        if i < font.msk.len() && font.msk[i] & (0x01 << j) != 0 {
-                                            self.draw_point(x, y, fc).await;
-                                        }
-
-                                        x += 1;
-                                        if (x - x0) == size_y as u16 {
-                                            x = x0;
-                                            y += 1;
-                                            break;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        return; // Found the character, exit the function
+                        self.set_address(x + start.as_u16(), y, x + i.as_u16() - 1, y)
+                            .await;
+                        self.write_data(&row[start..i]).await;
                     }
                 }
             }
         }
     }
 
+    pub async fn show_alpha_string(
+        &mut self, (mut x, y): (u16, u16), s: &str, fc: u16, bc: u16, font: &AlphaFont,
+        mode: CharMode,
+    ) {
+        for ch in s.chars() {
+            self.show_alpha_char((x, y), ch, fc, bc, font, mode).await;
+            x += font.width.as_u16();
+        }
+    }
+
     pub async fn show_picture(
         &mut self, (x, y): (u16, u16), (length, width): (u16, u16), pic: &[u8],
     ) {
@@ -667,7 +620,84 @@ impl<C: timer::GeneralInstance4Channel> LCD<C> {
         }
     }
 
+    /// Draws a `w x h` picture stored in the external flash at `addr`, in the
+    /// same big endian RGB565 layout as [`LCD::show_picture`], clipped to the
+    /// panel.
+    ///
+    /// The picture is streamed through two buffers: while one is being sent
+    /// to the panel, the next chunk is read from the flash into the other.
+    pub async fn show_picture_from_flash(
+        &mut self, (x, y): (u16, u16), flash: &mut W25Qxx, addr: u32, (w, h): (u16, u16),
+    ) {
+        if x >= WIDTH || y >= HEIGHT || w == 0 || h == 0 {
+            return;
+        }
+        let visible_w = w.min(WIDTH - x);
+        let visible_h = h.min(HEIGHT - y);
+        self.set_address(x, y, x + visible_w - 1, y + visible_h - 1)
+            .await;
+
+        let stride = u32::from(w) * 2;
+        if visible_w == w {
+            // Rows are contiguous in flash, stream them in one go
+            self.stream_from_flash(flash, addr, stride * u32::from(visible_h))
+                .await;
+        } else {
+            for j in 0..u32::from(visible_h) {
+                self.stream_from_flash(flash, addr + j * stride, u32::from(visible_w) * 2)
+                    .await;
+            }
+        }
+    }
+
+    /// Sends `len` bytes of pixel data starting at `addr` in the flash,
+    /// overlapping the flash reads with the panel writes.
+    async fn stream_from_flash(&mut self, flash: &mut W25Qxx, mut addr: u32, len: u32) {
+        let mut a = [0u8; FLASH_CHUNK_SIZE];
+        let mut b = [0u8; FLASH_CHUNK_SIZE];
+        let (mut ready, mut spare) = (&mut a, &mut b);
+
+        let mut remaining = len.as_usize();
+        let mut pending = remaining.min(FLASH_CHUNK_SIZE);
+        flash.buffer_read(&mut ready[..pending], addr).await;
+
+        while pending > 0 {
+            addr += pending.as_u32();
+            remaining -= pending;
+            let next = remaining.min(FLASH_CHUNK_SIZE);
+
+            let read = async {
+                if next > 0 {
+                    flash.buffer_read(&mut spare[..next], addr).await;
+                }
+            };
+            join(self.write_data8(&ready[..pending]), read).await;
+
+            core::mem::swap(&mut ready, &mut spare);
+            pending = next;
+        }
+    }
+
     pub fn set_brightness(&mut self, percent: u8) {
+        self.brightness = percent;
         self.blk.set_duty_cycle_percent(percent);
     }
+
+    /// Backlight percent last set with [`LCD::set_brightness`].
+    pub fn brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    /// Splits the frame memory into a fixed top area, a scrolling area and a
+    /// fixed bottom area, in lines. The three must add up to [`FRAME_LINES`].
+    pub async fn set_scroll_area(&mut self, top_fixed: u16, scroll: u16, bottom_fixed: u16) {
+        self.write_reg(&[0x33]).await;
+        self.write_data(&[top_fixed, scroll, bottom_fixed]).await;
+    }
+
+    /// Shows frame memory line `line` at the top of the scrolling area.
+    pub async fn set_scroll_start(&mut self, line: u16) {
+        self.write_reg(&[0x37]).await;
+        self.write_data(&[line]).await;
+    }
 }
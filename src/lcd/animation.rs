@@ -0,0 +1,257 @@
+//! Frame animations made of delta rectangles.
+//!
+//! Produced by `boe-tools anim` from a sequence of PNG frames. Each frame
+//! only carries the rectangles that changed since the previous one, the first
+//! frame covers the whole animation. Layout, all integers little endian:
+//!
+//! | Offset | Size | Content                  |
+//! |--------|------|--------------------------|
+//! | 0      | 4    | magic `RANM`             |
+//! | 4      | 2    | width                    |
+//! | 6      | 2    | height                   |
+//! | 8      | 2    | number of frames         |
+//! | 10     | ..   | frames                   |
+//!
+//! A frame is its delay in milliseconds and its number of rectangles, both
+//! `u16`, followed by the rectangles. A rectangle is `x`, `y`, `w` and `h` as
+//! `u16`, relative to the animation, followed by `w * h` RGB565 colors row by
+//! row.
+//!
+//! Animations are read through [`AnimationSource`], either straight out of
+//! MCU flash or from the external flash, one row at a time.
+
+use super::{HEIGHT, LCD, Rect, WIDTH};
+use crate::{asset::AssetEntry, w25qxx::W25Qxx};
+use as_what::{AsU16, AsU32, AsUsize};
+use embassy_stm32::timer;
+use embassy_time::{Duration, Instant, Timer};
+
+pub const ANIMATION_MAGIC: &[u8; 4] = b"RANM";
+pub const ANIMATION_HEADER_SIZE: u32 = 10;
+
+const FRAME_HEADER_SIZE: u32 = 4;
+const RECT_HEADER_SIZE: u32 = 8;
+
+/// Longest row [`Animator`] reads at once.
+const MAX_ROW: usize = if WIDTH > HEIGHT { WIDTH } else { HEIGHT } as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum AnimationError {
+    /// Missing `RANM` magic
+    NotAnimation,
+    /// A frame is cut off or a rectangle lies outside the animation
+    Corrupt,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum Playback {
+    /// Stop after the last frame
+    Once,
+    /// Start over after the last frame
+    Loop,
+}
+
+/// Where the animation data lives.
+pub enum AnimationSource<'a> {
+    Memory(&'a [u8]),
+    Flash {
+        flash: &'a mut W25Qxx,
+        addr: u32,
+        len: u32,
+    },
+}
+
+impl<'a> AnimationSource<'a> {
+    /// The data of an asset in the external flash.
+    pub fn asset(flash: &'a mut W25Qxx, entry: &AssetEntry) -> Self {
+        Self::Flash {
+            flash,
+            addr: entry.addr,
+            len: entry.len,
+        }
+    }
+
+    fn len(&self) -> u32 {
+        match self {
+            Self::Memory(data) => data.len().as_u32(),
+            Self::Flash { len, .. } => *len,
+        }
+    }
+
+    async fn read(&mut self, offset: u32, buf: &mut [u8]) -> Result<(), AnimationError> {
+        let end = offset.checked_add(buf.len().as_u32());
+        if end.is_none_or(|end| end > self.len()) {
+            return Err(AnimationError::Corrupt);
+        }
+        match self {
+            Self::Memory(data) => {
+                let start = offset.as_usize();
+                buf.copy_from_slice(&data[start..start + buf.len()]);
+            }
+            Self::Flash { flash, addr, .. } => flash.buffer_read(buf, *addr + offset).await,
+        }
+        Ok(())
+    }
+
+    async fn read_u16s<const N: usize>(&mut self, offset: u32) -> Result<[u16; N], AnimationError> {
+        let mut bytes = [0u8; 8];
+        self.read(offset, &mut bytes[..N * 2]).await?;
+        Ok(core::array::from_fn(|i| {
+            u16::from_le_bytes([bytes[i * 2], bytes[i * 2 + 1]])
+        }))
+    }
+}
+
+/// Plays an animation on the panel with its top left corner at a fixed
+/// position.
+///
+/// Either drive it frame by frame with [`Animator::step`], or let
+/// [`Animator::play`] take care of the timing.
+pub struct Animator<'a> {
+    source: AnimationSource<'a>,
+    width: u16,
+    height: u16,
+    frames: u16,
+    position: (u16, u16),
+    playback: Playback,
+    /// Index and data offset of the next frame
+    frame: u16,
+    offset: u32,
+}
+
+impl<'a> Animator<'a> {
+    pub async fn new(
+        mut source: AnimationSource<'a>, position: (u16, u16), playback: Playback,
+    ) -> Result<Self, AnimationError> {
+        let mut magic = [0u8; 4];
+        source
+            .read(0, &mut magic)
+            .await
+            .map_err(|_| AnimationError::NotAnimation)?;
+        if &magic != ANIMATION_MAGIC {
+            return Err(AnimationError::NotAnimation);
+        }
+        let [width, height, frames] = source.read_u16s(4).await?;
+
+        Ok(Self {
+            source,
+            width,
+            height,
+            frames,
+            position,
+            playback,
+            frame: 0,
+            offset: ANIMATION_HEADER_SIZE,
+        })
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn frames(&self) -> u16 {
+        self.frames
+    }
+
+    /// Whether a one-shot animation has shown its last frame.
+    pub fn is_done(&self) -> bool {
+        self.frame >= self.frames
+    }
+
+    /// Goes back to the first frame, which redraws the whole animation.
+    pub fn rewind(&mut self) {
+        self.frame = 0;
+        self.offset = ANIMATION_HEADER_SIZE;
+    }
+
+    /// Draws the next frame and returns how long it should stay on screen,
+    /// or `None` once a one-shot animation is done.
+    pub async fn step<C: timer::GeneralInstance4Channel>(
+        &mut self, lcd: &mut LCD<C>,
+    ) -> Result<Option<Duration>, AnimationError> {
+        if self.is_done() {
+            match self.playback {
+                Playback::Once => return Ok(None),
+                Playback::Loop => self.rewind(),
+            }
+        }
+        if self.frames == 0 {
+            return Ok(None);
+        }
+
+        let [delay, rects] = self.source.read_u16s(self.offset).await?;
+        let mut offset = self
+            .offset
+            .checked_add(FRAME_HEADER_SIZE)
+            .ok_or(AnimationError::Corrupt)?;
+        for _ in 0..rects {
+            let [x, y, w, h] = self.source.read_u16s(offset).await?;
+            offset = offset
+                .checked_add(RECT_HEADER_SIZE)
+                .ok_or(AnimationError::Corrupt)?;
+            if u32::from(x) + u32::from(w) > u32::from(self.width)
+                || u32::from(y) + u32::from(h) > u32::from(self.height)
+            {
+                return Err(AnimationError::Corrupt);
+            }
+            self.draw_rect(lcd, Rect::new(x, y, w, h), offset).await?;
+            offset = (u32::from(w) * u32::from(h))
+                .checked_mul(2)
+                .and_then(|size| offset.checked_add(size))
+                .ok_or(AnimationError::Corrupt)?;
+        }
+
+        self.frame += 1;
+        self.offset = offset;
+        Ok(Some(Duration::from_millis(delay.into())))
+    }
+
+    /// Plays the animation, returning after the last frame of a one-shot
+    /// animation and never for a looping one.
+    pub async fn play<C: timer::GeneralInstance4Channel>(
+        &mut self, lcd: &mut LCD<C>,
+    ) -> Result<(), AnimationError> {
+        loop {
+            let start = Instant::now();
+            match self.step(lcd).await? {
+                // Drawing time counts towards the delay
+                Some(delay) => Timer::at(start + delay).await,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Draws `rect` of the animation, with its pixels at `offset`, clipped to
+    /// the panel.
+    async fn draw_rect<C: timer::GeneralInstance4Channel>(
+        &mut self, lcd: &mut LCD<C>, rect: Rect, offset: u32,
+    ) -> Result<(), AnimationError> {
+        let x = self.position.0.saturating_add(rect.x);
+        let y = self.position.1.saturating_add(rect.y);
+        if rect.is_empty() || x >= WIDTH || y >= HEIGHT {
+            return Ok(());
+        }
+        let w = rect.w.min(WIDTH - x).as_usize().min(MAX_ROW);
+        let h = rect.h.min(HEIGHT - y);
+        lcd.set_address(x, y, x + w.as_u16() - 1, y + h - 1).await;
+
+        let stride = u32::from(rect.w) * 2;
+        let mut bytes = [0u8; MAX_ROW * 2];
+        let mut row = [0u16; MAX_ROW];
+        for j in 0..u32::from(h) {
+            self.source
+                .read(offset + j * stride, &mut bytes[..w * 2])
+                .await?;
+            for (pixel, color) in row[..w].iter_mut().zip(bytes.chunks_exact(2)) {
+                *pixel = u16::from_le_bytes([color[0], color[1]]);
+            }
+            lcd.write_data(&row[..w]).await;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,363 @@
+//! Code 128 and EAN-13 barcodes.
+//!
+//! [`Barcode`] holds the encoded bars as one bit per module, quiet zones
+//! included. [`LCD::draw_barcode`] scales them by an integer factor to fit a
+//! target width; since every pixel row of the bars is the same, one row is
+//! built and sent for the whole bar height through a single address window.
+
+use super::{HEIGHT, LCD, WIDTH, font::FontSize, style::GlyphStyle};
+use as_what::{AsU16, AsUsize};
+use embassy_stm32::timer;
+
+/// Longest data [`Barcode::code128`] accepts.
+pub const CODE128_MAX_LEN: usize = 32;
+
+/// Modules of the longest Code 128 symbol: start, data with a code set
+/// switch every two characters at worst, checksum and stop.
+const MAX_MODULES: usize =
+    CODE128_QUIET * 2 + (2 + CODE128_MAX_LEN + CODE128_MAX_LEN / 2) * 11 + 13;
+
+const CODE128_QUIET: usize = 10;
+const EAN13_QUIET_LEFT: usize = 11;
+const EAN13_QUIET_RIGHT: usize = 7;
+
+const CODE128_START_B: u8 = 104;
+const CODE128_START_C: u8 = 105;
+const CODE128_CODE_B: u8 = 100;
+const CODE128_CODE_C: u8 = 99;
+const CODE128_STOP: u8 = 106;
+
+/// Bar and space widths of every Code 128 symbol, one hex digit each. The
+/// stop symbol has a seventh element, the final bar of width 2.
+const CODE128_PATTERNS: [u32; 107] = [
+    0x212222, 0x222122, 0x222221, 0x121223, 0x121322, 0x131222, 0x122213, 0x122312, 0x132212,
+    0x221213, 0x221312, 0x231212, 0x112232, 0x122132, 0x122231, 0x113222, 0x123122, 0x123221,
+    0x223211, 0x221132, 0x221231, 0x213212, 0x223112, 0x312131, 0x311222, 0x321122, 0x321221,
+    0x312212, 0x322112, 0x322211, 0x212123, 0x212321, 0x232121, 0x111323, 0x131123, 0x131321,
+    0x112313, 0x132113, 0x132311, 0x211313, 0x231113, 0x231311, 0x112133, 0x112331, 0x132131,
+    0x113123, 0x113321, 0x133121, 0x313121, 0x211331, 0x231131, 0x213113, 0x213311, 0x213131,
+    0x311123, 0x311321, 0x331121, 0x312113, 0x312311, 0x332111, 0x314111, 0x221411, 0x431111,
+    0x111224, 0x111422, 0x121124, 0x121421, 0x141122, 0x141221, 0x112214, 0x112412, 0x122114,
+    0x122411, 0x142112, 0x142211, 0x241211, 0x221114, 0x413111, 0x241112, 0x134111, 0x111242,
+    0x121142, 0x121241, 0x114212, 0x124112, 0x124211, 0x411212, 0x421112, 0x421211, 0x212141,
+    0x214121, 0x412121, 0x111143, 0x111341, 0x131141, 0x114113, 0x114311, 0x411113, 0x411311,
+    0x113141, 0x114131, 0x311141, 0x411131, 0x211412, 0x211214, 0x211232, 0x233111,
+];
+
+/// EAN-13 left hand digits with odd parity, 7 modules each, most
+/// significant bit first. Even parity is the reverse of the right hand
+/// pattern, which is the complement of the odd one.
+const EAN_L: [u8; 10] = [0x0D, 0x19, 0x13, 0x3D, 0x23, 0x31, 0x2F, 0x3B, 0x37, 0x0B];
+
+/// Parity of digits 2 to 7, set bits meaning even, selected by the first
+/// digit.
+const EAN_PARITY: [u8; 10] = [0x00, 0x0B, 0x0D, 0x0E, 0x13, 0x19, 0x1C, 0x15, 0x16, 0x1A];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum BarcodeError {
+    /// A character the symbology cannot encode, or the wrong number of
+    /// digits for EAN-13
+    InvalidData,
+    /// The EAN-13 check digit does not match
+    InvalidChecksum,
+    /// More than [`CODE128_MAX_LEN`] characters
+    TooLong,
+    /// The bars do not fit into the target width at one pixel per module
+    TooWide,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum Symbology {
+    Code128,
+    Ean13,
+}
+
+pub struct Barcode {
+    symbology: Symbology,
+    bars: [u8; MAX_MODULES.div_ceil(8)],
+    modules: u16,
+    text: [u8; CODE128_MAX_LEN],
+    text_len: usize,
+}
+
+impl Barcode {
+    fn new(symbology: Symbology, text: &[u8]) -> Self {
+        let mut barcode = Self {
+            symbology,
+            bars: [0; MAX_MODULES.div_ceil(8)],
+            modules: 0,
+            text: [0; CODE128_MAX_LEN],
+            text_len: text.len(),
+        };
+        barcode.text[..text.len()].copy_from_slice(text);
+        barcode
+    }
+
+    /// Encodes printable ASCII with code set B, switching to code set C for
+    /// runs of four or more digits.
+    pub fn code128(data: &str) -> Result<Self, BarcodeError> {
+        let bytes = data.as_bytes();
+        if bytes.len() > CODE128_MAX_LEN {
+            return Err(BarcodeError::TooLong);
+        }
+        if !bytes.iter().all(|b| (b' '..=b'~').contains(b)) {
+            return Err(BarcodeError::InvalidData);
+        }
+
+        let mut barcode = Self::new(Symbology::Code128, bytes);
+        barcode.space(CODE128_QUIET);
+
+        let digit_run = |i: usize| bytes[i..].iter().take_while(|b| b.is_ascii_digit()).count();
+        let mut code_c = digit_run(0) >= 4;
+        let start = if code_c {
+            CODE128_START_C
+        } else {
+            CODE128_START_B
+        };
+        barcode.code128_symbol(start);
+        let mut checksum = u32::from(start);
+        let mut weight = 1;
+        let mut emit = |barcode: &mut Self, value: u8| {
+            barcode.code128_symbol(value);
+            checksum += u32::from(value) * weight;
+            weight += 1;
+        };
+
+        let mut i = 0;
+        while i < bytes.len() {
+            let run = digit_run(i);
+            if code_c && run >= 2 {
+                emit(&mut barcode, (bytes[i] - b'0') * 10 + bytes[i + 1] - b'0');
+                i += 2;
+            } else if !code_c && run >= 4 && run % 2 == 0 {
+                emit(&mut barcode, CODE128_CODE_C);
+                code_c = true;
+            } else if code_c {
+                emit(&mut barcode, CODE128_CODE_B);
+                code_c = false;
+            } else {
+                // An odd run of digits leaves its first one in code set B
+                emit(&mut barcode, bytes[i] - b' ');
+                i += 1;
+            }
+        }
+
+        barcode.code128_symbol((checksum % 103) as u8);
+        barcode.code128_symbol(CODE128_STOP);
+        barcode.bar(2);
+        barcode.space(CODE128_QUIET);
+        Ok(barcode)
+    }
+
+    /// Encodes 12 digits and appends the check digit, or checks the last of
+    /// 13 digits.
+    pub fn ean13(digits: &str) -> Result<Self, BarcodeError> {
+        let bytes = digits.as_bytes();
+        if !matches!(bytes.len(), 12 | 13) || !bytes.iter().all(u8::is_ascii_digit) {
+            return Err(BarcodeError::InvalidData);
+        }
+        let mut d = [0u8; 13];
+        for (d, b) in d.iter_mut().zip(bytes) {
+            *d = b - b'0';
+        }
+        let sum: u32 = d[..12]
+            .iter()
+            .enumerate()
+            .map(|(i, &d)| u32::from(d) * if i % 2 == 0 { 1 } else { 3 })
+            .sum();
+        let check = ((10 - sum % 10) % 10) as u8;
+        if bytes.len() == 13 && d[12] != check {
+            return Err(BarcodeError::InvalidChecksum);
+        }
+        d[12] = check;
+
+        let mut text = [0u8; 13];
+        for (t, d) in text.iter_mut().zip(d) {
+            *t = b'0' + d;
+        }
+        let mut barcode = Self::new(Symbology::Ean13, &text);
+        barcode.space(EAN13_QUIET_LEFT);
+        barcode.pattern(0b101, 3);
+        for (i, &digit) in d[1..7].iter().enumerate() {
+            let even = EAN_PARITY[d[0].as_usize()] & (0x20 >> i) != 0;
+            let l = EAN_L[digit.as_usize()];
+            let code = if even {
+                (!l & 0x7F).reverse_bits() >> 1
+            } else {
+                l
+            };
+            barcode.pattern(code, 7);
+        }
+        barcode.pattern(0b01010, 5);
+        for &digit in &d[7..] {
+            barcode.pattern(!EAN_L[digit.as_usize()] & 0x7F, 7);
+        }
+        barcode.pattern(0b101, 3);
+        barcode.space(EAN13_QUIET_RIGHT);
+        Ok(barcode)
+    }
+
+    pub fn symbology(&self) -> Symbology {
+        self.symbology
+    }
+
+    /// Width in modules, quiet zones included.
+    pub fn modules(&self) -> u16 {
+        self.modules
+    }
+
+    pub fn is_bar(&self, module: u16) -> bool {
+        let m = module.as_usize();
+        module < self.modules && self.bars[m / 8] & (0x80 >> (m % 8)) != 0
+    }
+
+    /// Human readable text, including the EAN-13 check digit.
+    pub fn text(&self) -> &str {
+        // Only ASCII was accepted
+        core::str::from_utf8(&self.text[..self.text_len]).unwrap_or_default()
+    }
+
+    /// Whether `module` belongs to the EAN-13 guard bars, which extend into
+    /// the text below.
+    fn is_guard(&self, module: u16) -> bool {
+        if self.symbology != Symbology::Ean13 {
+            return false;
+        }
+        let m = module.as_usize().wrapping_sub(EAN13_QUIET_LEFT);
+        m < 3 || (45..50).contains(&m) || (92..95).contains(&m)
+    }
+
+    fn code128_symbol(&mut self, value: u8) {
+        let widths = CODE128_PATTERNS[value.as_usize()];
+        for i in 0..6 {
+            let width = (widths >> (20 - i * 4) & 0xF).as_usize();
+            if i % 2 == 0 {
+                self.bar(width);
+            } else {
+                self.space(width);
+            }
+        }
+    }
+
+    /// Appends `len` modules of `bits`, most significant first.
+    fn pattern(&mut self, bits: u8, len: usize) {
+        for i in (0..len).rev() {
+            if bits & (1 << i) != 0 {
+                self.bar(1);
+            } else {
+                self.space(1);
+            }
+        }
+    }
+
+    fn bar(&mut self, width: usize) {
+        for _ in 0..width {
+            let m = self.modules.as_usize();
+            self.bars[m / 8] |= 0x80 >> (m % 8);
+            self.modules += 1;
+        }
+    }
+
+    fn space(&mut self, width: usize) {
+        self.modules += width.as_u16();
+    }
+}
+
+impl<C: timer::GeneralInstance4Channel> LCD<C> {
+    /// Draws `barcode` into the `width x height` area at `(x, y)`, scaled by
+    /// the largest integer factor that fits and centered horizontally.
+    ///
+    /// With `text` set, the human readable digits or characters are drawn in
+    /// that font at the bottom of the area. Returns the pixels per module.
+    pub async fn draw_barcode(
+        &mut self, (x, y): (u16, u16), barcode: &Barcode, (width, height): (u16, u16),
+        text: Option<FontSize>, fc: u16, bc: u16,
+    ) -> Result<u16, BarcodeError> {
+        let module_px = width / barcode.modules();
+        if module_px == 0 {
+            return Err(BarcodeError::TooWide);
+        }
+        let offset = (width - barcode.modules() * module_px) / 2;
+        let text_h = text.map_or(0, |size| size.y().as_u16()).min(height);
+        let bars_h = height - text_h;
+        // EAN-13 guard bars reach halfway into the text
+        let guard_h = match barcode.symbology {
+            Symbology::Ean13 => text_h / 2,
+            Symbology::Code128 => 0,
+        };
+
+        let mut row = [0u16; WIDTH as usize];
+        let build = |row: &mut [u16], bar: &dyn Fn(u16) -> bool| {
+            for (i, pixel) in row.iter_mut().enumerate() {
+                let px = i.as_u16();
+                let module = px.wrapping_sub(offset) / module_px;
+                *pixel = if px >= offset && bar(module) { fc } else { bc };
+            }
+        };
+
+        if x >= WIDTH || y >= HEIGHT {
+            return Ok(module_px);
+        }
+        let visible_w = width.min(WIDTH - x).as_usize();
+        let row = &mut row[..visible_w];
+
+        build(row, &|m| barcode.is_bar(m));
+        self.write_rows((x, y), row, bars_h).await;
+        build(row, &|m| barcode.is_bar(m) && barcode.is_guard(m));
+        self.write_rows((x, y + bars_h), row, guard_h).await;
+        row.fill(bc);
+        self.write_rows((x, y + bars_h + guard_h), row, text_h - guard_h)
+            .await;
+
+        if let Some(size) = text {
+            let text_y = y + bars_h;
+            let char_w = size.x().as_u16();
+            let module_x = |module: usize| x + offset + module.as_u16() * module_px;
+            let label = barcode.text();
+            match barcode.symbology {
+                Symbology::Code128 => {
+                    let w = label.len().as_u16() * char_w;
+                    let text_x = (x + width / 2).saturating_sub(w / 2);
+                    self.show_string((text_x, text_y), label, size, &GlyphStyle::new(fc, bc))
+                        .await;
+                }
+                Symbology::Ean13 => {
+                    // First digit in the quiet zone, then one group under
+                    // each half between the guards
+                    let first_x = module_x(EAN13_QUIET_LEFT).saturating_sub(char_w + module_px);
+                    let groups = [
+                        (first_x, &label[..1]),
+                        (module_x(EAN13_QUIET_LEFT + 3 + 21), &label[1..7]),
+                        (module_x(EAN13_QUIET_LEFT + 50 + 21), &label[7..]),
+                    ];
+                    for (i, (center, digits)) in groups.into_iter().enumerate() {
+                        let w = digits.len().as_u16() * char_w;
+                        let text_x = if i == 0 {
+                            center
+                        } else {
+                            center.saturating_sub(w / 2)
+                        };
+                        self.show_string((text_x, text_y), digits, size, &GlyphStyle::new(fc, bc))
+                            .await;
+                    }
+                }
+            }
+        }
+
+        Ok(module_px)
+    }
+
+    /// Writes the same row of pixels `rows` times below `(x, y)`, clipped to
+    /// the panel.
+    async fn write_rows(&mut self, (x, y): (u16, u16), row: &[u16], rows: u16) {
+        if rows == 0 || row.is_empty() || y >= HEIGHT {
+            return;
+        }
+        let rows = rows.min(HEIGHT - y);
+        self.set_address(x, y, x + row.len().as_u16() - 1, y + rows - 1)
+            .await;
+        for _ in 0..rows {
+            self.write_data(row).await;
+        }
+    }
+}
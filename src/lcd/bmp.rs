@@ -0,0 +1,261 @@
+//! Streaming BMP decoder.
+//!
+//! Supported are uncompressed 1, 4 and 8-bit palettized, 16-bit (X1R5G5B5,
+//! or R5G6B5 through `BI_BITFIELDS`), 24-bit and 32-bit images, stored either
+//! bottom-up or top-down. Pixels are converted to RGB565 one row at a time,
+//! straight out of the file data. Files that are not in memory as a whole
+//! are opened from their first bytes with [`Bmp::parse_header`], and spans
+//! of rows converted with [`Bmp::convert`] as they are read.
+
+use super::{HEIGHT, LCD, WIDTH, rgb565};
+use as_what::{AsU16, AsUsize};
+use embassy_stm32::timer;
+
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+
+const FILE_HEADER_SIZE: usize = 14;
+
+/// Bytes up to the end of the largest header and palette supported, a V5
+/// header followed by 256 colors.
+pub const BMP_HEADER_MAX: usize = FILE_HEADER_SIZE + 124 + 256 * 4;
+
+/// Longest row [`LCD::show_bmp`] converts at once.
+const MAX_ROW: usize = if WIDTH > HEIGHT { WIDTH } else { HEIGHT } as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum BmpError {
+    /// Missing `BM` signature
+    NotBmp,
+    /// Compression, header version or bit depth is not supported
+    Unsupported,
+    /// The data ends before the pixel array does
+    Truncated,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Palette(u8),
+    Rgb555,
+    Rgb565,
+    Bgr888,
+    Bgrx8888,
+}
+
+impl Format {
+    fn bits(self) -> usize {
+        match self {
+            Format::Palette(bits) => bits.as_usize(),
+            Format::Rgb555 | Format::Rgb565 => 16,
+            Format::Bgr888 => 24,
+            Format::Bgrx8888 => 32,
+        }
+    }
+}
+
+pub struct Bmp<'a> {
+    data: &'a [u8],
+    width: u16,
+    height: u16,
+    top_down: bool,
+    format: Format,
+    pixels: usize,
+    stride: usize,
+    palette: &'a [u8],
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16, BmpError> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or(BmpError::Truncated)
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32, BmpError> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or(BmpError::Truncated)
+}
+
+impl<'a> Bmp<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Self, BmpError> {
+        Self::parse_header(data, data.len())
+    }
+
+    /// Parses a file of `len` bytes from its first bytes, which have to
+    /// reach to the end of the palette. Rows are not read, so
+    /// [`Bmp::read_row`] leaves its output alone.
+    pub fn parse_header(data: &'a [u8], len: usize) -> Result<Self, BmpError> {
+        if !data.starts_with(b"BM") {
+            return Err(BmpError::NotBmp);
+        }
+        let pixels = u32_at(data, 10)?.as_usize();
+        let header_size = u32_at(data, FILE_HEADER_SIZE)?.as_usize();
+        if header_size < 40 {
+            // OS/2 BITMAPCOREHEADER
+            return Err(BmpError::Unsupported);
+        }
+
+        let width = u32_at(data, 18)? as i32;
+        let height = u32_at(data, 22)? as i32;
+        let bpp = u16_at(data, 28)?;
+        let compression = u32_at(data, 30)?;
+        if width <= 0 || height == 0 || width > u16::MAX as i32 || height.abs() > u16::MAX as i32 {
+            return Err(BmpError::Unsupported);
+        }
+
+        let format = match (bpp, compression) {
+            (1 | 4 | 8, BI_RGB) => Format::Palette(bpp as u8),
+            (16, BI_RGB) => Format::Rgb555,
+            (16, BI_BITFIELDS) => {
+                // Masks follow the 40 byte header, or are part of V4/V5 ones
+                let masks = FILE_HEADER_SIZE + 40;
+                match (
+                    u32_at(data, masks)?,
+                    u32_at(data, masks + 4)?,
+                    u32_at(data, masks + 8)?,
+                ) {
+                    (0xF800, 0x07E0, 0x001F) => Format::Rgb565,
+                    (0x7C00, 0x03E0, 0x001F) => Format::Rgb555,
+                    _ => return Err(BmpError::Unsupported),
+                }
+            }
+            (24, BI_RGB) => Format::Bgr888,
+            (32, BI_RGB) => Format::Bgrx8888,
+            _ => return Err(BmpError::Unsupported),
+        };
+
+        let palette = match format {
+            Format::Palette(bits) => {
+                let used = u32_at(data, 46)?.as_usize();
+                let colors = match used {
+                    0 => 1 << bits,
+                    used if used > 1 << bits => return Err(BmpError::Unsupported),
+                    used => used,
+                };
+                let start = FILE_HEADER_SIZE
+                    .checked_add(header_size)
+                    .ok_or(BmpError::Truncated)?;
+                let end = colors
+                    .checked_mul(4)
+                    .and_then(|size| size.checked_add(start))
+                    .ok_or(BmpError::Truncated)?;
+                data.get(start..end).ok_or(BmpError::Truncated)?
+            }
+            _ => &[],
+        };
+
+        let stride = (format.bits() * width as usize).div_ceil(32) * 4;
+        let height_abs = height.unsigned_abs().as_usize();
+        let end = stride
+            .checked_mul(height_abs)
+            .and_then(|size| size.checked_add(pixels))
+            .ok_or(BmpError::Truncated)?;
+        if len < end {
+            return Err(BmpError::Truncated);
+        }
+
+        Ok(Self {
+            data,
+            width: width as u16,
+            height: height_abs as u16,
+            top_down: height < 0,
+            format,
+            pixels,
+            stride,
+            palette,
+        })
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Converts the first `out.len()` pixels of row `y`, counted from the
+    /// top, to RGB565.
+    pub fn read_row(&self, y: u16, out: &mut [u16]) {
+        let start = self.pixel_offset(0, y);
+        let n = out.len().min(self.width.as_usize());
+        if let Some(src) = self.data.get(start..start + self.stride) {
+            self.convert(0, src, &mut out[..n]);
+        }
+    }
+
+    /// Offset in the file of the byte holding pixel `x` of row `y`, counted
+    /// from the top.
+    pub fn pixel_offset(&self, x: u16, y: u16) -> usize {
+        let row = if self.top_down {
+            y.as_usize()
+        } else {
+            (self.height - 1 - y).as_usize()
+        };
+        self.pixels + row * self.stride + x.as_usize() * self.format.bits() / 8
+    }
+
+    /// Bytes from [`Bmp::pixel_offset`] of pixel `x` to the end of pixel
+    /// `x + n - 1`.
+    pub fn span_len(&self, x: u16, n: usize) -> usize {
+        let bits = self.format.bits();
+        (x.as_usize() * bits % 8 + n * bits).div_ceil(8)
+    }
+
+    /// Converts the pixels of a row from pixel `x` on to RGB565, filling
+    /// `out` as far as `src` reaches. `src` starts at [`Bmp::pixel_offset`]
+    /// of pixel `x`.
+    pub fn convert(&self, x: u16, src: &[u8], out: &mut [u16]) {
+        let bits = self.format.bits();
+        // Bit of pixel `x` in its byte, for palettes of less than 8 bits
+        let first = x.as_usize() * bits % 8;
+        let n = out.len().min((src.len() * 8).saturating_sub(first) / bits);
+
+        for (i, pixel) in out[..n].iter_mut().enumerate() {
+            let b = (first + i * bits) / 8;
+            *pixel = match self.format {
+                Format::Palette(bits) => {
+                    let bit = first + i * bits.as_usize();
+                    // Pixels are packed from the most significant bits
+                    let shift = 8 - bits.as_usize() - bit % 8;
+                    let index = (src[b] >> shift) & ((1 << bits) - 1);
+                    self.palette_color(index)
+                }
+                Format::Rgb555 => {
+                    let c = u16::from_le_bytes([src[b], src[b + 1]]);
+                    ((c & 0x7FE0) << 1) | ((c & 0x0200) >> 4) | (c & 0x001F)
+                }
+                Format::Rgb565 => u16::from_le_bytes([src[b], src[b + 1]]),
+                Format::Bgr888 | Format::Bgrx8888 => rgb565(src[b + 2], src[b + 1], src[b]),
+            };
+        }
+    }
+
+    fn palette_color(&self, index: u8) -> u16 {
+        let i = index.as_usize() * 4;
+        match self.palette.get(i..i + 3) {
+            Some(bgr) => rgb565(bgr[2], bgr[1], bgr[0]),
+            None => 0,
+        }
+    }
+}
+
+impl<C: timer::GeneralInstance4Channel> LCD<C> {
+    /// Draws `bmp` with its top left corner at `(x, y)`, clipped to the
+    /// panel.
+    pub async fn show_bmp(&mut self, (x, y): (u16, u16), bmp: &Bmp<'_>) {
+        if x >= WIDTH || y >= HEIGHT {
+            return;
+        }
+        let w = bmp.width().min(WIDTH - x).as_usize().min(MAX_ROW);
+        let h = bmp.height().min(HEIGHT - y);
+
+        self.set_address(x, y, x + w.as_u16() - 1, y + h - 1).await;
+
+        let mut row = [0u16; MAX_ROW];
+        for j in 0..h {
+            bmp.read_row(j, &mut row[..w]);
+            self.write_data(&row[..w]).await;
+        }
+    }
+}
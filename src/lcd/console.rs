@@ -0,0 +1,368 @@
+//! Text console implementing [`core::fmt::Write`].
+//!
+//! Writing only updates a character grid in RAM, so `write!` can be used from
+//! synchronous code, and nothing shows until [`Console::flush`] draws the
+//! cells that changed. [`Console::print`] does both:
+//!
+//! ```ignore
+//! console.print(format_args!("{} samples\n", count)).await;
+//! ```
+//!
+//! Where [`HW_VSCROLL`] is set, scrolling moves the picture with the
+//! controller's vertical scroll. In the other directions the controller
+//! could only scroll along the lines, so the lines are redrawn instead, only
+//! the cells that look different from before.
+//!
+//! The console understands `\n` (start of the next line), `\r`, `\t` and a
+//! subset of ANSI escape sequences:
+//!
+//! - `ESC[<n>m`: 0 reset, 1/22 bold (bright colors) on/off, 30-37/90-97
+//!   foreground, 40-47/100-107 background, 39/49 default colors
+//! - `ESC[<row>;<col>H` and `ESC[<row>;<col>f`: move the cursor, 1-based
+//! - `ESC[<n>A`/`B`/`C`/`D`: move the cursor up/down/right/left
+//! - `ESC[<n>J`: clear to the end (0), the start (1) or all of the screen (2)
+//! - `ESC[<n>K`: clear to the end (0), the start (1) or all of the line (2)
+
+use super::{FRAME_LINES, HEIGHT, HW_VSCROLL, LCD, WIDTH, font::FontSize, style::GlyphStyle};
+use as_what::{AsU16, AsUsize};
+use core::fmt;
+use embassy_stm32::timer;
+
+pub const MAX_COLS: usize = WIDTH as usize / FontSize::_6x12.x() as usize;
+pub const MAX_ROWS: usize = HEIGHT as usize / FontSize::_6x12.y() as usize;
+
+pub const TAB_WIDTH: usize = 4;
+
+const ESC: char = '\x1b';
+const MAX_PARAMS: usize = 4;
+
+/// The 16 ANSI colors, normal then bright.
+pub const ANSI_COLORS: [u16; 16] = [
+    0x0000, 0xA800, 0x0540, 0xAD40, 0x0015, 0xA815, 0x0555, 0xAD55, 0x52AA, 0xFAAA, 0x57EA, 0xFFEA,
+    0x52BF, 0xFABF, 0x57FF, 0xFFFF,
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Cell {
+    ch: u8,
+    fc: u16,
+    bc: u16,
+}
+
+enum State {
+    Normal,
+    Escape,
+    Csi,
+}
+
+pub struct Console<'a, C: timer::GeneralInstance4Channel> {
+    lcd: &'a mut LCD<C>,
+    font: FontSize,
+    cols: usize,
+    rows: usize,
+    cells: [[Cell; MAX_COLS]; MAX_ROWS],
+    /// What the panel shows, by screen row
+    shown: [[Cell; MAX_COLS]; MAX_ROWS],
+    /// Physical row of the first line, the grid is used as a ring buffer
+    top: usize,
+    scrolled: bool,
+    col: usize,
+    row: usize,
+    /// The last column was written, wrap before the next character
+    pending_wrap: bool,
+    default_fc: u16,
+    default_bc: u16,
+    fc: u16,
+    bc: u16,
+    bold: bool,
+    state: State,
+    params: [u16; MAX_PARAMS],
+    param_count: usize,
+}
+
+impl<'a, C: timer::GeneralInstance4Channel> Console<'a, C> {
+    /// Creates a console covering the whole panel and clears it to `bc`.
+    pub async fn new(lcd: &'a mut LCD<C>, font: FontSize, fc: u16, bc: u16) -> Self {
+        let cols = (WIDTH / font.x().as_u16()).as_usize();
+        let rows = (HEIGHT / font.y().as_u16()).as_usize();
+        let blank = Cell { ch: b' ', fc, bc };
+
+        lcd.fill(0, 0, WIDTH, HEIGHT, bc).await;
+        if HW_VSCROLL {
+            let scroll = (rows * font.y().as_usize()).as_u16();
+            lcd.set_scroll_area(0, scroll, FRAME_LINES - scroll).await;
+            lcd.set_scroll_start(0).await;
+        }
+
+        Self {
+            lcd,
+            font,
+            cols,
+            rows,
+            cells: [[blank; MAX_COLS]; MAX_ROWS],
+            shown: [[blank; MAX_COLS]; MAX_ROWS],
+            top: 0,
+            scrolled: false,
+            col: 0,
+            row: 0,
+            pending_wrap: false,
+            default_fc: fc,
+            default_bc: bc,
+            fc,
+            bc,
+            bold: false,
+            state: State::Normal,
+            params: [0; MAX_PARAMS],
+            param_count: 0,
+        }
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.cols, self.rows)
+    }
+
+    /// Cursor position as (column, row).
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.col, self.row)
+    }
+
+    pub fn set_cursor(&mut self, col: usize, row: usize) {
+        self.col = col.min(self.cols - 1);
+        self.row = row.min(self.rows - 1);
+        self.pending_wrap = false;
+    }
+
+    pub fn set_colors(&mut self, fc: u16, bc: u16) {
+        self.fc = fc;
+        self.bc = bc;
+    }
+
+    /// Blanks the screen and moves the cursor home.
+    pub fn clear(&mut self) {
+        for row in 0..self.rows {
+            self.clear_line(row, 0, self.cols);
+        }
+        self.set_cursor(0, 0);
+    }
+
+    /// Writes `args` and draws them, `write!` followed by
+    /// [`Console::flush`].
+    pub async fn print(&mut self, args: fmt::Arguments<'_>) {
+        let _ = fmt::Write::write_fmt(self, args);
+        self.flush().await;
+    }
+
+    /// Draws every cell that changed since the last flush.
+    pub async fn flush(&mut self) {
+        let (w, h) = (self.font.x().as_u16(), self.font.y().as_u16());
+
+        for physical in 0..self.rows {
+            // With hardware scrolling rows stay where they are in frame
+            // memory, otherwise the screen shows the ring in logical order.
+            let screen_row = if HW_VSCROLL {
+                physical
+            } else {
+                (physical + self.rows - self.top) % self.rows
+            };
+            for col in 0..self.cols {
+                let cell = self.cells[physical][col];
+                let shown = &mut self.shown[screen_row][col];
+                if *shown == cell {
+                    continue;
+                }
+                *shown = cell;
+                self.lcd
+                    .show_char(
+                        (col.as_u16() * w, screen_row.as_u16() * h),
+                        cell.ch as char,
+                        self.font,
+                        &GlyphStyle::new(cell.fc, cell.bc),
+                    )
+                    .await;
+            }
+        }
+
+        if self.scrolled && HW_VSCROLL {
+            self.lcd.set_scroll_start(self.top.as_u16() * h).await;
+        }
+        self.scrolled = false;
+    }
+
+    fn cell(&mut self, col: usize, row: usize) -> &mut Cell {
+        let physical = (self.top + row) % self.rows;
+        &mut self.cells[physical][col]
+    }
+
+    fn clear_line(&mut self, row: usize, from: usize, to: usize) {
+        let (fc, bc) = (self.fc, self.bc);
+        for col in from..to.min(self.cols) {
+            *self.cell(col, row) = Cell { ch: b' ', fc, bc };
+        }
+    }
+
+    fn new_line(&mut self) {
+        self.col = 0;
+        self.pending_wrap = false;
+        if self.row + 1 < self.rows {
+            self.row += 1;
+            return;
+        }
+
+        self.top = (self.top + 1) % self.rows;
+        self.scrolled = true;
+        self.clear_line(self.rows - 1, 0, self.cols);
+    }
+
+    fn put(&mut self, ch: char) {
+        if self.pending_wrap {
+            self.new_line();
+        }
+
+        let ch = if ch.is_ascii() && !ch.is_ascii_control() {
+            ch as u8
+        } else {
+            b'?'
+        };
+        let (fc, bc) = (self.fc, self.bc);
+        let (col, row) = (self.col, self.row);
+        *self.cell(col, row) = Cell { ch, fc, bc };
+
+        if self.col + 1 < self.cols {
+            self.col += 1;
+        } else {
+            self.pending_wrap = true;
+        }
+    }
+
+    fn process(&mut self, ch: char) {
+        match self.state {
+            State::Normal => match ch {
+                '\n' => self.new_line(),
+                '\r' => {
+                    self.col = 0;
+                    self.pending_wrap = false;
+                }
+                '\t' => {
+                    let next = (self.col / TAB_WIDTH + 1) * TAB_WIDTH;
+                    while self.col < next.min(self.cols) && !self.pending_wrap {
+                        self.put(' ');
+                    }
+                }
+                ESC => self.state = State::Escape,
+                _ => self.put(ch),
+            },
+            State::Escape => {
+                if ch == '[' {
+                    self.params = [0; MAX_PARAMS];
+                    self.param_count = 0;
+                    self.state = State::Csi;
+                } else {
+                    self.state = State::Normal;
+                }
+            }
+            State::Csi => match ch {
+                '0'..='9' => {
+                    self.param_count = self.param_count.max(1);
+                    let param = &mut self.params[self.param_count - 1];
+                    *param = param
+                        .saturating_mul(10)
+                        .saturating_add(ch as u16 - '0' as u16);
+                }
+                ';' => self.param_count = (self.param_count.max(1) + 1).min(MAX_PARAMS),
+                '\x40'..='\x7e' => {
+                    self.state = State::Normal;
+                    self.dispatch(ch);
+                }
+                _ => {}
+            },
+        }
+    }
+
+    fn param(&self, index: usize, default: u16) -> u16 {
+        if index < self.param_count && self.params[index] != 0 {
+            self.params[index]
+        } else {
+            default
+        }
+    }
+
+    fn dispatch(&mut self, command: char) {
+        let n = self.param(0, 1).as_usize();
+        match command {
+            'm' => {
+                for i in 0..self.param_count.max(1) {
+                    self.select_graphic_rendition(self.params[i]);
+                }
+            }
+            'H' | 'f' => {
+                let row = self.param(0, 1).as_usize() - 1;
+                let col = self.param(1, 1).as_usize() - 1;
+                self.set_cursor(col, row);
+            }
+            'A' => self.set_cursor(self.col, self.row.saturating_sub(n)),
+            'B' => self.set_cursor(self.col, self.row + n),
+            'C' => self.set_cursor(self.col + n, self.row),
+            'D' => self.set_cursor(self.col.saturating_sub(n), self.row),
+            'J' => {
+                let (col, row) = (self.col, self.row);
+                match self.param(0, 0) {
+                    0 => {
+                        self.clear_line(row, col, self.cols);
+                        for row in row + 1..self.rows {
+                            self.clear_line(row, 0, self.cols);
+                        }
+                    }
+                    1 => {
+                        for row in 0..row {
+                            self.clear_line(row, 0, self.cols);
+                        }
+                        self.clear_line(row, 0, col + 1);
+                    }
+                    _ => {
+                        for row in 0..self.rows {
+                            self.clear_line(row, 0, self.cols);
+                        }
+                    }
+                }
+            }
+            'K' => {
+                let (col, row) = (self.col, self.row);
+                match self.param(0, 0) {
+                    0 => self.clear_line(row, col, self.cols),
+                    1 => self.clear_line(row, 0, col + 1),
+                    _ => self.clear_line(row, 0, self.cols),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn select_graphic_rendition(&mut self, code: u16) {
+        let bright = if self.bold { 8 } else { 0 };
+        match code {
+            0 => {
+                self.fc = self.default_fc;
+                self.bc = self.default_bc;
+                self.bold = false;
+            }
+            1 => self.bold = true,
+            22 => self.bold = false,
+            30..=37 => self.fc = ANSI_COLORS[(code - 30).as_usize() + bright],
+            39 => self.fc = self.default_fc,
+            40..=47 => self.bc = ANSI_COLORS[(code - 40).as_usize()],
+            49 => self.bc = self.default_bc,
+            90..=97 => self.fc = ANSI_COLORS[(code - 90).as_usize() + 8],
+            100..=107 => self.bc = ANSI_COLORS[(code - 100).as_usize() + 8],
+            _ => {}
+        }
+    }
+}
+
+impl<C: timer::GeneralInstance4Channel> fmt::Write for Console<'_, C> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for ch in s.chars() {
+            self.process(ch);
+        }
+        Ok(())
+    }
+}
@@ -2214,3 +2214,375 @@ impl ChineseFontSize {
         self.y()
     }
 }
+
+/// Bitmap of a printable ASCII character, `None` for anything else
+pub fn ascii_glyph(ch: char, size: FontSize) -> Option<&'static [u8]> {
+    if !(' '..='~').contains(&ch) {
+        return None;
+    }
+    let num = ch as usize - ' ' as usize;
+    Some(match size {
+        FontSize::_6x12 => {
+            let table: &'static [[u8; 12]; 95] = &ASCII_1206;
+            &table[num]
+        }
+        FontSize::_8x16 => {
+            let table: &'static [[u8; 16]; 95] = &ASCII_1608;
+            &table[num]
+        }
+        FontSize::_12x24 => {
+            let table: &'static [[u8; 48]; 95] = &ASCII_2412;
+            &table[num]
+        }
+        FontSize::_16x32 => {
+            let table: &'static [[u8; 64]; 95] = &ASCII_3216;
+            &table[num]
+        }
+    })
+}
+
+/// Bitmap of a Chinese character, `None` if it is not in the dictionary
+pub fn chinese_glyph(ch: char, size: ChineseFontSize) -> Option<&'static [u8]> {
+    match size {
+        ChineseFontSize::_12x12 => {
+            let table: &'static [FontGB12] = &TFONT12;
+            table.iter().find(|f| f.index == ch).map(|f| &f.msk[..])
+        }
+        ChineseFontSize::_16x16 => {
+            let table: &'static [FontGB16] = &TFONT16;
+            table.iter().find(|f| f.index == ch).map(|f| &f.msk[..])
+        }
+        ChineseFontSize::_24x24 => {
+            let table: &'static [FontGB24] = &TFONT24;
+            table.iter().find(|f| f.index == ch).map(|f| &f.msk[..])
+        }
+        ChineseFontSize::_32x32 => {
+            let table: &'static [FontGB32] = &TFONT32;
+            table.iter().find(|f| f.index == ch).map(|f| &f.msk[..])
+        }
+    }
+}
+
+/// Bits of alpha stored per pixel of an [`AlphaFont`] glyph
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Bpp {
+    _1 = 1,
+    _2 = 2,
+    _4 = 4,
+}
+
+impl Bpp {
+    pub const fn bits(self) -> u8 {
+        self as u8
+    }
+
+    /// Largest alpha value, i.e. a fully covered pixel.
+    pub const fn max(self) -> u8 {
+        (1 << self.bits()) - 1
+    }
+}
+
+/// Anti-aliased glyph of an [`AlphaFont`]
+pub struct AlphaGlyph {
+    /// Character index (UTF-8 character)
+    pub index: char,
+    /// Alpha data, row by row. Every row starts on a byte boundary and pixels
+    /// are packed from the least significant bits, like the 1-bit fonts above.
+    pub msk: &'static [u8],
+}
+
+/// Font whose glyphs carry 1, 2 or 4 bits of coverage per pixel, generated by
+/// `boe-tools font`
+pub struct AlphaFont {
+    pub width: u8,
+    pub height: u8,
+    pub bpp: Bpp,
+    pub glyphs: &'static [AlphaGlyph],
+}
+
+impl AlphaFont {
+    pub const fn row_bytes(&self) -> usize {
+        (self.width as usize * self.bpp.bits() as usize).div_ceil(8)
+    }
+
+    pub fn glyph(&self, ch: char) -> Option<&AlphaGlyph> {
+        self.glyphs.iter().find(|glyph| glyph.index == ch)
+    }
+
+    /// Alpha of pixel `(x, y)` of `glyph`, between 0 and [`Bpp::max`].
+    pub fn alpha(&self, glyph: &AlphaGlyph, x: u8, y: u8) -> u8 {
+        let bits = self.bpp.bits() as usize;
+        let bit = x as usize * bits;
+        let byte = glyph.msk[y as usize * self.row_bytes() + bit / 8];
+        (byte >> (bit % 8)) & self.bpp.max()
+    }
+}
+
+// Generated with:
+// boe-tools font DejaVuSansMono.ttf --name DIGITS_1224_AA4 \
+//     --width 12 --height 24 --bpp 4 --chars "0123456789.:-+ %"
+/// Anti-aliased font with 12x24 pixels and 4-bit alpha
+pub const DIGITS_1224_AA4: AlphaFont = AlphaFont {
+    width: 12,
+    height: 24,
+    bpp: Bpp::_4,
+    glyphs: &[
+        AlphaGlyph {
+            index: '0',
+            msk: &[
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x30, 0xFB, 0xDF,
+                0x07, 0x00, 0x00, 0xE3, 0xAF, 0xD8, 0x8F, 0x00, 0x00, 0xFA, 0x07, 0x20, 0xFE, 0x02,
+                0x10, 0xEF, 0x00, 0x00, 0xF8, 0x07, 0x60, 0xAF, 0x00, 0x00, 0xF4, 0x0C, 0x70, 0x8F,
+                0x00, 0x00, 0xF2, 0x0D, 0x80, 0x7F, 0x60, 0x19, 0xF1, 0x0F, 0xA0, 0x6F, 0xF1, 0x7F,
+                0xF0, 0x1F, 0x90, 0x7F, 0x90, 0x2C, 0xF0, 0x0F, 0x70, 0x8F, 0x00, 0x00, 0xF1, 0x0E,
+                0x60, 0x9F, 0x00, 0x00, 0xF3, 0x0C, 0x30, 0xDF, 0x00, 0x00, 0xF7, 0x09, 0x00, 0xFC,
+                0x04, 0x00, 0xFC, 0x03, 0x00, 0xF5, 0x6E, 0xB4, 0xBF, 0x00, 0x00, 0x60, 0xFF, 0xFF,
+                0x1B, 0x00, 0x00, 0x00, 0x41, 0x25, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00,
+            ],
+        },
+        AlphaGlyph {
+            index: '1',
+            msk: &[
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x62, 0xC9, 0xCD,
+                0x00, 0x00, 0x00, 0xF8, 0xFF, 0xDF, 0x00, 0x00, 0x00, 0x75, 0x24, 0xDF, 0x00, 0x00,
+                0x00, 0x00, 0x20, 0xDF, 0x00, 0x00, 0x00, 0x00, 0x20, 0xDF, 0x00, 0x00, 0x00, 0x00,
+                0x20, 0xDF, 0x00, 0x00, 0x00, 0x00, 0x20, 0xDF, 0x00, 0x00, 0x00, 0x00, 0x20, 0xDF,
+                0x00, 0x00, 0x00, 0x00, 0x20, 0xDF, 0x00, 0x00, 0x00, 0x00, 0x20, 0xDF, 0x00, 0x00,
+                0x00, 0x00, 0x20, 0xDF, 0x00, 0x00, 0x00, 0x00, 0x20, 0xDF, 0x00, 0x00, 0x00, 0x00,
+                0x20, 0xDF, 0x00, 0x00, 0x00, 0x92, 0x99, 0xEF, 0x99, 0x09, 0x00, 0xF4, 0xFF, 0xFF,
+                0xFF, 0x0F, 0x00, 0x21, 0x22, 0x22, 0x22, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00,
+            ],
+        },
+        AlphaGlyph {
+            index: '2',
+            msk: &[
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x01, 0x00, 0x00, 0x10, 0xC7, 0xFF, 0xDF,
+                0x06, 0x00, 0x50, 0xEF, 0x9B, 0xFA, 0x9F, 0x00, 0x40, 0x17, 0x00, 0x30, 0xFE, 0x03,
+                0x00, 0x00, 0x00, 0x00, 0xFA, 0x07, 0x00, 0x00, 0x00, 0x00, 0xF9, 0x07, 0x00, 0x00,
+                0x00, 0x00, 0xFC, 0x03, 0x00, 0x00, 0x00, 0x50, 0xCF, 0x00, 0x00, 0x00, 0x00, 0xE2,
+                0x3F, 0x00, 0x00, 0x00, 0x10, 0xFC, 0x05, 0x00, 0x00, 0x00, 0xB1, 0x7F, 0x00, 0x00,
+                0x00, 0x00, 0xFA, 0x08, 0x00, 0x00, 0x00, 0xA0, 0x9F, 0x00, 0x00, 0x00, 0x00, 0xF9,
+                0x09, 0x00, 0x00, 0x00, 0x60, 0xFF, 0x99, 0x99, 0x99, 0x06, 0x70, 0xFF, 0xFF, 0xFF,
+                0xFF, 0x0A, 0x10, 0x22, 0x22, 0x22, 0x22, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00,
+            ],
+        },
+        AlphaGlyph {
+            index: '3',
+            msk: &[
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x01, 0x00, 0x00, 0x10, 0xDA, 0xFF, 0xDF,
+                0x07, 0x00, 0x30, 0xDF, 0x9A, 0xEA, 0x9F, 0x00, 0x10, 0x02, 0x00, 0x20, 0xFE, 0x03,
+                0x00, 0x00, 0x00, 0x00, 0xF9, 0x07, 0x00, 0x00, 0x00, 0x00, 0xF9, 0x06, 0x00, 0x00,
+                0x00, 0x40, 0xEE, 0x02, 0x00, 0x00, 0xBB, 0xFD, 0x3C, 0x00, 0x00, 0x00, 0xEE, 0xFF,
+                0x29, 0x00, 0x00, 0x00, 0x00, 0x71, 0xEF, 0x02, 0x00, 0x00, 0x00, 0x00, 0xF8, 0x09,
+                0x00, 0x00, 0x00, 0x00, 0xF3, 0x0C, 0x00, 0x00, 0x00, 0x00, 0xF4, 0x0B, 0x20, 0x00,
+                0x00, 0x00, 0xFB, 0x09, 0x90, 0x8D, 0x56, 0xC6, 0xEF, 0x02, 0x70, 0xFE, 0xFF, 0xFF,
+                0x3B, 0x00, 0x00, 0x30, 0x65, 0x35, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00,
+            ],
+        },
+        AlphaGlyph {
+            index: '4',
+            msk: &[
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xD3,
+                0x6D, 0x00, 0x00, 0x00, 0x00, 0xFC, 0x6F, 0x00, 0x00, 0x00, 0x70, 0xBE, 0x6F, 0x00,
+                0x00, 0x00, 0xE2, 0x97, 0x6F, 0x00, 0x00, 0x00, 0xDB, 0x90, 0x6F, 0x00, 0x00, 0x50,
+                0x4F, 0x90, 0x6F, 0x00, 0x00, 0xE1, 0x0A, 0x90, 0x6F, 0x00, 0x00, 0xE9, 0x02, 0x90,
+                0x6F, 0x00, 0x40, 0x7F, 0x00, 0x90, 0x6F, 0x00, 0xD0, 0x2E, 0x11, 0xA1, 0x7F, 0x11,
+                0xF0, 0xFF, 0xFF, 0xFF, 0xFF, 0x6F, 0x80, 0x88, 0x88, 0xC8, 0xBF, 0x48, 0x00, 0x00,
+                0x00, 0x90, 0x6F, 0x00, 0x00, 0x00, 0x00, 0x90, 0x6F, 0x00, 0x00, 0x00, 0x00, 0x90,
+                0x6F, 0x00, 0x00, 0x00, 0x00, 0x10, 0x12, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00,
+            ],
+        },
+        AlphaGlyph {
+            index: '5',
+            msk: &[
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xDC, 0xDD, 0xDD,
+                0x9D, 0x00, 0x00, 0xFE, 0xCC, 0xCC, 0x9C, 0x00, 0x00, 0xEE, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0xEE, 0x00, 0x00, 0x00, 0x00, 0x00, 0xEE, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFE,
+                0xCA, 0x7A, 0x01, 0x00, 0x00, 0xFE, 0xEF, 0xFF, 0x4E, 0x00, 0x00, 0x27, 0x00, 0xA2,
+                0xEF, 0x01, 0x00, 0x00, 0x00, 0x00, 0xFB, 0x07, 0x00, 0x00, 0x00, 0x00, 0xF6, 0x09,
+                0x00, 0x00, 0x00, 0x00, 0xF5, 0x0B, 0x00, 0x00, 0x00, 0x00, 0xF7, 0x08, 0x10, 0x00,
+                0x00, 0x10, 0xFE, 0x05, 0x80, 0x8D, 0x56, 0xE7, 0xAF, 0x00, 0x70, 0xFF, 0xFF, 0xFF,
+                0x18, 0x00, 0x00, 0x41, 0x65, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00,
+            ],
+        },
+        AlphaGlyph {
+            index: '6',
+            msk: &[
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x00, 0x00, 0x00, 0x10, 0xE8, 0xFF,
+                0xAF, 0x00, 0x00, 0xC1, 0xEF, 0x9A, 0xEB, 0x00, 0x00, 0xF8, 0x1B, 0x00, 0x20, 0x00,
+                0x10, 0xEF, 0x01, 0x00, 0x00, 0x00, 0x40, 0x9F, 0x00, 0x00, 0x00, 0x00, 0x80, 0x6F,
+                0xA3, 0x9B, 0x06, 0x00, 0x90, 0x8F, 0xEF, 0xFD, 0xBF, 0x00, 0xA0, 0xFF, 0x09, 0x10,
+                0xFC, 0x06, 0x90, 0xDF, 0x00, 0x00, 0xF4, 0x0C, 0x80, 0xAF, 0x00, 0x00, 0xF0, 0x0E,
+                0x70, 0x9F, 0x00, 0x00, 0xE0, 0x1F, 0x40, 0xBF, 0x00, 0x00, 0xF1, 0x0E, 0x00, 0xFD,
+                0x02, 0x00, 0xF6, 0x0B, 0x00, 0xF6, 0x5D, 0x73, 0xFE, 0x03, 0x00, 0x80, 0xFF, 0xFF,
+                0x5E, 0x00, 0x00, 0x00, 0x51, 0x46, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00,
+            ],
+        },
+        AlphaGlyph {
+            index: '7',
+            msk: &[
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0xDD, 0xDD, 0xDD,
+                0xDD, 0x0C, 0x70, 0xCC, 0xCC, 0xCC, 0xFD, 0x0A, 0x00, 0x00, 0x00, 0x00, 0xFA, 0x04,
+                0x00, 0x00, 0x00, 0x10, 0xDF, 0x00, 0x00, 0x00, 0x00, 0x70, 0x8F, 0x00, 0x00, 0x00,
+                0x00, 0xD0, 0x2F, 0x00, 0x00, 0x00, 0x00, 0xF4, 0x0B, 0x00, 0x00, 0x00, 0x00, 0xFA,
+                0x06, 0x00, 0x00, 0x00, 0x10, 0xEE, 0x01, 0x00, 0x00, 0x00, 0x60, 0x9F, 0x00, 0x00,
+                0x00, 0x00, 0xC0, 0x4F, 0x00, 0x00, 0x00, 0x00, 0xF3, 0x0D, 0x00, 0x00, 0x00, 0x00,
+                0xF9, 0x07, 0x00, 0x00, 0x00, 0x10, 0xFE, 0x02, 0x00, 0x00, 0x00, 0x60, 0xBF, 0x00,
+                0x00, 0x00, 0x00, 0x10, 0x12, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00,
+            ],
+        },
+        AlphaGlyph {
+            index: '8',
+            msk: &[
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x02, 0x00, 0x00, 0x00, 0x70, 0xFE, 0xFF,
+                0x2A, 0x00, 0x00, 0xF8, 0x8D, 0xB7, 0xDF, 0x01, 0x10, 0xEF, 0x02, 0x00, 0xFA, 0x08,
+                0x40, 0xBF, 0x00, 0x00, 0xF5, 0x0A, 0x30, 0xCF, 0x00, 0x00, 0xF6, 0x09, 0x00, 0xFC,
+                0x03, 0x00, 0xFB, 0x04, 0x00, 0xB2, 0xBF, 0xDA, 0x5E, 0x00, 0x00, 0x71, 0xFE, 0xFF,
+                0x3A, 0x00, 0x00, 0xFC, 0x28, 0x41, 0xED, 0x04, 0x60, 0xAF, 0x00, 0x00, 0xF4, 0x0C,
+                0x90, 0x6F, 0x00, 0x00, 0xF0, 0x0F, 0x90, 0x7F, 0x00, 0x00, 0xF1, 0x1F, 0x60, 0xCF,
+                0x00, 0x00, 0xF5, 0x0D, 0x10, 0xFD, 0x4A, 0x73, 0xFE, 0x05, 0x00, 0xC3, 0xFF, 0xFF,
+                0x6E, 0x00, 0x00, 0x00, 0x53, 0x46, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00,
+            ],
+        },
+        AlphaGlyph {
+            index: '9',
+            msk: &[
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x01, 0x00, 0x00, 0x00, 0x80, 0xFE, 0xEF,
+                0x07, 0x00, 0x00, 0xFA, 0x8D, 0xD8, 0x9F, 0x00, 0x40, 0xDF, 0x01, 0x10, 0xFD, 0x02,
+                0x80, 0x7F, 0x00, 0x00, 0xF7, 0x08, 0xA0, 0x5F, 0x00, 0x00, 0xF4, 0x0B, 0xA0, 0x5F,
+                0x00, 0x00, 0xF4, 0x0D, 0x80, 0x8F, 0x00, 0x00, 0xF7, 0x0E, 0x30, 0xDF, 0x01, 0x20,
+                0xFE, 0x0F, 0x00, 0xF9, 0x9E, 0xE9, 0xEC, 0x0E, 0x00, 0x70, 0xFC, 0xAF, 0xF1, 0x0D,
+                0x00, 0x00, 0x00, 0x00, 0xF2, 0x0B, 0x00, 0x00, 0x00, 0x00, 0xF8, 0x07, 0x00, 0x00,
+                0x00, 0x30, 0xEE, 0x01, 0x00, 0x99, 0x56, 0xE8, 0x7F, 0x00, 0x00, 0xF9, 0xFF, 0xEF,
+                0x06, 0x00, 0x00, 0x30, 0x65, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00,
+            ],
+        },
+        AlphaGlyph {
+            index: '.',
+            msk: &[
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0xE1, 0x6E, 0x00, 0x00, 0x00, 0x00, 0xF1, 0x7F, 0x00, 0x00, 0x00, 0x00, 0xF1, 0x7F,
+                0x00, 0x00, 0x00, 0x00, 0x20, 0x12, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00,
+            ],
+        },
+        AlphaGlyph {
+            index: ':',
+            msk: &[
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x81, 0x48, 0x00, 0x00, 0x00, 0x00,
+                0xF1, 0x7F, 0x00, 0x00, 0x00, 0x00, 0xF1, 0x7F, 0x00, 0x00, 0x00, 0x00, 0x71, 0x37,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0xE1, 0x6E, 0x00, 0x00, 0x00, 0x00, 0xF1, 0x7F, 0x00, 0x00, 0x00, 0x00, 0xF1, 0x7F,
+                0x00, 0x00, 0x00, 0x00, 0x20, 0x12, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00,
+            ],
+        },
+        AlphaGlyph {
+            index: '-',
+            msk: &[
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x20, 0x55, 0x55, 0x04, 0x00, 0x00, 0x60, 0xFF, 0xFF, 0x0C, 0x00,
+                0x00, 0x20, 0x55, 0x55, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00,
+            ],
+        },
+        AlphaGlyph {
+            index: '+',
+            msk: &[
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x60, 0x0A, 0x00, 0x00, 0x00, 0x00, 0xA0, 0x1F, 0x00, 0x00, 0x00, 0x00,
+                0xA0, 0x1F, 0x00, 0x00, 0x00, 0x00, 0xA0, 0x1F, 0x00, 0x00, 0x30, 0x33, 0xB3, 0x3F,
+                0x33, 0x13, 0xF2, 0xFF, 0xFF, 0xFF, 0xFF, 0x8F, 0x81, 0x88, 0xC8, 0x8F, 0x88, 0x48,
+                0x00, 0x00, 0xA0, 0x1F, 0x00, 0x00, 0x00, 0x00, 0xA0, 0x1F, 0x00, 0x00, 0x00, 0x00,
+                0xA0, 0x1F, 0x00, 0x00, 0x00, 0x00, 0xA0, 0x1F, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00,
+            ],
+        },
+        AlphaGlyph {
+            index: ' ',
+            msk: &[
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00,
+            ],
+        },
+        AlphaGlyph {
+            index: '%',
+            msk: &[
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x31, 0x01, 0x00,
+                0x00, 0x00, 0x70, 0xFF, 0x8F, 0x00, 0x00, 0x00, 0xF4, 0x27, 0xF6, 0x05, 0x00, 0x00,
+                0xC9, 0x00, 0xA0, 0x0A, 0x00, 0x00, 0xC8, 0x00, 0xB0, 0x0A, 0x00, 0x00, 0xF3, 0x49,
+                0xF8, 0x05, 0x10, 0x56, 0x60, 0xFE, 0x6E, 0x30, 0xE9, 0x4B, 0x00, 0x20, 0x61, 0xEC,
+                0x28, 0x00, 0x00, 0x93, 0xBE, 0x15, 0x33, 0x00, 0xB0, 0x8E, 0x02, 0xE4, 0xFF, 0x1B,
+                0x40, 0x00, 0x10, 0xAE, 0x42, 0xAD, 0x00, 0x00, 0x40, 0x1F, 0x00, 0xF6, 0x00, 0x00,
+                0x40, 0x2F, 0x00, 0xE7, 0x00, 0x00, 0x10, 0xCD, 0x64, 0x9E, 0x00, 0x00, 0x00, 0xC3,
+                0xFF, 0x19, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00,
+            ],
+        },
+    ],
+};
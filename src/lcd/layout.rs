@@ -0,0 +1,398 @@
+//! Paragraph layout inside a bounding rectangle.
+//!
+//! Text is broken greedily into lines: at spaces, between CJK characters and,
+//! when a single word is wider than the box, between any two characters.
+//! ASCII is drawn with the [`FontSize`] font and everything else with the
+//! [`ChineseFontSize`] font.
+
+use super::{
+    CharMode, LCD, Rect,
+    font::{ChineseFontSize, FontSize},
+    style::GlyphStyle,
+};
+use as_what::AsU16;
+use core::{cmp::max, iter::Peekable};
+use embassy_stm32::timer;
+
+const ELLIPSIS: &str = "...";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum HAlign {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum VAlign {
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// Size of a laid out block of text.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextMetrics {
+    pub width: u16,
+    pub height: u16,
+    pub lines: u16,
+}
+
+/// A single wrapped line, without its trailing whitespace.
+#[derive(Clone, Copy)]
+pub struct Line<'a> {
+    pub text: &'a str,
+    pub width: u16,
+}
+
+#[derive(Clone, Copy)]
+pub struct TextLayout {
+    pub font: FontSize,
+    pub chinese_font: ChineseFontSize,
+    pub h_align: HAlign,
+    pub v_align: VAlign,
+    /// Extra pixels between two lines.
+    pub line_spacing: u16,
+    /// Truncate the last visible line with "..." when the text does not fit.
+    pub ellipsis: bool,
+}
+
+impl TextLayout {
+    pub const fn new(font: FontSize, chinese_font: ChineseFontSize) -> Self {
+        Self {
+            font,
+            chinese_font,
+            h_align: HAlign::Left,
+            v_align: VAlign::Top,
+            line_spacing: 0,
+            ellipsis: false,
+        }
+    }
+
+    pub const fn align(mut self, h_align: HAlign, v_align: VAlign) -> Self {
+        self.h_align = h_align;
+        self.v_align = v_align;
+        self
+    }
+
+    pub const fn line_spacing(mut self, line_spacing: u16) -> Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+
+    pub const fn ellipsis(mut self, ellipsis: bool) -> Self {
+        self.ellipsis = ellipsis;
+        self
+    }
+
+    pub fn char_width(&self, ch: char) -> u16 {
+        if ch.is_ascii_control() {
+            0
+        } else if ch.is_ascii() {
+            self.font.x().as_u16()
+        } else {
+            self.chinese_font.x().as_u16()
+        }
+    }
+
+    pub fn line_height(&self) -> u16 {
+        max(self.font.y(), self.chinese_font.y()).as_u16()
+    }
+
+    pub fn text_width(&self, s: &str) -> u16 {
+        s.chars().map(|ch| self.char_width(ch)).sum()
+    }
+
+    /// Splits `text` into lines no wider than `max_width`.
+    pub fn lines<'a>(&self, text: &'a str, max_width: u16) -> Lines<'a> {
+        Lines {
+            layout: *self,
+            rest: Some(text),
+            max_width,
+        }
+    }
+
+    /// Number of lines that fit into `height` pixels.
+    pub fn max_lines(&self, height: u16) -> u16 {
+        (height + self.line_spacing) / (self.line_height() + self.line_spacing)
+    }
+
+    /// Measures `text` wrapped to `max_width`, without limiting the height.
+    pub fn measure(&self, text: &str, max_width: u16) -> TextMetrics {
+        let mut metrics = TextMetrics::default();
+        for line in self.lines(text, max_width) {
+            metrics.width = max(metrics.width, line.width);
+            metrics.lines += 1;
+        }
+        metrics.height = self.block_height(metrics.lines);
+        metrics
+    }
+
+    /// Measures `text` as it would be drawn into `rect`, i.e. clipped to the
+    /// lines that fit and with ellipsis truncation applied.
+    pub fn measure_in(&self, text: &str, rect: Rect) -> TextMetrics {
+        let mut metrics = TextMetrics::default();
+        for (line, ellipsis) in self.visible_lines(text, rect) {
+            metrics.width = max(metrics.width, self.drawn_width(line, ellipsis));
+            metrics.lines += 1;
+        }
+        metrics.height = self.block_height(metrics.lines);
+        metrics
+    }
+
+    /// Lines of `text` that are visible in `rect`, each paired with whether
+    /// an ellipsis has to be appended to it.
+    pub fn visible_lines<'a>(&self, text: &'a str, rect: Rect) -> VisibleLines<'a> {
+        VisibleLines {
+            layout: *self,
+            lines: self.lines(text, rect.w).peekable(),
+            remaining: self.max_lines(rect.h),
+            max_width: rect.w,
+        }
+    }
+
+    fn block_height(&self, lines: u16) -> u16 {
+        if lines == 0 {
+            0
+        } else {
+            lines * self.line_height() + (lines - 1) * self.line_spacing
+        }
+    }
+
+    fn drawn_width(&self, line: Line, ellipsis: bool) -> u16 {
+        if ellipsis {
+            line.width + self.text_width(ELLIPSIS)
+        } else {
+            line.width
+        }
+    }
+
+    /// Shortens `s` so that it still fits `max_width` with an ellipsis
+    /// appended.
+    fn truncate<'a>(&self, s: &'a str, max_width: u16) -> Line<'a> {
+        let budget = max_width.saturating_sub(self.text_width(ELLIPSIS));
+        let mut width = 0;
+        for (i, ch) in s.char_indices() {
+            let w = self.char_width(ch);
+            if width + w > budget {
+                let text = split(s, i).0.trim_end_matches(' ');
+                return Line {
+                    text,
+                    width: self.text_width(text),
+                };
+            }
+            width += w;
+        }
+        Line { text: s, width }
+    }
+}
+
+pub struct Lines<'a> {
+    layout: TextLayout,
+    rest: Option<&'a str>,
+    max_width: u16,
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Line<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let text = self.rest?;
+
+        let mut width = 0;
+        // Last place the line may end: (end, resume, width up to end)
+        let mut brk: Option<(usize, usize, u16)> = None;
+        let mut prev_wide = false;
+
+        for (i, ch) in text.char_indices() {
+            if ch == '\n' {
+                let line = split(text, i).0;
+                self.rest = Some(split(text, i + 1).1);
+                return Some(Line {
+                    text: line.trim_end_matches(' '),
+                    width: width - trailing_spaces(&self.layout, line),
+                });
+            }
+
+            let wide = !ch.is_ascii();
+            if ch == ' ' {
+                brk = Some((i, i + 1, width));
+            } else if i > 0 && (wide || prev_wide) {
+                brk = Some((i, i, width));
+            }
+            prev_wide = wide;
+
+            let w = self.layout.char_width(ch);
+            if width + w > self.max_width && i > 0 && ch != ' ' {
+                let (end, resume, line_width) = brk.unwrap_or((i, i, width));
+                self.rest = Some(split(text, resume).1.trim_start_matches(' '));
+                let line = split(text, end).0;
+                return Some(Line {
+                    text: line.trim_end_matches(' '),
+                    width: line_width - trailing_spaces(&self.layout, line),
+                });
+            }
+            width += w;
+        }
+
+        self.rest = None;
+        Some(Line {
+            text: text.trim_end_matches(' '),
+            width: width - trailing_spaces(&self.layout, text),
+        })
+    }
+}
+
+pub struct VisibleLines<'a> {
+    layout: TextLayout,
+    lines: Peekable<Lines<'a>>,
+    remaining: u16,
+    max_width: u16,
+}
+
+impl<'a> Iterator for VisibleLines<'a> {
+    type Item = (Line<'a>, bool);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let line = self.lines.next()?;
+        self.remaining -= 1;
+
+        if self.remaining == 0 && self.layout.ellipsis && self.lines.peek().is_some() {
+            Some((self.layout.truncate(line.text, self.max_width), true))
+        } else {
+            Some((line, false))
+        }
+    }
+}
+
+/// `s` split at `mid`, a char boundary. Slicing would do the same but links
+/// the formatting code of its panic into the image.
+fn split(s: &str, mid: usize) -> (&str, &str) {
+    s.split_at_checked(mid).unwrap_or((s, ""))
+}
+
+fn trailing_spaces(layout: &TextLayout, s: &str) -> u16 {
+    let count = s.len() - s.trim_end_matches(' ').len();
+    count.as_u16() * layout.char_width(' ')
+}
+
+impl<C: timer::GeneralInstance4Channel> LCD<C> {
+    /// Draws `text` wrapped and aligned inside `rect` and returns the size of
+    /// what was drawn.
+    ///
+    /// In [`CharMode::NonOverlay`] the whole of `rect` is painted, with `bc`
+    /// filling everything that is not covered by a glyph.
+    pub async fn show_text(
+        &mut self, rect: Rect, text: &str, fc: u16, bc: u16, layout: &TextLayout, mode: CharMode,
+    ) -> TextMetrics {
+        let metrics = layout.measure_in(text, rect);
+        let line_height = layout.line_height();
+        let clear = matches!(mode, CharMode::NonOverlay);
+
+        let top = match layout.v_align {
+            VAlign::Top => 0,
+            VAlign::Middle => (rect.h - metrics.height) / 2,
+            VAlign::Bottom => rect.h - metrics.height,
+        };
+        if clear {
+            self.fill_rect(Rect::new(rect.x, rect.y, rect.w, top), bc)
+                .await;
+        }
+
+        let mut y = rect.y + top;
+        for (index, (line, ellipsis)) in layout.visible_lines(text, rect).enumerate() {
+            if index > 0 {
+                if clear {
+                    self.fill_rect(Rect::new(rect.x, y, rect.w, layout.line_spacing), bc)
+                        .await;
+                }
+                y += layout.line_spacing;
+            }
+
+            let width = layout.drawn_width(line, ellipsis);
+            let left = match layout.h_align {
+                HAlign::Left => 0,
+                HAlign::Center => rect.w.saturating_sub(width) / 2,
+                HAlign::Right => rect.w.saturating_sub(width),
+            };
+            if clear {
+                self.fill_rect(Rect::new(rect.x, y, left, line_height), bc)
+                    .await;
+                let right = rect.x + left + width;
+                self.fill_rect(
+                    Rect::new(right, y, rect.right().saturating_sub(right), line_height),
+                    bc,
+                )
+                .await;
+            }
+
+            let x = self
+                .show_run((rect.x + left, y), line.text, fc, bc, layout, mode)
+                .await;
+            if ellipsis {
+                self.show_run((x, y), ELLIPSIS, fc, bc, layout, mode).await;
+            }
+
+            y += line_height;
+        }
+
+        if clear {
+            self.fill_rect(
+                Rect::new(rect.x, y, rect.w, rect.bottom().saturating_sub(y)),
+                bc,
+            )
+            .await;
+        }
+
+        metrics
+    }
+
+    /// Draws `s` on a single line starting at `(x, y)` and returns the x
+    /// position following the last glyph.
+    async fn show_run(
+        &mut self, (mut x, y): (u16, u16), s: &str, fc: u16, bc: u16, layout: &TextLayout,
+        mode: CharMode,
+    ) -> u16 {
+        for ch in s.chars() {
+            let w = layout.char_width(ch);
+            if w == 0 {
+                continue;
+            }
+            self.show_glyph((x, y), ch, fc, bc, layout, mode).await;
+            x += w;
+        }
+        x
+    }
+
+    /// Draws a single character bottom-aligned in a line of
+    /// [`TextLayout::line_height`] pixels.
+    async fn show_glyph(
+        &mut self, (x, y): (u16, u16), ch: char, fc: u16, bc: u16, layout: &TextLayout,
+        mode: CharMode,
+    ) {
+        let (w, h) = if ch.is_ascii() {
+            (layout.font.x().as_u16(), layout.font.y().as_u16())
+        } else {
+            (
+                layout.chinese_font.x().as_u16(),
+                layout.chinese_font.y().as_u16(),
+            )
+        };
+        let offset = layout.line_height() - h;
+        if matches!(mode, CharMode::NonOverlay) {
+            self.fill_rect(Rect::new(x, y, w, offset), bc).await;
+        }
+
+        let style = &GlyphStyle::new(fc, bc).mode(mode);
+        if ch.is_ascii() {
+            self.show_char((x, y + offset), ch, layout.font, style)
+                .await;
+        } else {
+            self.show_chinese_char((x, y + offset), ch, layout.chinese_font, style)
+                .await;
+        }
+    }
+}
@@ -0,0 +1,313 @@
+//! Numeric formatting on top of `core::fmt`.
+
+use super::{LCD, font::FontSize, layout::HAlign, style::GlyphStyle};
+use core::fmt::{self, Write};
+use embassy_stm32::timer;
+
+/// Longest text [`LCD::show_number`] can draw.
+pub const NUMBER_MAX_LEN: usize = 48;
+
+/// Most digits after the decimal point, all a `u32` can have.
+pub const NUMBER_MAX_DECIMALS: u8 = 10;
+
+#[derive(Clone, Copy)]
+pub enum Number {
+    I32(i32),
+    U32(u32),
+    F32(f32),
+}
+
+impl From<i32> for Number {
+    fn from(value: i32) -> Self {
+        Self::I32(value)
+    }
+}
+
+impl From<u32> for Number {
+    fn from(value: u32) -> Self {
+        Self::U32(value)
+    }
+}
+
+impl From<u16> for Number {
+    fn from(value: u16) -> Self {
+        Self::U32(value.into())
+    }
+}
+
+impl From<u8> for Number {
+    fn from(value: u8) -> Self {
+        Self::U32(value.into())
+    }
+}
+
+impl From<f32> for Number {
+    fn from(value: f32) -> Self {
+        Self::F32(value)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Radix {
+    Decimal,
+    /// Lower case hexadecimal, negative integers are shown in two's
+    /// complement. Floats are always shown in decimal.
+    Hex,
+    UpperHex,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Pad {
+    Space,
+    /// Zeros are inserted after the sign, only when right aligned.
+    Zero,
+}
+
+#[derive(Clone, Copy)]
+pub struct NumberFormat {
+    pub radix: Radix,
+    /// Digits after the decimal point, at most [`NUMBER_MAX_DECIMALS`].
+    /// Integers are treated as fixed-point values scaled by `10^decimals`.
+    pub decimals: u8,
+    /// Minimum number of characters, 0 to use exactly as many as needed.
+    pub width: u8,
+    pub pad: Pad,
+    /// Always show the sign, also for positive numbers.
+    pub sign: bool,
+    pub align: HAlign,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NumberFormat {
+    pub const fn new() -> Self {
+        Self {
+            radix: Radix::Decimal,
+            decimals: 0,
+            width: 0,
+            pad: Pad::Space,
+            sign: false,
+            align: HAlign::Right,
+        }
+    }
+
+    pub const fn radix(mut self, radix: Radix) -> Self {
+        self.radix = radix;
+        self
+    }
+
+    pub const fn decimals(mut self, decimals: u8) -> Self {
+        self.decimals = decimals;
+        self
+    }
+
+    pub const fn width(mut self, width: u8) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub const fn pad(mut self, pad: Pad) -> Self {
+        self.pad = pad;
+        self
+    }
+
+    pub const fn sign(mut self, sign: bool) -> Self {
+        self.sign = sign;
+        self
+    }
+
+    pub const fn align(mut self, align: HAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Formats `num` into `buf`, replacing its contents. Text that does not
+    /// fit into `buf` is shown as `#`.
+    // Inlined so that callers formatting only integers leave out the float
+    // code
+    #[inline]
+    pub fn format<const N: usize>(&self, num: Number, buf: &mut FmtBuf<N>) {
+        let mut digits = FmtBuf::<N>::new();
+        let result = match num {
+            Number::F32(v) => self.write_float(&mut digits, v),
+            Number::I32(v) => self.write_integer(&mut digits, v < 0, v.unsigned_abs(), v as u32),
+            Number::U32(v) => self.write_integer(&mut digits, false, v, v),
+        };
+        self.fill(result, &mut digits, buf);
+    }
+
+    /// Pads `digits` to the width into `buf`, or `#` if writing them failed.
+    fn fill<const N: usize>(
+        &self, result: fmt::Result, digits: &mut FmtBuf<N>, buf: &mut FmtBuf<N>,
+    ) {
+        if result.is_err() {
+            digits.clear();
+            let _ = digits.write_char('#');
+        }
+        // Everything written is ASCII, so bytes are characters
+        let digits = digits.as_bytes();
+        let pad = (self.width as usize).saturating_sub(digits.len());
+        let (left, right) = match self.align {
+            HAlign::Left => (0, pad),
+            HAlign::Center => (pad / 2, pad - pad / 2),
+            HAlign::Right => (pad, 0),
+        };
+        let zeros = self.pad == Pad::Zero && self.align == HAlign::Right;
+        let (sign, rest) = match digits.first() {
+            Some(b'+' | b'-') if zeros => digits.split_at(1),
+            _ => (&[][..], digits),
+        };
+
+        buf.clear();
+        let result = buf
+            .push(sign)
+            .and_then(|_| buf.repeat(if zeros { b'0' } else { b' ' }, left))
+            .and_then(|_| buf.push(rest))
+            .and_then(|_| buf.repeat(b' ', right));
+        if result.is_err() {
+            buf.clear();
+        }
+    }
+
+    /// An integer given as sign and magnitude for decimal, and as its two's
+    /// complement `bits` for hexadecimal.
+    fn write_integer(
+        &self, f: &mut impl Write, negative: bool, abs: u32, bits: u32,
+    ) -> fmt::Result {
+        match self.radix {
+            Radix::Hex => write!(f, "{bits:x}"),
+            Radix::UpperHex => write!(f, "{bits:X}"),
+            Radix::Decimal => self.write_fixed(f, negative, abs.into()),
+        }
+    }
+
+    /// Rounds `v` to a fixed-point integer, so floats never go through
+    /// `core::fmt`, whose float formatting takes tens of KB of flash.
+    fn write_float(&self, f: &mut impl Write, v: f32) -> fmt::Result {
+        if v.is_nan() {
+            return f.write_str("NaN");
+        }
+        let negative = v < 0.0;
+        let magnitude = if negative { -v } else { v };
+        if magnitude.is_infinite() {
+            self.write_sign(f, negative)?;
+            return f.write_str("inf");
+        }
+        let scaled = magnitude * 10u64.pow(self.clamped_decimals().into()) as f32 + 0.5;
+        if scaled >= u64::MAX as f32 {
+            return Err(fmt::Error);
+        }
+        // Rounds half away from zero
+        let abs = scaled as u64;
+        self.write_fixed(f, negative && abs != 0, abs)
+    }
+
+    fn write_fixed(&self, f: &mut impl Write, negative: bool, abs: u64) -> fmt::Result {
+        self.write_sign(f, negative)?;
+        let decimals = self.clamped_decimals();
+        if decimals == 0 {
+            return write!(f, "{abs}");
+        }
+        let scale = 10u64.pow(decimals.into());
+        let decimals = usize::from(decimals);
+        write!(f, "{}.{:0decimals$}", abs / scale, abs % scale)
+    }
+
+    fn write_sign(&self, f: &mut impl Write, negative: bool) -> fmt::Result {
+        if negative {
+            f.write_char('-')
+        } else if self.sign {
+            f.write_char('+')
+        } else {
+            Ok(())
+        }
+    }
+
+    fn clamped_decimals(&self) -> u8 {
+        self.decimals.min(NUMBER_MAX_DECIMALS)
+    }
+}
+
+/// Fixed-capacity string that can be written to with `core::fmt`.
+pub struct FmtBuf<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Default for FmtBuf<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> FmtBuf<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Appends `bytes`, which have to end on a character boundary.
+    fn push(&mut self, bytes: &[u8]) -> fmt::Result {
+        let end = self.len + bytes.len();
+        if end > N {
+            return Err(fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+
+    fn repeat(&mut self, byte: u8, count: usize) -> fmt::Result {
+        for _ in 0..count {
+            self.push(&[byte])?;
+        }
+        Ok(())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    pub fn as_str(&self) -> &str {
+        // Only whole `&str`s are ever copied in
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or_default()
+    }
+}
+
+impl<const N: usize> Write for FmtBuf<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push(s.as_bytes())
+    }
+}
+
+impl<C: timer::GeneralInstance4Channel> LCD<C> {
+    /// Draws `num` formatted by `format` and returns the width drawn in
+    /// pixels.
+    pub async fn show_number(
+        &mut self, (x, y): (u16, u16), num: impl Into<Number>, format: &NumberFormat, fc: u16,
+        bc: u16, size: FontSize,
+    ) -> u16 {
+        let mut buf = FmtBuf::<NUMBER_MAX_LEN>::new();
+        format.format(num.into(), &mut buf);
+
+        // The text is ASCII, drawn byte by byte
+        let style = GlyphStyle::new(fc, bc);
+        let mut width = 0;
+        for &b in buf.as_bytes() {
+            width += self
+                .show_char((x + width, y), b as char, size, &style)
+                .await;
+        }
+        width
+    }
+}
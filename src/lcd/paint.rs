@@ -0,0 +1,173 @@
+//! Gradient and pattern fills.
+//!
+//! [`LCD::fill_paint`] computes a [`Paint`] one row at a time into a single
+//! row buffer, so any rectangle up to the whole panel can be filled without
+//! a frame buffer. Patterns and gradients are anchored to the rectangle being
+//! filled, not to the panel.
+
+use super::{HEIGHT, LCD, Rect, WIDTH, blend};
+use as_what::{AsU16, AsU32, AsUsize};
+use embassy_stm32::timer;
+
+/// Direction in which the colors change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum Axis {
+    /// Left to right
+    Horizontal,
+    /// Top to bottom
+    Vertical,
+    /// Top left to bottom right
+    Diagonal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Paint {
+    Solid(u16),
+    /// `from` at the start of the rectangle to `to` at its end along `axis`.
+    Linear {
+        from: u16,
+        to: u16,
+        axis: Axis,
+    },
+    /// `center` in the middle of the rectangle to `edge` at its corners.
+    Radial {
+        center: u16,
+        edge: u16,
+    },
+    /// Squares of `size` pixels, `a` in the top left one.
+    Checkerboard {
+        a: u16,
+        b: u16,
+        size: u8,
+    },
+    /// Bands of `width` pixels alternating along `axis`, starting with `a`.
+    Stripes {
+        a: u16,
+        b: u16,
+        width: u8,
+        axis: Axis,
+    },
+    /// Repeating 8x8 bitmap, one byte per row with the most significant bit
+    /// on the left, set bits drawn in `fg`.
+    Tile {
+        bits: [u8; 8],
+        fg: u16,
+        bg: u16,
+    },
+}
+
+impl Paint {
+    /// Writes the colors of row `y` of a `w x h` rectangle, starting at
+    /// column `x`, into `row`.
+    fn row(&self, row: &mut [u16], x: u16, y: u16, (w, h): (u16, u16)) {
+        match *self {
+            Paint::Solid(color) => row.fill(color),
+            Paint::Linear { from, to, axis } => {
+                let span = match axis {
+                    Axis::Horizontal => w - 1,
+                    Axis::Vertical => h - 1,
+                    Axis::Diagonal => w + h - 2,
+                };
+                for (i, pixel) in row.iter_mut().enumerate() {
+                    let pos = match axis {
+                        Axis::Horizontal => x + i.as_u16(),
+                        Axis::Vertical => y,
+                        Axis::Diagonal => x + i.as_u16() + y,
+                    };
+                    *pixel = lerp(from, to, pos.as_u32(), span.as_u32());
+                }
+            }
+            Paint::Radial { center, edge } => {
+                // Distances doubled, so that the center of an even sized
+                // rectangle falls between pixels
+                let dy = (2 * y + 1).abs_diff(h).as_u32();
+                let radius = (w.as_u32().pow(2) + h.as_u32().pow(2)).isqrt();
+                for (i, pixel) in row.iter_mut().enumerate() {
+                    let dx = (2 * (x + i.as_u16()) + 1).abs_diff(w).as_u32();
+                    let dist = (dx * dx + dy * dy).isqrt();
+                    *pixel = lerp(center, edge, dist.min(radius), radius);
+                }
+            }
+            Paint::Checkerboard { a, b, size } => {
+                let size = size.max(1).as_u16();
+                for (i, pixel) in row.iter_mut().enumerate() {
+                    let odd = ((x + i.as_u16()) / size + y / size) & 1 != 0;
+                    *pixel = if odd { b } else { a };
+                }
+            }
+            Paint::Stripes { a, b, width, axis } => {
+                let width = width.max(1).as_u16();
+                for (i, pixel) in row.iter_mut().enumerate() {
+                    let pos = match axis {
+                        Axis::Horizontal => x + i.as_u16(),
+                        Axis::Vertical => y,
+                        Axis::Diagonal => x + i.as_u16() + y,
+                    };
+                    *pixel = if (pos / width) & 1 != 0 { b } else { a };
+                }
+            }
+            Paint::Tile { bits, fg, bg } => {
+                let line = bits[(y % 8).as_usize()];
+                for (i, pixel) in row.iter_mut().enumerate() {
+                    let set = line & (0x80 >> ((x.as_usize() + i) % 8)) != 0;
+                    *pixel = if set { fg } else { bg };
+                }
+            }
+        }
+    }
+}
+
+/// Color `pos / span` of the way from `from` to `to`.
+fn lerp(from: u16, to: u16, pos: u32, span: u32) -> u16 {
+    if span == 0 {
+        return from;
+    }
+    let alpha = (pos.min(span) * 255 + span / 2) / span;
+    blend(to, from, alpha as u8)
+}
+
+impl<C: timer::GeneralInstance4Channel> LCD<C> {
+    /// Fills `rect` with `paint`, clipped to the panel.
+    pub async fn fill_paint(&mut self, rect: Rect, paint: &Paint) {
+        self.fill_paint_clipped(rect, rect, paint).await;
+    }
+
+    /// Fills the part of `rect` inside `clip` with `paint`, laid out as if
+    /// the whole of `rect` was filled.
+    ///
+    /// Redraws a piece of a gradient background, such as the part of a
+    /// progress bar that changed, without repainting the rest.
+    pub async fn fill_paint_clipped(&mut self, rect: Rect, clip: Rect, paint: &Paint) {
+        let area = rect
+            .intersect(&clip)
+            .intersect(&Rect::new(0, 0, WIDTH, HEIGHT));
+        if area.is_empty() {
+            return;
+        }
+        self.set_address(area.x, area.y, area.right() - 1, area.bottom() - 1)
+            .await;
+
+        let mut row = [0u16; WIDTH as usize];
+        let row = &mut row[..area.w.as_usize()];
+        // Only gradients and patterns that change down the rectangle need
+        // more than one row
+        let uniform = matches!(
+            paint,
+            Paint::Solid(_)
+                | Paint::Linear {
+                    axis: Axis::Horizontal,
+                    ..
+                }
+                | Paint::Stripes {
+                    axis: Axis::Horizontal,
+                    ..
+                }
+        );
+        for y in area.y - rect.y..area.bottom() - rect.y {
+            if !uniform || y == area.y - rect.y {
+                paint.row(row, area.x - rect.x, y, (rect.w, rect.h));
+            }
+            self.write_data(row).await;
+        }
+    }
+}
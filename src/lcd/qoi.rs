@@ -0,0 +1,209 @@
+//! Streaming decoder for the "Quite OK Image" format.
+//!
+//! [`QoiDecoder`] keeps only the 64 entry color index and the previous pixel,
+//! and accepts its input in arbitrarily sized pieces, so images can be
+//! decoded straight out of MCU flash or chunk by chunk from external memory.
+//! Pixels are converted to RGB565, the alpha channel is ignored.
+
+use super::{HEIGHT, LCD, WIDTH, rgb565};
+use as_what::{AsU16, AsUsize};
+use embassy_stm32::timer;
+
+pub const QOI_HEADER_SIZE: usize = 14;
+
+const QOI_MAGIC: &[u8; 4] = b"qoif";
+
+const QOI_OP_INDEX: u8 = 0x00;
+const QOI_OP_DIFF: u8 = 0x40;
+const QOI_OP_LUMA: u8 = 0x80;
+const QOI_OP_RUN: u8 = 0xC0;
+const QOI_OP_RGB: u8 = 0xFE;
+const QOI_OP_RGBA: u8 = 0xFF;
+const QOI_MASK_2: u8 = 0xC0;
+
+/// Pixels decoded per chunk by [`LCD::show_qoi`].
+const MAX_ROW: usize = if WIDTH > HEIGHT { WIDTH } else { HEIGHT } as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum QoiError {
+    /// Missing `qoif` magic
+    NotQoi,
+    /// Zero sized image, or larger than 65535 pixels in a dimension
+    Unsupported,
+    /// The data ends before all pixels are decoded
+    Truncated,
+}
+
+pub struct QoiDecoder {
+    width: u16,
+    height: u16,
+    index: [[u8; 4]; 64],
+    px: [u8; 4],
+    run: u8,
+    /// Pixels still to be produced
+    remaining: u32,
+    /// Bytes of a partially received op
+    op: [u8; 5],
+    op_len: usize,
+}
+
+impl QoiDecoder {
+    pub fn new(header: &[u8]) -> Result<Self, QoiError> {
+        if header.len() < QOI_HEADER_SIZE {
+            return Err(QoiError::Truncated);
+        }
+        if &header[..4] != QOI_MAGIC {
+            return Err(QoiError::NotQoi);
+        }
+        let width = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+        let height = u32::from_be_bytes([header[8], header[9], header[10], header[11]]);
+        if width == 0 || height == 0 || width > u16::MAX.into() || height > u16::MAX.into() {
+            return Err(QoiError::Unsupported);
+        }
+
+        Ok(Self {
+            width: width as u16,
+            height: height as u16,
+            index: [[0; 4]; 64],
+            px: [0, 0, 0, 255],
+            run: 0,
+            remaining: width * height,
+            op: [0; 5],
+            op_len: 0,
+        })
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Decodes from `input` (the data following the header) into `out`.
+    ///
+    /// Returns how many bytes of `input` were consumed and how many pixels
+    /// were written. An op split across two calls is buffered internally.
+    pub fn decode(&mut self, input: &[u8], out: &mut [u16]) -> (usize, usize) {
+        let mut consumed = 0;
+        let mut produced = 0;
+
+        while produced < out.len() && self.remaining > 0 {
+            if self.run == 0 {
+                // Collect the bytes of the next op
+                if self.op_len == 0 {
+                    let Some(&first) = input.get(consumed) else {
+                        break;
+                    };
+                    self.op[0] = first;
+                    self.op_len = 1;
+                    consumed += 1;
+                }
+                let need = op_size(self.op[0]);
+                while self.op_len < need {
+                    let Some(&byte) = input.get(consumed) else {
+                        return (consumed, produced);
+                    };
+                    self.op[self.op_len] = byte;
+                    self.op_len += 1;
+                    consumed += 1;
+                }
+                self.op_len = 0;
+                self.execute();
+            } else {
+                self.run -= 1;
+            }
+
+            out[produced] = rgb565(self.px[0], self.px[1], self.px[2]);
+            produced += 1;
+            self.remaining -= 1;
+        }
+
+        (consumed, produced)
+    }
+
+    /// Applies the op in `self.op` to the current pixel. Runs leave the
+    /// pixel alone and set `self.run` to the number of extra repeats.
+    fn execute(&mut self) {
+        let b1 = self.op[0];
+        let px = &mut self.px;
+        match b1 {
+            QOI_OP_RGB => px[..3].copy_from_slice(&self.op[1..4]),
+            QOI_OP_RGBA => px.copy_from_slice(&self.op[1..5]),
+            _ => match b1 & QOI_MASK_2 {
+                QOI_OP_INDEX => *px = self.index[b1.as_usize()],
+                QOI_OP_DIFF => {
+                    px[0] = px[0].wrapping_add((b1 >> 4) & 0x03).wrapping_sub(2);
+                    px[1] = px[1].wrapping_add((b1 >> 2) & 0x03).wrapping_sub(2);
+                    px[2] = px[2].wrapping_add(b1 & 0x03).wrapping_sub(2);
+                }
+                QOI_OP_LUMA => {
+                    let b2 = self.op[1];
+                    let vg = (b1 & 0x3f).wrapping_sub(32);
+                    px[0] = px[0].wrapping_add(vg.wrapping_sub(8).wrapping_add((b2 >> 4) & 0x0f));
+                    px[1] = px[1].wrapping_add(vg);
+                    px[2] = px[2].wrapping_add(vg.wrapping_sub(8).wrapping_add(b2 & 0x0f));
+                }
+                QOI_OP_RUN => self.run = b1 & 0x3f,
+                _ => unreachable!(),
+            },
+        }
+
+        let [r, g, b, a] = *px;
+        let hash = (r as usize * 3 + g as usize * 5 + b as usize * 7 + a as usize * 11) % 64;
+        self.index[hash] = *px;
+    }
+}
+
+fn op_size(b1: u8) -> usize {
+    match b1 {
+        QOI_OP_RGB => 4,
+        QOI_OP_RGBA => 5,
+        _ if b1 & QOI_MASK_2 == QOI_OP_LUMA => 2,
+        _ => 1,
+    }
+}
+
+impl<C: timer::GeneralInstance4Channel> LCD<C> {
+    /// Decodes a QOI image held in memory and draws it with its top left
+    /// corner at `(x, y)`, clipped to the panel. Every row is sent to the
+    /// panel as one transfer.
+    pub async fn show_qoi(&mut self, (x, y): (u16, u16), data: &[u8]) -> Result<(), QoiError> {
+        let mut decoder = QoiDecoder::new(data)?;
+        if x >= WIDTH || y >= HEIGHT {
+            return Ok(());
+        }
+        let (width, height) = (decoder.width(), decoder.height());
+        let w = width.min(WIDTH - x);
+        let h = height.min(HEIGHT - y);
+        self.set_address(x, y, x + w - 1, y + h - 1).await;
+
+        let mut input = &data[QOI_HEADER_SIZE..];
+        let mut row = [0u16; MAX_ROW];
+        for _ in 0..h {
+            // Decode the visible part of the row in one go, then skip the
+            // clipped remainder
+            let mut column = 0;
+            while column < width {
+                let chunk = (width - column).min(MAX_ROW.as_u16()).as_usize();
+                let (consumed, produced) = decoder.decode(input, &mut row[..chunk]);
+                input = &input[consumed..];
+                if produced < chunk {
+                    return Err(QoiError::Truncated);
+                }
+                if column < w {
+                    let visible = (w - column).as_usize().min(chunk);
+                    self.write_data(&row[..visible]).await;
+                }
+                column += chunk.as_u16();
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,672 @@
+//! QR code encoder and renderer.
+//!
+//! Data is encoded in byte mode, with the smallest version that holds it at
+//! the requested error correction level. Versions are limited to those that
+//! still fit on the 80 pixel side of the panel with one pixel per module and
+//! the quiet zone, and the code is built in fixed size buffers on the stack.
+//! The encoder follows Project Nayuki's QR Code generator, including its mask
+//! penalty rules, but keeps the requested error correction level where that
+//! generator raises it as far as the version allows, so their symbols only
+//! match when the level is not raised.
+
+use super::LCD;
+use embassy_stm32::timer;
+
+/// Largest version whose symbol, quiet zone included, fits into 80 pixels.
+pub const QR_MAX_VERSION: u8 = 13;
+pub const QR_MAX_SIZE: usize = QR_MAX_VERSION as usize * 4 + 17;
+/// Light modules required around the symbol.
+pub const QR_QUIET_ZONE: u16 = 4;
+
+const GRID_BYTES: usize = (QR_MAX_SIZE * QR_MAX_SIZE).div_ceil(8);
+/// Data and error correction codewords of the largest version.
+const MAX_CODEWORDS: usize = num_raw_data_modules(QR_MAX_VERSION) / 8;
+const MAX_ECC_LEN: usize = 30;
+
+const PENALTY_N1: i32 = 3;
+const PENALTY_N2: i32 = 3;
+const PENALTY_N3: i32 = 40;
+const PENALTY_N4: i32 = 10;
+
+/// Error correction codewords per block, by level and version.
+const ECC_CODEWORDS_PER_BLOCK: [[u8; QR_MAX_VERSION as usize + 1]; 4] = [
+    [0, 7, 10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26],
+    [0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22],
+    [0, 13, 22, 18, 26, 18, 24, 18, 22, 20, 24, 28, 26, 24],
+    [0, 17, 28, 22, 16, 22, 28, 26, 26, 24, 28, 24, 28, 22],
+];
+
+/// Error correction blocks, by level and version.
+const NUM_ERROR_CORRECTION_BLOCKS: [[u8; QR_MAX_VERSION as usize + 1]; 4] = [
+    [0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 4, 4, 4, 4],
+    [0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9],
+    [0, 1, 1, 2, 2, 4, 4, 6, 6, 8, 8, 8, 10, 12],
+    [0, 1, 1, 2, 4, 4, 4, 5, 6, 8, 8, 11, 11, 16],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum QrError {
+    /// The data does not fit into [`QR_MAX_VERSION`] at the requested level
+    DataTooLong,
+}
+
+/// Error correction level, the share of codewords that can be restored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum QrEcc {
+    /// About 7%
+    Low,
+    /// About 15%
+    Medium,
+    /// About 25%
+    Quartile,
+    /// About 30%
+    High,
+}
+
+impl QrEcc {
+    fn ordinal(self) -> usize {
+        self as usize
+    }
+
+    fn format_bits(self) -> u32 {
+        match self {
+            QrEcc::Low => 1,
+            QrEcc::Medium => 0,
+            QrEcc::Quartile => 3,
+            QrEcc::High => 2,
+        }
+    }
+}
+
+/// Square bit matrix, one bit per module.
+#[derive(Clone)]
+struct Grid {
+    size: usize,
+    bits: [u8; GRID_BYTES],
+}
+
+impl Grid {
+    fn new(size: usize) -> Self {
+        Self {
+            size,
+            bits: [0; GRID_BYTES],
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        let i = y * self.size + x;
+        self.bits[i / 8] & (1 << (i % 8)) != 0
+    }
+
+    fn set(&mut self, x: usize, y: usize, dark: bool) {
+        let i = y * self.size + x;
+        if dark {
+            self.bits[i / 8] |= 1 << (i % 8);
+        } else {
+            self.bits[i / 8] &= !(1 << (i % 8));
+        }
+    }
+}
+
+/// Encoded QR code symbol.
+#[derive(Clone)]
+pub struct QrCode {
+    version: u8,
+    ecc: QrEcc,
+    mask: u8,
+    modules: Grid,
+}
+
+impl QrCode {
+    /// Encodes `data` in byte mode with the smallest version that fits.
+    pub fn encode(data: &[u8], ecc: QrEcc) -> Result<Self, QrError> {
+        let mut version = 1;
+        let used_bits = loop {
+            let count_bits = if version <= 9 { 8 } else { 16 };
+            let bits = 4 + count_bits + data.len() * 8;
+            if data.len() < 1 << count_bits && bits <= num_data_codewords(version, ecc) * 8 {
+                break bits;
+            }
+            if version == QR_MAX_VERSION {
+                return Err(QrError::DataTooLong);
+            }
+            version += 1;
+        };
+
+        // Mode indicator, character count, data, terminator and padding
+        let capacity = num_data_codewords(version, ecc);
+        let mut codewords = BitWriter::new();
+        codewords.push(0b0100, 4);
+        codewords.push(data.len() as u32, if version <= 9 { 8 } else { 16 });
+        for &byte in data {
+            codewords.push(byte.into(), 8);
+        }
+        codewords.push(0, (capacity * 8 - used_bits).min(4));
+        codewords.push(0, codewords.len.wrapping_neg() & 7);
+        for pad in [0xEC, 0x11].into_iter().cycle() {
+            if codewords.len >= capacity * 8 {
+                break;
+            }
+            codewords.push(pad, 8);
+        }
+
+        let size = usize::from(version) * 4 + 17;
+        let mut qr = Self {
+            version,
+            ecc,
+            mask: 0,
+            modules: Grid::new(size),
+        };
+        let mut function = Grid::new(size);
+        qr.draw_function_patterns(&mut function);
+        let all = qr.add_ecc_and_interleave(&codewords.bytes[..capacity]);
+        qr.draw_codewords(&all[..num_raw_data_modules(version) / 8], &function);
+
+        // Pick the mask with the lowest penalty
+        let mut best = (i32::MAX, 0);
+        for mask in 0..8 {
+            qr.apply_mask(mask, &function);
+            qr.draw_format_bits(mask, &mut function);
+            let penalty = qr.penalty_score();
+            if penalty < best.0 {
+                best = (penalty, mask);
+            }
+            qr.apply_mask(mask, &function);
+        }
+        qr.mask = best.1;
+        qr.apply_mask(qr.mask, &function);
+        qr.draw_format_bits(qr.mask, &mut function);
+
+        Ok(qr)
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn ecc(&self) -> QrEcc {
+        self.ecc
+    }
+
+    pub fn mask(&self) -> u8 {
+        self.mask
+    }
+
+    /// Modules per side, without the quiet zone.
+    pub fn size(&self) -> u16 {
+        self.modules.size as u16
+    }
+
+    /// Whether the module at `(x, y)` is dark, light outside the symbol.
+    pub fn get(&self, x: u16, y: u16) -> bool {
+        let (x, y) = (usize::from(x), usize::from(y));
+        x < self.modules.size && y < self.modules.size && self.modules.get(x, y)
+    }
+
+    /// Pixels per side with `module_px` pixels per module, quiet zone
+    /// included.
+    pub fn side_px(&self, module_px: u16) -> u16 {
+        (self.size() + QR_QUIET_ZONE * 2) * module_px
+    }
+
+    fn set_function(&mut self, function: &mut Grid, x: usize, y: usize, dark: bool) {
+        self.modules.set(x, y, dark);
+        function.set(x, y, true);
+    }
+
+    fn draw_function_patterns(&mut self, function: &mut Grid) {
+        let size = self.modules.size;
+        // Timing patterns
+        for i in 0..size {
+            self.set_function(function, 6, i, i % 2 == 0);
+            self.set_function(function, i, 6, i % 2 == 0);
+        }
+
+        // Finder patterns with their separators
+        for (cx, cy) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            for dy in -4..=4isize {
+                for dx in -4..=4isize {
+                    let (x, y) = (cx as isize + dx, cy as isize + dy);
+                    if (0..size as isize).contains(&x) && (0..size as isize).contains(&y) {
+                        let dist = dx.abs().max(dy.abs());
+                        self.set_function(function, x as usize, y as usize, dist != 2 && dist != 4);
+                    }
+                }
+            }
+        }
+
+        // Alignment patterns, except on the finder corners
+        let (positions, count) = alignment_positions(self.version);
+        for i in 0..count {
+            for j in 0..count {
+                let last = count - 1;
+                if (i, j) == (0, 0) || (i, j) == (0, last) || (i, j) == (last, 0) {
+                    continue;
+                }
+                for dy in -2..=2isize {
+                    for dx in -2..=2isize {
+                        let x = (positions[i] as isize + dx) as usize;
+                        let y = (positions[j] as isize + dy) as usize;
+                        self.set_function(function, x, y, dx.abs().max(dy.abs()) != 1);
+                    }
+                }
+            }
+        }
+
+        // Reserve the format bits, drawn for real once the mask is known
+        self.draw_format_bits(0, function);
+        self.draw_version(function);
+    }
+
+    fn draw_format_bits(&mut self, mask: u8, function: &mut Grid) {
+        let data = self.ecc.format_bits() << 3 | u32::from(mask);
+        let mut rem = data;
+        for _ in 0..10 {
+            rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+        }
+        let bits = (data << 10 | rem) ^ 0x5412;
+        let bit = |i: usize| (bits >> i) & 1 != 0;
+
+        // First copy, around the top left finder
+        for i in 0..6 {
+            self.set_function(function, 8, i, bit(i));
+        }
+        self.set_function(function, 8, 7, bit(6));
+        self.set_function(function, 8, 8, bit(7));
+        self.set_function(function, 7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(function, 14 - i, 8, bit(i));
+        }
+
+        // Second copy, split between the other two finders
+        let size = self.modules.size;
+        for i in 0..8 {
+            self.set_function(function, size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(function, 8, size - 15 + i, bit(i));
+        }
+        self.set_function(function, 8, size - 8, true);
+    }
+
+    fn draw_version(&mut self, function: &mut Grid) {
+        if self.version < 7 {
+            return;
+        }
+        let data = u32::from(self.version);
+        let mut rem = data;
+        for _ in 0..12 {
+            rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+        }
+        let bits = data << 12 | rem;
+
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 != 0;
+            let a = self.modules.size - 11 + i % 3;
+            let b = i / 3;
+            self.set_function(function, a, b, dark);
+            self.set_function(function, b, a, dark);
+        }
+    }
+
+    /// Splits the data codewords into blocks, appends the Reed-Solomon
+    /// codewords of each and interleaves the blocks.
+    fn add_ecc_and_interleave(&self, data: &[u8]) -> [u8; MAX_CODEWORDS] {
+        let (version, level) = (usize::from(self.version), self.ecc.ordinal());
+        let num_blocks = usize::from(NUM_ERROR_CORRECTION_BLOCKS[level][version]);
+        let ecc_len = usize::from(ECC_CODEWORDS_PER_BLOCK[level][version]);
+        let raw_codewords = num_raw_data_modules(self.version) / 8;
+        let num_short_blocks = num_blocks - raw_codewords % num_blocks;
+        let short_block_len = raw_codewords / num_blocks;
+        let data_len =
+            |block: usize| short_block_len - ecc_len + usize::from(block >= num_short_blocks);
+
+        let mut divisor = [0u8; MAX_ECC_LEN];
+        let divisor = reed_solomon_divisor(&mut divisor[..ecc_len]);
+        let mut ecc = [0u8; MAX_CODEWORDS];
+        let mut start = 0;
+        for block in 0..num_blocks {
+            let end = start + data_len(block);
+            reed_solomon_remainder(
+                &data[start..end],
+                divisor,
+                &mut ecc[block * ecc_len..][..ecc_len],
+            );
+            start = end;
+        }
+
+        let mut result = [0u8; MAX_CODEWORDS];
+        let mut n = 0;
+        for i in 0..short_block_len - ecc_len + 1 {
+            let mut start = 0;
+            for block in 0..num_blocks {
+                if i < data_len(block) {
+                    result[n] = data[start + i];
+                    n += 1;
+                }
+                start += data_len(block);
+            }
+        }
+        for i in 0..ecc_len {
+            for block in 0..num_blocks {
+                result[n] = ecc[block * ecc_len + i];
+                n += 1;
+            }
+        }
+        result
+    }
+
+    /// Places the codewords in the zigzag order over the non-function
+    /// modules.
+    fn draw_codewords(&mut self, data: &[u8], function: &Grid) {
+        let size = self.modules.size;
+        let mut i = 0;
+        let mut right = size - 1;
+        loop {
+            if right == 6 {
+                right = 5;
+            }
+            for vert in 0..size {
+                for j in 0..2 {
+                    let x = right - j;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward { size - 1 - vert } else { vert };
+                    if !function.get(x, y) && i < data.len() * 8 {
+                        self.modules
+                            .set(x, y, (data[i >> 3] >> (7 - (i & 7))) & 1 != 0);
+                        i += 1;
+                    }
+                }
+            }
+            if right < 3 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: u8, function: &Grid) {
+        let size = self.modules.size;
+        for y in 0..size {
+            for x in 0..size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                if invert && !function.get(x, y) {
+                    let dark = self.modules.get(x, y);
+                    self.modules.set(x, y, !dark);
+                }
+            }
+        }
+    }
+
+    fn penalty_score(&self) -> i32 {
+        let size = self.modules.size;
+        let mut result = 0;
+
+        // Runs of the same color and finder-like patterns, in rows and then
+        // in columns
+        for transpose in [false, true] {
+            for a in 0..size {
+                let module = |b: usize| {
+                    if transpose {
+                        self.modules.get(a, b)
+                    } else {
+                        self.modules.get(b, a)
+                    }
+                };
+                let mut run_color = false;
+                let mut run = 0;
+                let mut history = FinderPenalty::new(size as i32);
+                for b in 0..size {
+                    if module(b) == run_color {
+                        run += 1;
+                        if run == 5 {
+                            result += PENALTY_N1;
+                        } else if run > 5 {
+                            result += 1;
+                        }
+                    } else {
+                        history.add(run);
+                        if !run_color {
+                            result += history.count_patterns() * PENALTY_N3;
+                        }
+                        run_color = module(b);
+                        run = 1;
+                    }
+                }
+                result += history.terminate_and_count(run_color, run) * PENALTY_N3;
+            }
+        }
+
+        // 2x2 blocks of the same color
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let color = self.modules.get(x, y);
+                if color == self.modules.get(x + 1, y)
+                    && color == self.modules.get(x, y + 1)
+                    && color == self.modules.get(x + 1, y + 1)
+                {
+                    result += PENALTY_N2;
+                }
+            }
+        }
+
+        // Balance of dark and light modules
+        let mut dark = 0;
+        for y in 0..size {
+            for x in 0..size {
+                dark += i32::from(self.modules.get(x, y));
+            }
+        }
+        let total = (size * size) as i32;
+        let k = ((dark * 20 - total * 10).abs() + total - 1) / total - 1;
+        result + k * PENALTY_N4
+    }
+}
+
+/// Big endian bit writer over the data codewords.
+struct BitWriter {
+    bytes: [u8; MAX_CODEWORDS],
+    len: usize,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            bytes: [0; MAX_CODEWORDS],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, value: u32, bits: usize) {
+        for i in (0..bits).rev() {
+            if (value >> i) & 1 != 0 {
+                self.bytes[self.len / 8] |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+}
+
+/// Lengths of the last seven runs of a row or column, newest first.
+struct FinderPenalty {
+    size: i32,
+    history: [i32; 7],
+}
+
+impl FinderPenalty {
+    fn new(size: i32) -> Self {
+        Self {
+            size,
+            history: [0; 7],
+        }
+    }
+
+    fn add(&mut self, mut run: i32) {
+        if self.history[0] == 0 {
+            // Light border before the first run
+            run += self.size;
+        }
+        self.history.copy_within(0..6, 1);
+        self.history[0] = run;
+    }
+
+    /// Finder-like patterns ending at the light run just added.
+    fn count_patterns(&self) -> i32 {
+        let h = &self.history;
+        let n = h[1];
+        let core = n > 0 && h[2] == n && h[3] == n * 3 && h[4] == n && h[5] == n;
+        i32::from(core && h[0] >= n * 4 && h[6] >= n)
+            + i32::from(core && h[6] >= n * 4 && h[0] >= n)
+    }
+
+    fn terminate_and_count(mut self, run_color: bool, mut run: i32) -> i32 {
+        if run_color {
+            self.add(run);
+            run = 0;
+        }
+        // Light border after the last run
+        self.add(run + self.size);
+        self.count_patterns()
+    }
+}
+
+const fn num_raw_data_modules(version: u8) -> usize {
+    let v = version as usize;
+    let mut result = (16 * v + 128) * v + 64;
+    if v >= 2 {
+        let num_align = v / 7 + 2;
+        result -= (25 * num_align - 10) * num_align - 55;
+        if v >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+fn num_data_codewords(version: u8, ecc: QrEcc) -> usize {
+    let (v, level) = (usize::from(version), ecc.ordinal());
+    num_raw_data_modules(version) / 8
+        - usize::from(ECC_CODEWORDS_PER_BLOCK[level][v])
+            * usize::from(NUM_ERROR_CORRECTION_BLOCKS[level][v])
+}
+
+/// Centers of the alignment patterns on either axis, and their count.
+fn alignment_positions(version: u8) -> ([usize; 7], usize) {
+    let mut positions = [0; 7];
+    if version == 1 {
+        return (positions, 0);
+    }
+    let count = usize::from(version) / 7 + 2;
+    let size = usize::from(version) * 4 + 17;
+    let step = (usize::from(version) * 4 + count * 2 + 1) / (count * 2 - 2) * 2;
+    positions[0] = 6;
+    for i in 1..count {
+        positions[count - i] = size - 7 - (i - 1) * step;
+    }
+    (positions, count)
+}
+
+/// Product of two elements of GF(2^8) modulo 0x11D.
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z = 0u8;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x1D);
+        z ^= ((y >> i) & 1) * x;
+    }
+    z
+}
+
+/// Generator polynomial of degree `divisor.len()`, highest power first
+/// without the leading 1.
+fn reed_solomon_divisor(divisor: &mut [u8]) -> &[u8] {
+    let degree = divisor.len();
+    divisor.fill(0);
+    divisor[degree - 1] = 1;
+    let mut root = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            divisor[j] = gf_multiply(divisor[j], root);
+            if j + 1 < degree {
+                divisor[j] ^= divisor[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    divisor
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8], result: &mut [u8]) {
+    result.fill(0);
+    for &byte in data {
+        let factor = byte ^ result[0];
+        result.copy_within(1.., 0);
+        let last = result.len() - 1;
+        result[last] = 0;
+        for (x, &y) in result.iter_mut().zip(divisor) {
+            *x ^= gf_multiply(y, factor);
+        }
+    }
+}
+
+impl<C: timer::GeneralInstance4Channel> LCD<C> {
+    /// Encodes `data` and draws it with its top left corner, quiet zone
+    /// included, at `(x, y)`. Returns the side length in pixels.
+    pub async fn draw_qr(
+        &mut self, (x, y): (u16, u16), data: &[u8], ecc: QrEcc, module_px: u16, fc: u16, bc: u16,
+    ) -> Result<u16, QrError> {
+        let qr = QrCode::encode(data, ecc)?;
+        Ok(self.draw_qr_code((x, y), &qr, module_px, fc, bc).await)
+    }
+
+    /// Draws `qr` with `module_px` pixels per module, dark modules in `fc`
+    /// and light ones and the quiet zone in `bc`. Every run of modules of
+    /// the same color in a row is filled through one address window.
+    pub async fn draw_qr_code(
+        &mut self, (x, y): (u16, u16), qr: &QrCode, module_px: u16, fc: u16, bc: u16,
+    ) -> u16 {
+        let module_px = module_px.max(1);
+        let side = qr.side_px(module_px);
+        let quiet = QR_QUIET_ZONE * module_px;
+        let modules = qr.size() + QR_QUIET_ZONE * 2;
+
+        self.fill_window((x, y), (side, quiet), bc).await;
+        for row in 0..qr.size() {
+            let row_y = y + quiet + row * module_px;
+            let mut start = 0;
+            while start < modules {
+                let dark = is_dark(qr, start, row);
+                let mut end = start + 1;
+                while end < modules && is_dark(qr, end, row) == dark {
+                    end += 1;
+                }
+                let color = if dark { fc } else { bc };
+                let run_x = x + start * module_px;
+                self.fill_window(
+                    (run_x, row_y),
+                    ((end - start) * module_px, module_px),
+                    color,
+                )
+                .await;
+                start = end;
+            }
+        }
+        self.fill_window((x, y + side - quiet), (side, quiet), bc)
+            .await;
+
+        side
+    }
+}
+
+/// Module `column` of `row`, counting the quiet zone.
+fn is_dark(qr: &QrCode, column: u16, row: u16) -> bool {
+    column >= QR_QUIET_ZONE && qr.get(column - QR_QUIET_ZONE, row)
+}
@@ -0,0 +1,198 @@
+//! One [`LCD`] used from several tasks.
+//!
+//! [`SharedLcd`] puts the LCD behind a mutex. A task that draws several
+//! things which belong together locks it once and draws through the guard,
+//! so no other task can set an address window in between.
+//!
+//! Tasks that only need to draw now and then, like a blinking status icon,
+//! send [`DrawCommand`]s to a [`DrawQueue`] instead, without waiting for the
+//! LCD. A single renderer task executes them in the order they were sent:
+//!
+//! ```ignore
+//! static LCD: SharedLcd<peripherals::TIM4> = SharedLcd::new();
+//! static DRAW: DrawQueue = DrawQueue::new();
+//!
+//! #[embassy_executor::task]
+//! async fn renderer() {
+//!     LCD.run_renderer(&DRAW).await
+//! }
+//!
+//! // In any task
+//! DRAW.send(DrawCommand::Fill(Rect::new(150, 0, 10, 10), Color::Red as u16)).await;
+//! ```
+
+use super::{CharMode, LCD, Rect, layout::TextLayout, number::FmtBuf, paint::Paint};
+use core::fmt::Write;
+use embassy_stm32::timer;
+use embassy_sync::{
+    blocking_mutex::raw::CriticalSectionRawMutex,
+    channel::Channel,
+    mutex::{MappedMutexGuard, Mutex, MutexGuard},
+};
+
+/// Commands buffered before senders wait for the renderer.
+pub const DRAW_QUEUE_LEN: usize = 8;
+
+/// Longest text a [`DrawCommand::Text`] carries, in bytes.
+pub const DRAW_TEXT_MAX_LEN: usize = 24;
+
+pub type DrawQueue = Channel<CriticalSectionRawMutex, DrawCommand, DRAW_QUEUE_LEN>;
+
+pub type LcdGuard<'a, C> = MappedMutexGuard<'a, CriticalSectionRawMutex, LCD<C>>;
+
+/// A drawing operation that owns its data, so it can wait in a
+/// [`DrawQueue`].
+pub enum DrawCommand {
+    Fill(Rect, u16),
+    Paint(Rect, Paint),
+    Point((u16, u16), u16),
+    Line((u16, u16), (u16, u16), u16),
+    /// Outline of the rectangle
+    Rectangle(Rect, u16),
+    Circle((u16, u16), u8, u16),
+    /// Text laid out in `rect`, on `bc` or over what is there for `None`
+    Text {
+        rect: Rect,
+        text: FmtBuf<DRAW_TEXT_MAX_LEN>,
+        fc: u16,
+        bc: Option<u16>,
+        layout: TextLayout,
+    },
+    /// RGB565 picture of `size` at `position`, as for
+    /// [`LCD::show_picture`]
+    Picture {
+        position: (u16, u16),
+        size: (u16, u16),
+        data: &'static [u8],
+    },
+    Brightness(u8),
+}
+
+impl DrawCommand {
+    /// A [`DrawCommand::Text`], cut off after [`DRAW_TEXT_MAX_LEN`] bytes.
+    pub fn text(rect: Rect, text: &str, fc: u16, bc: Option<u16>, layout: TextLayout) -> Self {
+        let mut buf = FmtBuf::new();
+        for ch in text.chars() {
+            if buf.write_char(ch).is_err() {
+                break;
+            }
+        }
+        Self::Text {
+            rect,
+            text: buf,
+            fc,
+            bc,
+            layout,
+        }
+    }
+}
+
+impl<C: timer::GeneralInstance4Channel> LCD<C> {
+    pub async fn execute(&mut self, command: &DrawCommand) {
+        match command {
+            DrawCommand::Fill(rect, color) => self.fill_rect(*rect, *color).await,
+            DrawCommand::Paint(rect, paint) => self.fill_paint(*rect, paint).await,
+            DrawCommand::Point((x, y), color) => self.draw_point(*x, *y, *color).await,
+            DrawCommand::Line((x1, y1), (x2, y2), color) => {
+                self.draw_line(*x1, *y1, *x2, *y2, *color).await
+            }
+            DrawCommand::Rectangle(rect, color) => {
+                if !rect.is_empty() {
+                    let (right, bottom) = (rect.right() - 1, rect.bottom() - 1);
+                    self.draw_rectangle(rect.x, rect.y, right, bottom, *color)
+                        .await;
+                }
+            }
+            DrawCommand::Circle((x, y), r, color) => self.draw_circle(*x, *y, *r, *color).await,
+            DrawCommand::Text {
+                rect,
+                text,
+                fc,
+                bc,
+                layout,
+            } => {
+                let (bc, mode) = match bc {
+                    Some(bc) => (*bc, CharMode::NonOverlay),
+                    None => (0, CharMode::Overlay),
+                };
+                self.show_text(*rect, text.as_str(), *fc, bc, layout, mode)
+                    .await;
+            }
+            DrawCommand::Picture {
+                position,
+                size,
+                data,
+            } => self.show_picture(*position, *size, data).await,
+            DrawCommand::Brightness(percent) => self.set_brightness(*percent),
+        }
+    }
+}
+
+/// An [`LCD`] behind a mutex, to be put in a `static`.
+pub struct SharedLcd<C: timer::GeneralInstance4Channel> {
+    lcd: Mutex<CriticalSectionRawMutex, Option<LCD<C>>>,
+}
+
+impl<C: timer::GeneralInstance4Channel> Default for SharedLcd<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: timer::GeneralInstance4Channel> SharedLcd<C> {
+    /// Empty until [`SharedLcd::init`].
+    pub const fn new() -> Self {
+        Self {
+            lcd: Mutex::new(None),
+        }
+    }
+
+    /// Hands the LCD over, normally right after [`LCD::init`].
+    pub async fn init(&self, lcd: LCD<C>) {
+        *self.lcd.lock().await = Some(lcd);
+    }
+
+    /// Takes the LCD back, e.g. for a
+    /// [`ScreenManager`](crate::screen::ScreenManager).
+    pub async fn take(&self) -> Option<LCD<C>> {
+        self.lcd.lock().await.take()
+    }
+
+    /// Waits until no other task uses the LCD and keeps it for the caller
+    /// until the guard is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Before [`SharedLcd::init`] or after [`SharedLcd::take`].
+    pub async fn lock(&self) -> LcdGuard<'_, C> {
+        MutexGuard::map(self.lcd.lock().await, |lcd| lcd.as_mut().unwrap())
+    }
+
+    /// Like [`SharedLcd::lock`], but `None` instead of waiting when another
+    /// task uses the LCD, or when there is none.
+    pub fn try_lock(&self) -> Option<LcdGuard<'_, C>> {
+        let guard = self.lcd.try_lock().ok()?;
+        if guard.is_none() {
+            return None;
+        }
+        Some(MutexGuard::map(guard, |lcd| lcd.as_mut().unwrap()))
+    }
+
+    /// Executes the commands sent to `queue` in order, forever. Commands
+    /// that are already waiting are drawn under the same lock. Before
+    /// [`SharedLcd::init`] and after [`SharedLcd::take`] commands are
+    /// dropped, so senders never wait for an LCD that is not there.
+    pub async fn run_renderer(&self, queue: &DrawQueue) -> ! {
+        loop {
+            let command = queue.receive().await;
+            let mut guard = self.lcd.lock().await;
+            let Some(lcd) = guard.as_mut() else {
+                continue;
+            };
+            lcd.execute(&command).await;
+            while let Ok(command) = queue.try_receive() {
+                lcd.execute(&command).await;
+            }
+        }
+    }
+}
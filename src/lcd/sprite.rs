@@ -0,0 +1,286 @@
+//! Run-length encoded RGB565 sprites with transparency.
+//!
+//! Produced by `boe-tools sprite`, which takes transparency either from the
+//! PNG alpha channel (a 1-bit mask, alpha below 128 is transparent) or from a
+//! color key. Layout, all integers little endian:
+//!
+//! | Offset | Size | Content                  |
+//! |--------|------|--------------------------|
+//! | 0      | 4    | magic `RSPR`             |
+//! | 4      | 2    | width                    |
+//! | 6      | 2    | height                   |
+//! | 8      | ..   | runs, row after row      |
+//!
+//! Runs never cross the end of a row. Each starts with a header byte:
+//!
+//! - `0nnnnnnn`: `n + 1` transparent pixels
+//! - `10nnnnnn`: `n + 1` literal pixels, followed by `n + 1` colors
+//! - `11nnnnnn`: `n + 1` pixels of the single color that follows
+//!
+//! Sprites in memory are drawn with [`LCD::draw_sprite`]. Those read piece
+//! by piece, out of external flash for instance, go through a
+//! [`SpriteDecoder`] instead.
+
+use super::{HEIGHT, LCD, WIDTH};
+use as_what::{AsU16, AsUsize};
+use embassy_stm32::timer;
+
+pub const SPRITE_MAGIC: &[u8; 4] = b"RSPR";
+pub const SPRITE_HEADER_SIZE: usize = 8;
+
+const RUN_OPAQUE: u8 = 0x80;
+const RUN_REPEAT: u8 = 0x40;
+
+/// Longest opaque span [`LCD::draw_sprite`] writes at once.
+const MAX_SPAN: usize = WIDTH as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum SpriteError {
+    /// Missing `RSPR` magic
+    NotSprite,
+    /// A run is cut off or the runs do not add up to the image size
+    Corrupt,
+}
+
+#[derive(Clone, Copy)]
+enum Run<'a> {
+    Transparent(u16),
+    Literal(&'a [u8]),
+    Repeat(u16, u16),
+}
+
+impl Run<'_> {
+    fn len(&self) -> u16 {
+        match self {
+            Run::Transparent(n) | Run::Repeat(n, _) => *n,
+            Run::Literal(colors) => (colors.len() / 2).as_u16(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Sprite<'a> {
+    width: u16,
+    height: u16,
+    runs: &'a [u8],
+}
+
+impl<'a> Sprite<'a> {
+    /// Checks the header and that the runs cover exactly `width x height`
+    /// pixels.
+    pub fn parse(data: &'a [u8]) -> Result<Self, SpriteError> {
+        if data.len() < SPRITE_HEADER_SIZE || &data[..4] != SPRITE_MAGIC {
+            return Err(SpriteError::NotSprite);
+        }
+        let sprite = Self {
+            width: u16::from_le_bytes([data[4], data[5]]),
+            height: u16::from_le_bytes([data[6], data[7]]),
+            runs: &data[SPRITE_HEADER_SIZE..],
+        };
+
+        let mut runs = sprite.runs;
+        for _ in 0..sprite.height {
+            let mut x = 0;
+            while x < sprite.width {
+                let (run, rest) = next_run(runs).ok_or(SpriteError::Corrupt)?;
+                x = x.checked_add(run.len()).ok_or(SpriteError::Corrupt)?;
+                runs = rest;
+            }
+            if x != sprite.width {
+                return Err(SpriteError::Corrupt);
+            }
+        }
+        if !runs.is_empty() {
+            return Err(SpriteError::Corrupt);
+        }
+
+        Ok(sprite)
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+}
+
+/// Streaming decoder for sprites, fed in arbitrarily sized pieces.
+///
+/// Unlike [`Sprite::parse`] it does not check the runs up front, and it
+/// paints transparent pixels in a background color instead of skipping
+/// them.
+pub struct SpriteDecoder {
+    width: u16,
+    height: u16,
+    bg: u16,
+    /// Pixels still to be produced
+    remaining: u32,
+    /// Header byte of the current run, and its pixels left
+    run: u8,
+    left: u16,
+    /// The next pixel needs a color from the input
+    need_color: bool,
+    /// Bytes of a color, possibly split across two calls
+    color: [u8; 2],
+    color_len: usize,
+}
+
+impl SpriteDecoder {
+    /// Reads the header, transparent pixels come out as `bg`.
+    pub fn new(header: &[u8], bg: u16) -> Result<Self, SpriteError> {
+        if header.len() < SPRITE_HEADER_SIZE || &header[..4] != SPRITE_MAGIC {
+            return Err(SpriteError::NotSprite);
+        }
+        let width = u16::from_le_bytes([header[4], header[5]]);
+        let height = u16::from_le_bytes([header[6], header[7]]);
+
+        Ok(Self {
+            width,
+            height,
+            bg,
+            remaining: u32::from(width) * u32::from(height),
+            run: 0,
+            left: 0,
+            need_color: false,
+            color: [0; 2],
+            color_len: 0,
+        })
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Decodes from `input` (the runs following the header) into `out`.
+    ///
+    /// Returns how many bytes of `input` were consumed and how many pixels
+    /// were written.
+    pub fn decode(&mut self, input: &[u8], out: &mut [u16]) -> (usize, usize) {
+        let mut consumed = 0;
+        let mut produced = 0;
+
+        while produced < out.len() && self.remaining > 0 {
+            if self.left == 0 {
+                let Some(&header) = input.get(consumed) else {
+                    break;
+                };
+                consumed += 1;
+                self.run = header;
+                self.left = if header & RUN_OPAQUE == 0 {
+                    (header & 0x7f).as_u16() + 1
+                } else {
+                    (header & 0x3f).as_u16() + 1
+                };
+                self.need_color = header & RUN_OPAQUE != 0;
+            }
+            while self.need_color && self.color_len < 2 {
+                let Some(&byte) = input.get(consumed) else {
+                    return (consumed, produced);
+                };
+                self.color[self.color_len] = byte;
+                self.color_len += 1;
+                consumed += 1;
+            }
+            self.color_len = 0;
+
+            out[produced] = if self.run & RUN_OPAQUE == 0 {
+                self.bg
+            } else {
+                u16::from_le_bytes(self.color)
+            };
+            produced += 1;
+            self.remaining -= 1;
+            self.left -= 1;
+            // Literal runs carry a color per pixel, repeats only one
+            self.need_color =
+                self.left > 0 && self.run & RUN_REPEAT == 0 && self.run & RUN_OPAQUE != 0;
+        }
+
+        (consumed, produced)
+    }
+}
+
+fn next_run(data: &[u8]) -> Option<(Run<'_>, &[u8])> {
+    let (&header, rest) = data.split_first()?;
+    if header & RUN_OPAQUE == 0 {
+        return Some((Run::Transparent((header & 0x7f).as_u16() + 1), rest));
+    }
+
+    let n = (header & 0x3f).as_usize() + 1;
+    if header & RUN_REPEAT == 0 {
+        let colors = rest.get(..n * 2)?;
+        Some((Run::Literal(colors), &rest[n * 2..]))
+    } else {
+        let color = rest.get(..2)?;
+        Some((
+            Run::Repeat(n.as_u16(), u16::from_le_bytes([color[0], color[1]])),
+            &rest[2..],
+        ))
+    }
+}
+
+impl<C: timer::GeneralInstance4Channel> LCD<C> {
+    /// Draws `sprite` with its top left corner at `(x, y)`, clipped to the
+    /// panel. Transparent pixels are skipped by opening one address window
+    /// per opaque span, so whatever is underneath stays visible.
+    pub async fn draw_sprite(&mut self, x: u16, y: u16, sprite: &Sprite<'_>) {
+        let mut span = [0u16; MAX_SPAN];
+        let mut runs = sprite.runs;
+        // Columns in `u32`, a sprite may reach past `u16::MAX`
+        let clip = |column: u32| column.min(WIDTH.into()) as u16;
+
+        for j in 0..sprite.height {
+            let row_y = match y.checked_add(j) {
+                Some(row_y) if row_y < HEIGHT => row_y,
+                // The remaining rows are below the panel
+                _ => return,
+            };
+            // Start of the pending opaque span, and its length
+            let mut start = u32::from(x);
+            let mut len = 0;
+            let mut column = u32::from(x);
+
+            let row_end = u32::from(x) + u32::from(sprite.width);
+            while column < row_end {
+                // `parse` validated the runs
+                let Some((run, rest)) = next_run(runs) else {
+                    return;
+                };
+                runs = rest;
+
+                match run {
+                    Run::Transparent(n) => {
+                        self.draw_span((clip(start), row_y), &span[..len]).await;
+                        start = column + u32::from(n);
+                        len = 0;
+                    }
+                    Run::Literal(colors) => {
+                        for color in colors.chunks_exact(2) {
+                            if len < MAX_SPAN {
+                                span[len] = u16::from_le_bytes([color[0], color[1]]);
+                                len += 1;
+                            }
+                        }
+                    }
+                    Run::Repeat(n, color) => {
+                        let end = (len + n.as_usize()).min(MAX_SPAN);
+                        span[len..end].fill(color);
+                        len = end;
+                    }
+                }
+                column += u32::from(run.len());
+            }
+            self.draw_span((clip(start), row_y), &span[..len]).await;
+        }
+    }
+}
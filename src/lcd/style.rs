@@ -0,0 +1,223 @@
+//! Rendering of the 1-bit fonts in a [`GlyphStyle`].
+//!
+//! Glyphs are scaled by an integer factor, optionally smoothed with an
+//! EPX-like corner rule, emboldened by striking the glyph a second time one
+//! source pixel to the right, and slanted by shearing rows to the right the
+//! higher they are.
+
+use super::{CharMode, HEIGHT, LCD, WIDTH};
+use as_what::{AsU16, AsUsize};
+use embassy_stm32::timer;
+
+pub const MAX_SCALE: u8 = 4;
+
+/// Widest glyph [`LCD::show_char`] can produce.
+const MAX_STYLED_WIDTH: usize = {
+    let w = 32 * MAX_SCALE as usize;
+    w + MAX_SCALE as usize + w / ITALIC_SLOPE
+};
+
+/// Rows per pixel of italic shear.
+const ITALIC_SLOPE: usize = 4;
+
+/// How [`LCD::show_char`], [`LCD::show_string`] and [`LCD::show_chinese`]
+/// draw glyphs.
+#[derive(Clone, Copy)]
+pub struct GlyphStyle {
+    pub fc: u16,
+    /// Background, not drawn in [`CharMode::Overlay`]
+    pub bc: u16,
+    pub mode: CharMode,
+    /// Integer scale factor, between 1 and [`MAX_SCALE`].
+    pub scale: u8,
+    /// Round the staircase of scaled diagonals.
+    pub smooth: bool,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+impl GlyphStyle {
+    /// `fc` on `bc`, unscaled and upright.
+    pub const fn new(fc: u16, bc: u16) -> Self {
+        Self {
+            fc,
+            bc,
+            mode: CharMode::NonOverlay,
+            scale: 1,
+            smooth: false,
+            bold: false,
+            italic: false,
+        }
+    }
+
+    pub const fn mode(mut self, mode: CharMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub const fn scale(mut self, scale: u8) -> Self {
+        self.scale = if scale == 0 {
+            1
+        } else if scale > MAX_SCALE {
+            MAX_SCALE
+        } else {
+            scale
+        };
+        self
+    }
+
+    pub const fn smooth(mut self, smooth: bool) -> Self {
+        self.smooth = smooth;
+        self
+    }
+
+    pub const fn bold(mut self, bold: bool) -> Self {
+        self.bold = bold;
+        self
+    }
+
+    pub const fn italic(mut self, italic: bool) -> Self {
+        self.italic = italic;
+        self
+    }
+
+    /// Size of a `w x h` glyph once rendered with this style.
+    pub const fn glyph_size(&self, w: u16, h: u16) -> (u16, u16) {
+        let scale = self.scale as u16;
+        let h = h * scale;
+        let mut w = w * scale;
+        if self.bold {
+            w += scale;
+        }
+        if self.italic {
+            w += (h - 1) / ITALIC_SLOPE as u16;
+        }
+        (w, h)
+    }
+}
+
+/// 1-bit glyph laid out like the fonts in [`font`].
+struct Bitmap<'a> {
+    msk: &'a [u8],
+    w: usize,
+    h: usize,
+}
+
+impl Bitmap<'_> {
+    fn get(&self, x: isize, y: isize) -> bool {
+        if x < 0 || y < 0 || x as usize >= self.w || y as usize >= self.h {
+            return false;
+        }
+        let (x, y) = (x as usize, y as usize);
+        let row_bytes = self.w.div_ceil(8);
+        self.msk[y * row_bytes + x / 8] & (0x01 << (x % 8)) != 0
+    }
+
+    /// Pixel of the glyph scaled by `style.scale`.
+    fn scaled(&self, x: isize, y: isize, style: &GlyphStyle) -> bool {
+        if x < 0 || y < 0 {
+            return false;
+        }
+        let n = style.scale as isize;
+        let (sx, sy) = (x / n, y / n);
+        let p = self.get(sx, sy);
+        if !style.smooth || n == 1 {
+            return p;
+        }
+
+        // Position inside the n x n block, and the four neighbors
+        let (i, j) = (x % n, y % n);
+        let t = self.get(sx, sy - 1);
+        let b = self.get(sx, sy + 1);
+        let l = self.get(sx - 1, sy);
+        let r = self.get(sx + 1, sy);
+        let corner = |di: isize, dj: isize| (di + dj) * 2 < n - 1;
+
+        if (corner(i, j) && t == l && t != r && l != b)
+            || (corner(n - 1 - i, j) && t == r && t != l && r != b)
+        {
+            t
+        } else if (corner(i, n - 1 - j) && b == l && b != r && l != t)
+            || (corner(n - 1 - i, n - 1 - j) && b == r && b != l && r != t)
+        {
+            b
+        } else {
+            p
+        }
+    }
+
+    /// Pixel `(x, y)` of the glyph rendered in `style`, `height` being the
+    /// scaled height.
+    fn styled(&self, x: isize, y: isize, height: isize, style: &GlyphStyle) -> bool {
+        let x = if style.italic {
+            x - (height - 1 - y) / ITALIC_SLOPE as isize
+        } else {
+            x
+        };
+        self.scaled(x, y, style) || (style.bold && self.scaled(x - style.scale as isize, y, style))
+    }
+}
+
+impl<C: timer::GeneralInstance4Channel> LCD<C> {
+    /// Draws the `w x h` glyph `msk` of a font in `style` and returns its
+    /// width. What falls outside the panel is cut off.
+    pub(super) async fn draw_glyph(
+        &mut self, (x, y): (u16, u16), msk: &[u8], (w, h): (u16, u16), style: &GlyphStyle,
+    ) -> u16 {
+        let bitmap = &Bitmap {
+            msk,
+            w: w.as_usize(),
+            h: h.as_usize(),
+        };
+        // Clamp a scale that was set through the public field
+        let style = &style.scale(style.scale);
+        let (fc, bc) = (style.fc, style.bc);
+        let (w, h) = style.glyph_size(w, h);
+        if x >= WIDTH || y >= HEIGHT {
+            return w;
+        }
+        let width = w.min(WIDTH - x).as_usize().min(MAX_STYLED_WIDTH);
+        let rows = h.min(HEIGHT - y);
+        let mut row = [0u16; MAX_STYLED_WIDTH];
+
+        match style.mode {
+            CharMode::NonOverlay => {
+                self.set_address(x, y, x + width.as_u16() - 1, y + rows - 1)
+                    .await;
+                for j in 0..rows as isize {
+                    for (i, pixel) in row[..width].iter_mut().enumerate() {
+                        *pixel = if bitmap.styled(i as isize, j, h as isize, style) {
+                            fc
+                        } else {
+                            bc
+                        };
+                    }
+                    self.write_data(&row[..width]).await;
+                }
+            }
+            CharMode::Overlay => {
+                row[..width].fill(fc);
+                // One address window per run of set pixels
+                for j in 0..rows as isize {
+                    let mut i = 0;
+                    while i < width {
+                        if !bitmap.styled(i as isize, j, h as isize, style) {
+                            i += 1;
+                            continue;
+                        }
+                        let start = i;
+                        while i < width && bitmap.styled(i as isize, j, h as isize, style) {
+                            i += 1;
+                        }
+                        let y = y + j as u16;
+                        self.set_address(x + start.as_u16(), y, x + i.as_u16() - 1, y)
+                            .await;
+                        self.write_data(&row[start..i]).await;
+                    }
+                }
+            }
+        }
+
+        w
+    }
+}
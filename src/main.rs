@@ -1,9 +1,19 @@
 #![no_std]
 #![no_main]
 
+pub mod asset;
+pub mod input;
 pub mod lcd;
+pub mod rtc;
+pub mod screen;
+pub mod settings;
+pub mod slideshow;
+pub mod tween;
+pub mod ui;
 pub mod w25qxx;
 
+#[cfg(feature = "demo-slideshow")]
+use asset::ASSET_BASE;
 use defmt::{error, info, warn};
 use defmt_rtt as _;
 use embassy_executor::Spawner;
@@ -21,16 +31,71 @@ use embassy_stm32::{
     timer::simple_pwm::{PwmPin, SimplePwm, SimplePwmChannels},
     usart::{self, Uart},
 };
-use embassy_time::Timer;
-use lcd::{
-    CharMode, Color, LCD,
-    font::{ChineseFontSize, FontSize},
-    pic::G_IMAGE_1,
+#[cfg(feature = "demo-input")]
+use embassy_stm32::{
+    exti::ExtiInput,
+    gpio::Pull,
+    timer::qei::{Qei, QeiPin},
 };
+use embassy_time::Timer;
+#[cfg(feature = "demo-input")]
+use input::{ButtonConfig, DebouncedButton, InputChannel, RotaryEncoder};
+#[cfg(any(feature = "demo-widgets", feature = "demo-status-bar"))]
+use lcd::Rect;
+#[cfg(feature = "demo-status-bar")]
+use lcd::shared::SharedLcd;
+#[cfg(not(feature = "demo-widgets"))]
+use lcd::style::GlyphStyle;
+use lcd::{Color, LCD, pic::G_IMAGE_1};
 use num_enum::TryFromPrimitive;
 use panic_probe as _;
+#[cfg(feature = "demo-status-bar")]
+use rtc::Rtc;
+#[cfg(feature = "demo-slideshow")]
+use slideshow::{SlideshowConfig, Transition};
+use tween::{Easing, Tween};
+use ui::Theme;
+#[cfg(feature = "demo-widgets")]
+use ui::{Icon, IconImage, Label, Panel, Widget};
+#[cfg(feature = "demo-status-bar")]
+use ui::{
+    StatusBar, StatusState,
+    status::{self, STATUS_BAR_HEIGHT},
+};
 use w25qxx::{W25Qxx, W25QxxID};
 
+/// Slideshow of the pictures in the asset bundle, run after the test screen.
+#[cfg(feature = "demo-slideshow")]
+const SLIDESHOW: SlideshowConfig = SlideshowConfig::new()
+    .dwell_ms(3000)
+    .transition(Transition::Slide)
+    .theme(THEME);
+
+/// Backlight breathing up once the slideshow has stopped, and down again
+/// reversed.
+const BREATHE: Tween<u8> = Tween::new(10, 100)
+    .duration_ms(9000)
+    .easing(Easing::EaseInOut);
+
+/// Colors and fonts of everything drawn: the light theme with the red text
+/// of the original test program.
+const THEME: Theme = Theme {
+    fg: Color::Red as u16,
+    ..Theme::LIGHT
+};
+
+/// Events of the key and the encoder.
+#[cfg(feature = "demo-input")]
+static INPUT: InputChannel = InputChannel::new();
+
+/// The LCD once the status bar shares it.
+#[cfg(feature = "demo-status-bar")]
+static LCD: SharedLcd<peripherals::TIM4> = SharedLcd::new();
+
+/// What the status bar shows.
+#[cfg(feature = "demo-status-bar")]
+static STATUS: StatusState = StatusState::new();
+
 bind_interrupts!(struct Irqs {
     USART1 => usart::InterruptHandler<peripherals::USART1>;
 });
@@ -69,6 +134,18 @@ async fn main(spawner: Spawner) {
     let led = Output::new(p.PC13, Level::High, Speed::VeryHigh);
     spawner.spawn(handle_led(led)).unwrap();
 
+    // Input: a key to ground on PA0 and an encoder on TIM3
+    #[cfg(feature = "demo-input")]
+    {
+        let key = ExtiInput::new(p.PA0, p.EXTI0, Pull::Up);
+        let key = DebouncedButton::new(0, key, ButtonConfig::new());
+        spawner.spawn(handle_key(key)).unwrap();
+        let qei = Qei::new(p.TIM3, QeiPin::new_ch1(p.PA6), QeiPin::new_ch2(p.PA7));
+        let encoder = RotaryEncoder::new(qei, 4);
+        spawner.spawn(handle_encoder(encoder)).unwrap();
+        spawner.spawn(log_input()).unwrap();
+    }
+
     let mut uart1 = Uart::new_blocking(p.USART1, p.PA10, p.PA9, Default::default()).unwrap();
     uart1
         .blocking_write(b"stm32f103-tft-board-boe-suite started!\r\n")
@@ -93,8 +170,7 @@ async fn main(spawner: Spawner) {
 
     let mut lcd = LCD::new(lcd_spi, lcd_cs, lcd_res, lcd_blk, lcd_dc);
     lcd.init().await;
-    lcd.fill(0, 0, lcd::WIDTH, lcd::HEIGHT, lcd::Color::White as u16)
-        .await;
+    lcd.fill(0, 0, lcd::WIDTH, lcd::HEIGHT, THEME.bg).await;
 
     // SPI Flash
     let mut w25qxx_spi_config: spi::Config = Default::default();
@@ -166,102 +242,113 @@ async fn main(spawner: Spawner) {
         }
     }
 
+    show_info(&mut lcd, flash_size).await;
+
+    #[cfg(feature = "demo-slideshow")]
     {
-        lcd.show_string(
-            (40, 0),
-            "ABROBOT",
-            Color::Red as u16,
-            Color::White as u16,
-            FontSize::_8x16,
-            CharMode::NonOverlay,
-        )
-        .await;
-        lcd.show_chinese(
-            (100, 0),
-            "电子",
-            Color::Red as u16,
-            Color::White as u16,
-            ChineseFontSize::_16x16,
-            CharMode::NonOverlay,
-        )
-        .await;
-        lcd.show_string(
-            (10, 20),
-            "LCD_W:",
-            Color::Red as u16,
-            Color::White as u16,
-            FontSize::_8x16,
-            CharMode::NonOverlay,
-        )
-        .await;
-        lcd.show_int_num(
-            (58, 20),
-            lcd::WIDTH,
-            3,
-            Color::Red as u16,
-            Color::White as u16,
-            FontSize::_8x16,
-        )
-        .await;
-        lcd.show_string(
-            (10, 40),
-            "LCD_H:",
-            Color::Red as u16,
-            Color::White as u16,
-            FontSize::_8x16,
-            CharMode::NonOverlay,
-        )
-        .await;
-        lcd.show_int_num(
-            (58, 40),
-            lcd::HEIGHT,
-            3,
-            Color::Red as u16,
-            Color::White as u16,
-            FontSize::_8x16,
-        )
-        .await;
-        lcd.show_string(
-            (10, 60),
-            "Flash:",
-            Color::Red as u16,
-            Color::White as u16,
-            FontSize::_8x16,
-            CharMode::NonOverlay,
-        )
-        .await;
-        lcd.show_int_num(
-            (55, 60),
-            flash_size as u16,
-            3,
-            Color::Red as u16,
-            Color::White as u16,
-            FontSize::_8x16,
-        )
-        .await;
-        lcd.show_string(
-            (79, 60),
-            "M!",
-            Color::Red as u16,
-            Color::White as u16,
-            FontSize::_8x16,
-            CharMode::NonOverlay,
-        )
-        .await;
-        lcd.show_picture((100, 20), (40, 40), G_IMAGE_1.as_slice())
-            .await;
+        Timer::after_secs(3).await;
+        let err = slideshow::run(&mut lcd, &mut w25qxx, ASSET_BASE, &SLIDESHOW).await;
+        warn!("幻灯片无法播放: {}", err);
     }
 
-    loop {
-        for percent in 10..=100 {
-            lcd.set_brightness(percent);
-            Timer::after_millis(100).await;
-        }
-        for percent in (11..100).rev() {
-            lcd.set_brightness(percent);
-            Timer::after_millis(100).await;
+    // The clock, flash health and backlight in a status bar drawn by a
+    // task of its own, over the cleared screen
+    #[cfg(feature = "demo-status-bar")]
+    {
+        Timer::after_secs(3).await;
+        lcd.fill(0, 0, lcd::WIDTH, lcd::HEIGHT, THEME.bg).await;
+        STATUS.set_flash(w25qxx.health().await);
+        STATUS.set_brightness(lcd.brightness());
+        LCD.init(lcd).await;
+        spawner.spawn(clock(Rtc::new(p.RTC))).unwrap();
+        spawner.spawn(status_bar()).unwrap();
+        loop {
+            breathe(&BREATHE).await;
+            breathe(&BREATHE.reversed()).await;
         }
     }
+
+    #[cfg(not(feature = "demo-status-bar"))]
+    loop {
+        tween::backlight(&mut lcd, &BREATHE).await;
+        tween::backlight(&mut lcd, &BREATHE.reversed()).await;
+    }
+}
+
+/// Moves the backlight of the shared LCD along `tween` and shows it in the
+/// status bar.
+#[cfg(feature = "demo-status-bar")]
+async fn breathe(tween: &Tween<u8>) {
+    tween
+        .run(async |percent| {
+            LCD.lock().await.set_brightness(percent);
+            STATUS.set_brightness(percent);
+        })
+        .await;
+}
+
+/// The test screen: the size of the panel and the flash.
+#[cfg(not(feature = "demo-widgets"))]
+async fn show_info(lcd: &mut LCD<peripherals::TIM4>, flash_size: u8) {
+    let (fc, bc, font) = (THEME.fg, THEME.bg, THEME.font);
+    let style = &GlyphStyle::new(fc, bc);
+    for (pos, text) in [
+        ((40, 0), "ABROBOT"),
+        ((10, 20), "LCD_W:"),
+        ((10, 40), "LCD_H:"),
+        ((10, 60), "Flash:"),
+        ((79, 60), "M!"),
+    ] {
+        lcd.show_string(pos, text, font, style).await;
+    }
+    lcd.show_chinese((100, 0), "电子", THEME.chinese_font, style)
+        .await;
+    for (pos, num) in [
+        ((58, 20), lcd::WIDTH.into()),
+        ((58, 40), lcd::HEIGHT.into()),
+        ((55, 60), flash_size.into()),
+    ] {
+        lcd.show_int_num(pos, num, 3, fc, bc, font).await;
+    }
+    lcd.show_picture((100, 20), (40, 40), G_IMAGE_1.as_slice())
+        .await;
+}
+
+/// The test screen as a widget tree.
+#[cfg(feature = "demo-widgets")]
+async fn show_info(lcd: &mut LCD<peripherals::TIM4>, flash_size: u8) {
+    let mut width = info_label(Rect::new(10, 20, 72, 16), "");
+    width.set_fmt(format_args!("LCD_W:{:>3}", lcd::WIDTH));
+    let mut height = info_label(Rect::new(10, 40, 72, 16), "");
+    height.set_fmt(format_args!("LCD_H:{:>3}", lcd::HEIGHT));
+    let mut flash = info_label(Rect::new(10, 60, 88, 16), "");
+    flash.set_fmt(format_args!("Flash:{:>3}M!", flash_size));
+
+    let mut info = [
+        Widget::Label(info_label(Rect::new(40, 0, 56, 16), "ABROBOT")),
+        Widget::Label(info_label(Rect::new(100, 0, 32, 16), "电子")),
+        Widget::Label(width),
+        Widget::Label(height),
+        Widget::Label(flash),
+        Widget::Icon(Icon::new(
+            (100, 20),
+            IconImage::Picture {
+                data: G_IMAGE_1.as_slice(),
+                size: (40, 40),
+            },
+        )),
+    ];
+    let mut screen = [Widget::Panel(Panel::new(
+        Rect::new(0, 0, lcd::WIDTH, lcd::HEIGHT),
+        &mut info,
+    ))];
+    ui::set_theme(&mut screen, &THEME);
+    ui::render(lcd, &mut screen).await;
+}
+
+#[cfg(feature = "demo-widgets")]
+fn info_label(rect: Rect, text: &str) -> Label {
+    Label::new(rect, text, THEME.text())
 }
 
 #[embassy_executor::task]
@@ -273,3 +360,38 @@ async fn handle_led(mut led: Output<'static>) {
         Timer::after_millis(500).await;
     }
 }
+
+#[cfg(feature = "demo-input")]
+#[embassy_executor::task]
+async fn handle_key(mut key: DebouncedButton<'static>) {
+    key.run(INPUT.sender()).await
+}
+
+#[cfg(feature = "demo-input")]
+#[embassy_executor::task]
+async fn handle_encoder(mut encoder: RotaryEncoder<'static, peripherals::TIM3>) {
+    encoder.run(INPUT.sender()).await
+}
+
+#[cfg(feature = "demo-input")]
+#[embassy_executor::task]
+async fn log_input() {
+    loop {
+        let event = INPUT.receive().await;
+        info!("Input: {}", event);
+    }
+}
+
+#[cfg(feature = "demo-status-bar")]
+#[embassy_executor::task]
+async fn clock(rtc: Rtc) {
+    status::run_clock(&rtc, &STATUS).await
+}
+
+#[cfg(feature = "demo-status-bar")]
+#[embassy_executor::task]
+async fn status_bar() {
+    let mut bar = StatusBar::new(Rect::new(0, 0, lcd::WIDTH, STATUS_BAR_HEIGHT));
+    bar.set_theme(&THEME);
+    status::run_status_bar(&mut bar, &STATUS, &LCD).await
+}
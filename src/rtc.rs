@@ -0,0 +1,87 @@
+//! Seconds counter of the STM32F1 real-time clock.
+//!
+//! embassy-stm32 has no driver for the RTC of the F1, so [`Rtc`] uses its
+//! registers directly. The clock runs from the 32.768 kHz LSE that
+//! `make_config` selects with `LsConfig::default_lse`, and keeps counting
+//! through resets, and on VBAT also through power cuts.
+
+use embassy_stm32::{
+    pac::{self, rtc::vals::Rtoff},
+    peripherals,
+};
+
+/// Divides the LSE down to one count per second.
+const PRESCALER: u32 = 32_768 - 1;
+
+pub const SECONDS_PER_DAY: u32 = 24 * 60 * 60;
+
+pub struct Rtc {
+    _rtc: peripherals::RTC,
+}
+
+impl Rtc {
+    /// Sets the counter to count seconds and waits until its registers can
+    /// be read. The counter itself keeps its value.
+    pub fn new(rtc: peripherals::RTC) -> Self {
+        pac::RCC.apb1enr().modify(|w| {
+            w.set_pwren(true);
+            w.set_bkpen(true);
+        });
+        // Backup domain writes, also needed by the RTC
+        pac::PWR.cr().modify(|w| w.set_dbp(true));
+
+        // The registers are only valid once synchronised after a reset
+        pac::RTC.crl().modify(|w| w.set_rsf(false));
+        while !pac::RTC.crl().read().rsf() {}
+
+        let mut rtc = Self { _rtc: rtc };
+        rtc.configure(|rtc| {
+            rtc.prlh().write(|w| w.set_prlh((PRESCALER >> 16) as u8));
+            rtc.prll().write(|w| w.set_prll(PRESCALER as u16));
+        });
+        rtc
+    }
+
+    /// Seconds counted, as last set with [`Rtc::set_seconds`].
+    pub fn seconds(&self) -> u32 {
+        // The low half may carry into the high half between the two reads
+        loop {
+            let high = pac::RTC.cnth().read().cnth();
+            let low = pac::RTC.cntl().read().cntl();
+            if pac::RTC.cnth().read().cnth() == high {
+                return u32::from(high) << 16 | u32::from(low);
+            }
+        }
+    }
+
+    pub fn set_seconds(&mut self, seconds: u32) {
+        self.configure(|rtc| {
+            rtc.cnth().write(|w| w.set_cnth((seconds >> 16) as u16));
+            rtc.cntl().write(|w| w.set_cntl(seconds as u16));
+        });
+    }
+
+    /// Time of day as (hour, minute, second), counting from midnight at 0.
+    pub fn time_of_day(&self) -> (u8, u8, u8) {
+        let seconds = self.seconds() % SECONDS_PER_DAY;
+        (
+            (seconds / 3600) as u8,
+            (seconds / 60 % 60) as u8,
+            (seconds % 60) as u8,
+        )
+    }
+
+    /// Writes registers in configuration mode, waiting for the previous
+    /// write to finish before and the new ones after.
+    fn configure(&mut self, write: impl FnOnce(pac::rtc::Rtc)) {
+        wait_write();
+        pac::RTC.crl().modify(|w| w.set_cnf(true));
+        write(pac::RTC);
+        pac::RTC.crl().modify(|w| w.set_cnf(false));
+        wait_write();
+    }
+}
+
+fn wait_write() {
+    while pac::RTC.crl().read().rtoff() == Rtoff::ONGOING {}
+}
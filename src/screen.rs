@@ -0,0 +1,311 @@
+//! Screens on a navigation stack, switched with a transition.
+//!
+//! A [`Screen`] is a tree of [`Widget`]s plus the logic reacting to
+//! [`InputEvent`]s. The [`ScreenManager`] owns the [`LCD`] and a fixed set of
+//! screens, keeps the stack of open ones by their index in that set, and
+//! draws the top one:
+//!
+//! ```ignore
+//! let mut screens: [&mut dyn Screen; 2] = [&mut status, &mut settings];
+//! let mut manager = ScreenManager::new(lcd, &mut screens, 0);
+//! manager.render().await;
+//! loop {
+//!     let event = INPUT.receive().await;
+//!     manager.handle(event).await.ok();
+//! }
+//! ```
+
+use crate::{
+    input::InputEvent,
+    lcd::{FRAME_LINES, HEIGHT, HW_SCROLL, LCD, Rect, ScrollAxis, WIDTH},
+    ui::{self, Theme, Widget},
+};
+use embassy_stm32::timer;
+use embassy_time::Timer;
+
+/// Most screens open at once, counting the first one.
+pub const SCREEN_STACK_DEPTH: usize = 8;
+
+/// Lines scrolled per frame of [`ScreenTransition::Slide`].
+const SLIDE_STEP: u16 = 8;
+/// Backlight percent changed per frame of [`ScreenTransition::Fade`].
+const FADE_STEP: u8 = 10;
+const FRAME_MS: u64 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum ScreenTransition {
+    /// Draw the next screen over the current one at once
+    Cut,
+    /// Scroll the current screen out with the controller's vertical scroll
+    /// along the axis of [`HW_SCROLL`], up or left when pushing and down or
+    /// right when popping, and draw the next screen on its background. In
+    /// the mirrored directions without it this is a
+    /// [`ScreenTransition::Cut`].
+    Slide,
+    /// Dim the backlight to black, draw the next screen and light it up
+    /// again
+    Fade,
+}
+
+/// What a screen asks the manager to do after an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum ScreenAction {
+    None,
+    /// Open the screen with this index on top
+    Push(usize),
+    /// Close the top screen, going back to the one below
+    Pop,
+    /// Close the top screen and open the screen with this index instead
+    Replace(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum ScreenError {
+    /// No screen has this index
+    UnknownScreen,
+    /// The screen is on the stack already
+    AlreadyOpen,
+    /// [`SCREEN_STACK_DEPTH`] screens are open
+    StackFull,
+    /// The first screen cannot be popped
+    LastScreen,
+}
+
+/// One screen of the application.
+///
+/// Only the top screen of the stack gets events and is drawn. It is
+/// expected to cover the whole panel, usually with a [`ui::Panel`] at the
+/// root of its tree.
+pub trait Screen<'w> {
+    /// The screen became the top of the stack, pushed or uncovered.
+    fn on_enter(&mut self) {}
+
+    fn on_event(&mut self, event: InputEvent) -> ScreenAction;
+
+    /// Brings the widgets up to date and returns them, for the manager to
+    /// draw what changed.
+    fn render(&mut self) -> &mut [Widget<'w>];
+
+    /// The screen stopped being the top of the stack, covered or closed.
+    fn on_exit(&mut self) {}
+
+    /// Color the panel is cleared to before the screen is drawn by
+    /// [`ScreenTransition::Slide`], `None` for the background of the theme.
+    fn background(&self) -> Option<u16> {
+        None
+    }
+
+    /// Applies `theme` to the widgets, for screens that keep colors of
+    /// their own to override.
+    fn set_theme(&mut self, theme: &Theme) {
+        ui::set_theme(self.render(), theme);
+    }
+}
+
+pub struct ScreenManager<'s, 'w, C: timer::GeneralInstance4Channel> {
+    lcd: LCD<C>,
+    screens: &'s mut [&'s mut dyn Screen<'w>],
+    /// Indices of the open screens, the top one last
+    stack: [usize; SCREEN_STACK_DEPTH],
+    depth: usize,
+    transition: ScreenTransition,
+    theme: Theme,
+    /// The top screen has to be drawn from scratch
+    full: bool,
+}
+
+impl<'s, 'w, C: timer::GeneralInstance4Channel> ScreenManager<'s, 'w, C> {
+    /// Opens screen `first`, which stays at the bottom of the stack. It is
+    /// drawn by the first [`ScreenManager::render`].
+    ///
+    /// # Panics
+    ///
+    /// If `first` is not an index into `screens`.
+    pub fn new(lcd: LCD<C>, screens: &'s mut [&'s mut dyn Screen<'w>], first: usize) -> Self {
+        screens[first].on_enter();
+        let mut stack = [0; SCREEN_STACK_DEPTH];
+        stack[0] = first;
+        Self {
+            lcd,
+            screens,
+            stack,
+            depth: 1,
+            transition: ScreenTransition::Slide,
+            theme: Theme::LIGHT,
+            full: true,
+        }
+    }
+
+    pub fn transition(mut self, transition: ScreenTransition) -> Self {
+        self.transition = transition;
+        self
+    }
+
+    pub fn set_transition(&mut self, transition: ScreenTransition) {
+        self.transition = transition;
+    }
+
+    /// Applies `theme` to every screen, see [`ScreenManager::set_theme`].
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.set_theme(theme);
+        self
+    }
+
+    /// Applies `theme` to every screen and draws the top one from scratch
+    /// on the next [`ScreenManager::render`].
+    pub fn set_theme(&mut self, theme: Theme) {
+        for screen in self.screens.iter_mut() {
+            screen.set_theme(&theme);
+        }
+        self.theme = theme;
+        self.full = true;
+    }
+
+    pub fn lcd(&mut self) -> &mut LCD<C> {
+        &mut self.lcd
+    }
+
+    /// Index of the top screen.
+    pub fn current(&self) -> usize {
+        self.stack[self.depth - 1]
+    }
+
+    /// Number of open screens.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn screen(&mut self) -> &mut dyn Screen<'w> {
+        &mut *self.screens[self.current()]
+    }
+
+    /// Draws the top screen from scratch on the next
+    /// [`ScreenManager::render`].
+    pub fn invalidate(&mut self) {
+        self.full = true;
+    }
+
+    /// Draws what changed on the top screen.
+    pub async fn render(&mut self) {
+        let widgets = self.screens[self.stack[self.depth - 1]].render();
+        if self.full {
+            ui::invalidate(widgets);
+            self.full = false;
+        }
+        ui::render(&mut self.lcd, widgets).await;
+    }
+
+    /// Passes `event` to the top screen, acts on its answer and draws what
+    /// changed.
+    pub async fn handle(&mut self, event: InputEvent) -> Result<(), ScreenError> {
+        let result = match self.screen().on_event(event) {
+            ScreenAction::None => Ok(()),
+            ScreenAction::Push(index) => self.push(index).await,
+            ScreenAction::Pop => self.pop().await,
+            ScreenAction::Replace(index) => self.replace(index).await,
+        };
+        self.render().await;
+        result
+    }
+
+    pub async fn push(&mut self, index: usize) -> Result<(), ScreenError> {
+        self.check(index)?;
+        if self.depth == SCREEN_STACK_DEPTH {
+            return Err(ScreenError::StackFull);
+        }
+        self.screen().on_exit();
+        self.stack[self.depth] = index;
+        self.depth += 1;
+        self.enter(false).await;
+        Ok(())
+    }
+
+    pub async fn pop(&mut self) -> Result<(), ScreenError> {
+        if self.depth == 1 {
+            return Err(ScreenError::LastScreen);
+        }
+        self.screen().on_exit();
+        self.depth -= 1;
+        self.enter(true).await;
+        Ok(())
+    }
+
+    /// Swaps the top screen for screen `index`, without growing the stack.
+    pub async fn replace(&mut self, index: usize) -> Result<(), ScreenError> {
+        self.check(index)?;
+        self.screen().on_exit();
+        self.stack[self.depth - 1] = index;
+        self.enter(false).await;
+        Ok(())
+    }
+
+    /// Whether screen `index` can be pushed.
+    fn check(&self, index: usize) -> Result<(), ScreenError> {
+        if index >= self.screens.len() {
+            Err(ScreenError::UnknownScreen)
+        } else if self.stack[..self.depth].contains(&index) {
+            Err(ScreenError::AlreadyOpen)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Shows the new top screen, going `back` down the stack or not.
+    async fn enter(&mut self, back: bool) {
+        self.screen().on_enter();
+        self.full = true;
+
+        match self.transition {
+            ScreenTransition::Slide => {
+                if let Some(axis) = HW_SCROLL {
+                    let bc = self.screen().background().unwrap_or(self.theme.bg);
+                    self.slide_out(axis, bc, back).await;
+                }
+                self.render().await;
+            }
+            ScreenTransition::Cut => self.render().await,
+            ScreenTransition::Fade => {
+                let level = self.lcd.brightness();
+                for step in (0..level / FADE_STEP).rev() {
+                    self.lcd.set_brightness(step * FADE_STEP);
+                    Timer::after_millis(FRAME_MS).await;
+                }
+                self.render().await;
+                for step in 1..=level / FADE_STEP {
+                    self.lcd.set_brightness(step * FADE_STEP);
+                    Timer::after_millis(FRAME_MS).await;
+                }
+                self.lcd.set_brightness(level);
+            }
+        }
+    }
+
+    /// Scrolls the panel out of view along `axis`, up or left, or `back`
+    /// down or right, filling the lines coming in with `bc`. Leaves the
+    /// scroll start where it began.
+    async fn slide_out(&mut self, axis: ScrollAxis, bc: u16, back: bool) {
+        let len = match axis {
+            ScrollAxis::X => WIDTH,
+            ScrollAxis::Y => HEIGHT,
+        };
+        self.lcd.set_scroll_area(0, len, FRAME_LINES - len).await;
+        self.lcd.set_scroll_start(0).await;
+
+        let mut moved = 0;
+        while moved < len {
+            let step = SLIDE_STEP.min(len - moved);
+            // Frame lines about to wrap around to the end, or going back to
+            // the start
+            let lines = if back { len - moved - step } else { moved };
+            let strip = match axis {
+                ScrollAxis::X => Rect::new(lines, 0, step, HEIGHT),
+                ScrollAxis::Y => Rect::new(0, lines, WIDTH, step),
+            };
+            self.lcd.fill_rect(strip, bc).await;
+            moved += step;
+            let start = if back { len - moved } else { moved % len };
+            self.lcd.set_scroll_start(start).await;
+            Timer::after_millis(FRAME_MS).await;
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! Small key-value store for settings in the external SPI flash.
+//!
+//! Values live in one of two 4 KiB sectors starting at [`SETTINGS_BASE`],
+//! between the sector the demo program uses for its read/write test and the
+//! asset bundle at [`ASSET_BASE`](crate::asset::ASSET_BASE). All integers are
+//! little endian. A sector in use starts with a header:
+//!
+//! | Offset | Size | Content                                     |
+//! |--------|------|---------------------------------------------|
+//! | 0      | 4    | magic `BOES`                                |
+//! | 4      | 4    | sequence number, the higher one is current  |
+//!
+//! followed by a log of records, each a 12 byte header and the value:
+//!
+//! | Offset | Size | Content                          |
+//! |--------|------|----------------------------------|
+//! | 0      | 4    | FNV-1a hash of the key           |
+//! | 4      | 2    | length of the value              |
+//! | 6      | 2    | reserved                         |
+//! | 8      | 4    | CRC-32 of the value              |
+//!
+//! Writing a key appends a record, the last valid one of a key wins. When
+//! the sector is full the latest value of every other key and the new value
+//! are copied to the other sector, whose header is written last, so a power
+//! cut at any point leaves either the old or the new sector in use. A record
+//! header cut short by a power loss leaves the rest of its sector unused.
+
+use crate::{
+    asset::{crc32_update, name_hash},
+    w25qxx::W25Qxx,
+};
+
+pub const SETTINGS_BASE: u32 = 0x00_1000;
+pub const SETTINGS_SECTOR_SIZE: u32 = 0x1000;
+pub const SETTINGS_MAGIC: &[u8; 4] = b"BOES";
+pub const SETTINGS_HEADER_SIZE: u32 = 8;
+pub const SETTINGS_RECORD_SIZE: u32 = 12;
+
+/// Longest value stored under one key.
+pub const SETTINGS_MAX_VALUE_LEN: usize = 64;
+
+/// What erased flash reads as.
+const ERASED: u32 = 0xFFFF_FFFF;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum SettingsError {
+    NotFound,
+    /// The value is longer than [`SETTINGS_MAX_VALUE_LEN`] or the buffer
+    /// it is read into
+    TooLong,
+    /// The latest values of all keys do not fit in one sector
+    Full,
+}
+
+#[derive(Clone, Copy)]
+struct Record {
+    addr: u32,
+    hash: u32,
+    len: u16,
+    crc: u32,
+}
+
+impl Record {
+    fn value_addr(&self) -> u32 {
+        self.addr + SETTINGS_RECORD_SIZE
+    }
+
+    fn next(&self) -> u32 {
+        self.value_addr() + u32::from(self.len)
+    }
+}
+
+pub struct SettingsStore {
+    /// Address of the sector in use
+    sector: u32,
+    seq: u32,
+    /// Where the next record goes
+    end: u32,
+}
+
+impl SettingsStore {
+    /// Finds the sector in use, formatting the first one if neither is.
+    pub async fn open(flash: &mut W25Qxx) -> Self {
+        let first = SETTINGS_BASE;
+        let second = SETTINGS_BASE + SETTINGS_SECTOR_SIZE;
+        let (sector, seq) = match (sequence(flash, first).await, sequence(flash, second).await) {
+            // Wrapping comparison, the sequence only ever grows by one
+            (Some(a), Some(b)) if (b.wrapping_sub(a) as i32) > 0 => (second, b),
+            (Some(a), _) => (first, a),
+            (None, Some(b)) => (second, b),
+            (None, None) => {
+                flash.sector_erase(first).await;
+                write_header(flash, first, 0).await;
+                (first, 0)
+            }
+        };
+
+        let limit = sector + SETTINGS_SECTOR_SIZE;
+        let mut end = sector + SETTINGS_HEADER_SIZE;
+        while let Some(record) = record_at(flash, sector, end).await {
+            end = record.next();
+        }
+        // A torn header: where its record ends is unknown, so nothing may be
+        // appended and the next write compacts
+        if !is_erased(flash, end, limit).await {
+            end = limit;
+        }
+        Self { sector, seq, end }
+    }
+
+    /// Reads the value of `key` into `buf`, returning its length.
+    pub async fn read(
+        &self, flash: &mut W25Qxx, key: &str, buf: &mut [u8],
+    ) -> Result<usize, SettingsError> {
+        let record = self
+            .latest(flash, name_hash(key))
+            .await
+            .ok_or(SettingsError::NotFound)?;
+        let len = usize::from(record.len);
+        if len > buf.len() {
+            return Err(SettingsError::TooLong);
+        }
+        flash
+            .buffer_read(&mut buf[..len], record.value_addr())
+            .await;
+        Ok(len)
+    }
+
+    /// Stores `value` under `key`, unless it is stored already.
+    pub async fn write(
+        &mut self, flash: &mut W25Qxx, key: &str, value: &[u8],
+    ) -> Result<(), SettingsError> {
+        if value.len() > SETTINGS_MAX_VALUE_LEN {
+            return Err(SettingsError::TooLong);
+        }
+        let hash = name_hash(key);
+
+        let mut current = [0u8; SETTINGS_MAX_VALUE_LEN];
+        if let Some(record) = self.latest(flash, hash).await {
+            let current = &mut current[..usize::from(record.len)];
+            flash.buffer_read(current, record.value_addr()).await;
+            if current == value {
+                return Ok(());
+            }
+        }
+
+        let size = SETTINGS_RECORD_SIZE + value.len() as u32;
+        if self.end + size > self.sector + SETTINGS_SECTOR_SIZE {
+            return self.compact(flash, hash, value).await;
+        }
+        self.end = append(flash, self.end, hash, value).await;
+        Ok(())
+    }
+
+    /// The last record of `hash` whose value matches its checksum.
+    async fn latest(&self, flash: &mut W25Qxx, hash: u32) -> Option<Record> {
+        let mut latest = None;
+        let mut addr = self.sector + SETTINGS_HEADER_SIZE;
+        while let Some(record) = record_at(flash, self.sector, addr).await {
+            if record.hash == hash && is_intact(flash, &record).await {
+                latest = Some(record);
+            }
+            addr = record.next();
+        }
+        latest
+    }
+
+    /// Moves the latest value of every key but `hash` to the other sector,
+    /// appends `value` under `hash` and only then switches to it.
+    async fn compact(
+        &mut self, flash: &mut W25Qxx, hash: u32, value: &[u8],
+    ) -> Result<(), SettingsError> {
+        let target = if self.sector == SETTINGS_BASE {
+            SETTINGS_BASE + SETTINGS_SECTOR_SIZE
+        } else {
+            SETTINGS_BASE
+        };
+        flash.sector_erase(target).await;
+
+        let mut end = target + SETTINGS_HEADER_SIZE;
+        let mut addr = self.sector + SETTINGS_HEADER_SIZE;
+        while let Some(record) = record_at(flash, self.sector, addr).await {
+            addr = record.next();
+            if record.hash == hash || !is_intact(flash, &record).await {
+                continue;
+            }
+            // Only the last intact record of a key is kept
+            if self
+                .latest(flash, record.hash)
+                .await
+                .map(|latest| latest.addr)
+                != Some(record.addr)
+            {
+                continue;
+            }
+            if end + SETTINGS_RECORD_SIZE + u32::from(record.len) > target + SETTINGS_SECTOR_SIZE {
+                return Err(SettingsError::Full);
+            }
+            let mut kept = [0u8; SETTINGS_MAX_VALUE_LEN];
+            let kept = &mut kept[..usize::from(record.len)];
+            flash.buffer_read(kept, record.value_addr()).await;
+            end = append(flash, end, record.hash, kept).await;
+        }
+        if end + SETTINGS_RECORD_SIZE + value.len() as u32 > target + SETTINGS_SECTOR_SIZE {
+            return Err(SettingsError::Full);
+        }
+        end = append(flash, end, hash, value).await;
+
+        self.seq = self.seq.wrapping_add(1);
+        write_header(flash, target, self.seq).await;
+        flash.sector_erase(self.sector).await;
+        self.sector = target;
+        self.end = end;
+        Ok(())
+    }
+}
+
+/// Sequence number of the sector at `sector`, `None` if it is not in use.
+async fn sequence(flash: &mut W25Qxx, sector: u32) -> Option<u32> {
+    let mut header = [0u8; SETTINGS_HEADER_SIZE as usize];
+    flash.buffer_read(&mut header, sector).await;
+    let seq = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    (&header[..4] == SETTINGS_MAGIC && seq != ERASED).then_some(seq)
+}
+
+async fn write_header(flash: &mut W25Qxx, sector: u32, seq: u32) {
+    let mut header = [0u8; SETTINGS_HEADER_SIZE as usize];
+    header[..4].copy_from_slice(SETTINGS_MAGIC);
+    header[4..].copy_from_slice(&seq.to_le_bytes());
+    flash.buffer_write(&header, sector).await;
+}
+
+/// The record at `addr` of the sector at `sector`, `None` where the log
+/// ends.
+async fn record_at(flash: &mut W25Qxx, sector: u32, addr: u32) -> Option<Record> {
+    let limit = sector + SETTINGS_SECTOR_SIZE;
+    if addr + SETTINGS_RECORD_SIZE > limit {
+        return None;
+    }
+    let mut header = [0u8; SETTINGS_RECORD_SIZE as usize];
+    flash.buffer_read(&mut header, addr).await;
+    let record = Record {
+        addr,
+        hash: u32::from_le_bytes([header[0], header[1], header[2], header[3]]),
+        len: u16::from_le_bytes([header[4], header[5]]),
+        crc: u32::from_le_bytes([header[8], header[9], header[10], header[11]]),
+    };
+    // An erased header, or one cut short by a power loss
+    let valid = record.hash != ERASED
+        && usize::from(record.len) <= SETTINGS_MAX_VALUE_LEN
+        && record.next() <= limit;
+    valid.then_some(record)
+}
+
+/// Whether the record header at `addr`, up to `limit`, was never written.
+async fn is_erased(flash: &mut W25Qxx, addr: u32, limit: u32) -> bool {
+    let mut header = [0u8; SETTINGS_RECORD_SIZE as usize];
+    let len = limit.saturating_sub(addr).min(SETTINGS_RECORD_SIZE) as usize;
+    let header = &mut header[..len];
+    flash.buffer_read(header, addr).await;
+    header.iter().all(|&b| b == 0xFF)
+}
+
+async fn is_intact(flash: &mut W25Qxx, record: &Record) -> bool {
+    let mut value = [0u8; SETTINGS_MAX_VALUE_LEN];
+    let value = &mut value[..usize::from(record.len)];
+    flash.buffer_read(value, record.value_addr()).await;
+    crc32_update(0, value) == record.crc
+}
+
+/// Writes a record at `addr` and returns where the next one goes.
+async fn append(flash: &mut W25Qxx, addr: u32, hash: u32, value: &[u8]) -> u32 {
+    let mut record = [0xFFu8; SETTINGS_RECORD_SIZE as usize + SETTINGS_MAX_VALUE_LEN];
+    record[..4].copy_from_slice(&hash.to_le_bytes());
+    record[4..6].copy_from_slice(&(value.len() as u16).to_le_bytes());
+    record[8..12].copy_from_slice(&crc32_update(0, value).to_le_bytes());
+    let size = SETTINGS_RECORD_SIZE as usize + value.len();
+    record[12..size].copy_from_slice(value);
+    flash.buffer_write(&record[..size], addr).await;
+    (addr as usize + size) as u32
+}
@@ -0,0 +1,438 @@
+//! Full-screen slideshow of the images in the asset bundle.
+//!
+//! Raw RGB565, BMP, QOI and sprite assets are shown one after the other,
+//! centered on the panel, with a transition between them. Sprites are shown
+//! on the background color. Other asset kinds are skipped.
+
+use crate::{
+    asset::{AssetEntry, AssetError, AssetKind, AssetReader, AssetStore},
+    lcd::{
+        CharMode, Color, HEIGHT, LCD, Rect, WIDTH,
+        bmp::{BMP_HEADER_MAX, Bmp},
+        layout::{HAlign, VAlign},
+        qoi::{QOI_HEADER_SIZE, QoiDecoder},
+        sprite::{SPRITE_HEADER_SIZE, SpriteDecoder},
+    },
+    ui::Theme,
+    w25qxx::W25Qxx,
+};
+use as_what::{AsU16, AsUsize};
+use embassy_stm32::timer;
+use embassy_time::Timer;
+
+/// Columns revealed per frame of [`Transition::Wipe`].
+const WIPE_STEP: u16 = 16;
+/// Columns moved per frame of [`Transition::Slide`].
+const SLIDE_STEP: u16 = 20;
+/// Backlight percent changed per frame of [`Transition::Fade`].
+const FADE_STEP: u8 = 10;
+const FRAME_MS: u64 = 20;
+
+/// Bytes of compressed data read from flash at once while decoding.
+const CHUNK_SIZE: usize = 64;
+
+#[derive(Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum Transition {
+    /// Replace the picture at once
+    Cut,
+    /// Reveal the next picture from left to right
+    Wipe,
+    /// Push the next picture in from the right
+    Slide,
+    /// Dim the backlight to black, swap the picture and light it up again
+    Fade,
+}
+
+#[derive(Clone, Copy)]
+pub struct SlideshowConfig {
+    /// How long each picture stays on screen, in milliseconds.
+    pub dwell_ms: u64,
+    pub transition: Transition,
+    /// Color around pictures smaller than the panel.
+    pub background: u16,
+    /// The error screen is drawn in its background color on its warning
+    /// color, in its font.
+    pub theme: Theme,
+}
+
+impl Default for SlideshowConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SlideshowConfig {
+    pub const fn new() -> Self {
+        Self {
+            dwell_ms: 3000,
+            transition: Transition::Slide,
+            background: Color::Black as u16,
+            theme: Theme::LIGHT,
+        }
+    }
+
+    pub const fn dwell_ms(mut self, dwell_ms: u64) -> Self {
+        self.dwell_ms = dwell_ms;
+        self
+    }
+
+    pub const fn transition(mut self, transition: Transition) -> Self {
+        self.transition = transition;
+        self
+    }
+
+    pub const fn background(mut self, background: u16) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub const fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+fn is_image(entry: &AssetEntry) -> bool {
+    matches!(
+        entry.kind,
+        AssetKind::Rgb565 | AssetKind::Bmp | AssetKind::Qoi | AssetKind::Sprite
+    ) && entry.width > 0
+        && entry.height > 0
+}
+
+/// Position that centers `entry` on the panel, may be negative for pictures
+/// larger than the panel.
+fn centered(entry: &AssetEntry) -> (i32, i32) {
+    (
+        (i32::from(WIDTH) - i32::from(entry.width)) / 2,
+        (i32::from(HEIGHT) - i32::from(entry.height)) / 2,
+    )
+}
+
+/// Runs the slideshow over the bundle at `base`.
+///
+/// Every picture is checked against its checksum before the show starts.
+/// If the bundle is missing, corrupt or holds no pictures, an error screen
+/// is shown and the error returned; otherwise this never returns.
+pub async fn run<C: timer::GeneralInstance4Channel>(
+    lcd: &mut LCD<C>, flash: &mut W25Qxx, base: u32, config: &SlideshowConfig,
+) -> AssetError {
+    let store = match open(flash, base).await {
+        Ok(store) => store,
+        Err(err) => {
+            show_error(lcd, err, &config.theme).await;
+            return err;
+        }
+    };
+
+    let mut first = true;
+    loop {
+        for index in 0..store.len() {
+            let entry = match store.entry(flash, index).await {
+                Ok(entry) if is_image(&entry) => entry,
+                Ok(_) => continue,
+                Err(err) => {
+                    show_error(lcd, err, &config.theme).await;
+                    return err;
+                }
+            };
+
+            let transition = if first {
+                Transition::Cut
+            } else {
+                config.transition
+            };
+            first = false;
+            if let Err(err) = show(lcd, flash, &entry, transition, config.background).await {
+                show_error(lcd, err, &config.theme).await;
+                return err;
+            }
+            Timer::after_millis(config.dwell_ms).await;
+        }
+    }
+}
+
+/// Opens the bundle and verifies all pictures in it.
+async fn open(flash: &mut W25Qxx, base: u32) -> Result<AssetStore, AssetError> {
+    let store = AssetStore::open(flash, base).await?;
+    let mut images = 0;
+    for index in 0..store.len() {
+        let entry = store.entry(flash, index).await?;
+        if is_image(&entry) {
+            store.verify(flash, &entry).await?;
+            images += 1;
+        }
+    }
+    if images == 0 {
+        return Err(AssetError::NotFound);
+    }
+    Ok(store)
+}
+
+async fn show<C: timer::GeneralInstance4Channel>(
+    lcd: &mut LCD<C>, flash: &mut W25Qxx, entry: &AssetEntry, transition: Transition, bg: u16,
+) -> Result<(), AssetError> {
+    let screen = Rect::new(0, 0, WIDTH, HEIGHT);
+    let (x, y) = centered(entry);
+
+    match transition {
+        Transition::Cut => draw_frame(lcd, flash, entry, (x, y), screen, bg).await?,
+        Transition::Wipe => {
+            let mut left = 0;
+            while left < WIDTH {
+                let strip = Rect::new(left, 0, WIPE_STEP.min(WIDTH - left), HEIGHT);
+                draw_frame(lcd, flash, entry, (x, y), strip, bg).await?;
+                left += strip.w;
+                Timer::after_millis(FRAME_MS).await;
+            }
+        }
+        Transition::Slide => {
+            let mut offset = WIDTH;
+            while offset > 0 {
+                offset = offset.saturating_sub(SLIDE_STEP);
+                // Everything right of `offset` shows the next picture,
+                // shifted right by `offset`
+                let area = Rect::new(offset, 0, WIDTH - offset, HEIGHT);
+                draw_frame(lcd, flash, entry, (x + i32::from(offset), y), area, bg).await?;
+                Timer::after_millis(FRAME_MS).await;
+            }
+        }
+        Transition::Fade => {
+            for percent in (0..100 / FADE_STEP).rev() {
+                lcd.set_brightness(percent * FADE_STEP);
+                Timer::after_millis(FRAME_MS).await;
+            }
+            draw_frame(lcd, flash, entry, (x, y), screen, bg).await?;
+            for percent in 1..=100 / FADE_STEP {
+                lcd.set_brightness(percent * FADE_STEP);
+                Timer::after_millis(FRAME_MS).await;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Redraws `area` of the panel with the picture at `(x, y)` and `bg`
+/// around it.
+async fn draw_frame<C: timer::GeneralInstance4Channel>(
+    lcd: &mut LCD<C>, flash: &mut W25Qxx, entry: &AssetEntry, (x, y): (i32, i32), area: Rect,
+    bg: u16,
+) -> Result<(), AssetError> {
+    // Bounds of the picture, clamped to the panel
+    let left = x.clamp(0, i32::from(WIDTH)) as u16;
+    let top = y.clamp(0, i32::from(HEIGHT)) as u16;
+    let right = (x + i32::from(entry.width)).clamp(0, i32::from(WIDTH)) as u16;
+    let bottom = (y + i32::from(entry.height)).clamp(0, i32::from(HEIGHT)) as u16;
+    let picture = Rect::new(left, top, right - left, bottom - top);
+
+    let around = [
+        Rect::new(0, 0, WIDTH, top),
+        Rect::new(0, bottom, WIDTH, HEIGHT - bottom),
+        Rect::new(0, top, left, bottom - top),
+        Rect::new(right, top, WIDTH - right, bottom - top),
+    ];
+    for rect in around {
+        lcd.fill_rect(rect.intersect(&area), bg).await;
+    }
+
+    let visible = picture.intersect(&area);
+    if visible.is_empty() {
+        return Ok(());
+    }
+    match entry.kind {
+        AssetKind::Bmp => draw_bmp(lcd, flash, entry, (x, y), visible).await,
+        AssetKind::Qoi => {
+            let mut reader = AssetReader::new(entry);
+            let mut header = [0u8; QOI_HEADER_SIZE];
+            reader.read(flash, &mut header).await;
+            let decoder = QoiDecoder::new(&header).map_err(|_| AssetError::CorruptAsset)?;
+            draw_decoded(lcd, flash, reader, decoder, (x, y), visible).await
+        }
+        AssetKind::Sprite => {
+            let mut reader = AssetReader::new(entry);
+            let mut header = [0u8; SPRITE_HEADER_SIZE];
+            reader.read(flash, &mut header).await;
+            let decoder = SpriteDecoder::new(&header, bg).map_err(|_| AssetError::CorruptAsset)?;
+            draw_decoded(lcd, flash, reader, decoder, (x, y), visible).await
+        }
+        _ => {
+            draw_rgb565(lcd, flash, entry, (x, y), visible).await;
+            Ok(())
+        }
+    }
+}
+
+async fn draw_rgb565<C: timer::GeneralInstance4Channel>(
+    lcd: &mut LCD<C>, flash: &mut W25Qxx, entry: &AssetEntry, (x, y): (i32, i32), visible: Rect,
+) {
+    let stride = u32::from(entry.width) * 2;
+    // Offset of the visible part inside the picture
+    let dx = (i32::from(visible.x) - x) as u32;
+    let dy = (i32::from(visible.y) - y) as u32;
+    let addr = entry.addr + dy * stride + dx * 2;
+
+    if visible.w == entry.width {
+        lcd.show_picture_from_flash((visible.x, visible.y), flash, addr, (visible.w, visible.h))
+            .await;
+    } else {
+        for j in 0..visible.h {
+            lcd.show_picture_from_flash(
+                (visible.x, visible.y + j),
+                flash,
+                addr + u32::from(j) * stride,
+                (visible.w, 1),
+            )
+            .await;
+        }
+    }
+}
+
+/// Streams the compressed picture after the header `reader` has passed
+/// through `decoder`. The whole picture is decoded, rows outside `visible`
+/// are thrown away.
+async fn draw_decoded<C: timer::GeneralInstance4Channel, D: Decoder>(
+    lcd: &mut LCD<C>, flash: &mut W25Qxx, mut reader: AssetReader, mut decoder: D,
+    (x, y): (i32, i32), visible: Rect,
+) -> Result<(), AssetError> {
+    let width = decoder.width();
+
+    let mut input = [0u8; CHUNK_SIZE];
+    let (mut start, mut end) = (0, 0);
+    let mut row = [0u16; WIDTH as usize];
+
+    for j in 0..decoder.height() {
+        let row_y = y + i32::from(j);
+        if row_y >= i32::from(visible.bottom()) {
+            break;
+        }
+
+        let mut column = 0;
+        while column < width {
+            let chunk = (width - column).min(WIDTH).as_usize();
+            let mut produced = 0;
+            while produced < chunk {
+                if start == end {
+                    start = 0;
+                    end = reader.read(flash, &mut input).await;
+                    if end == 0 {
+                        return Err(AssetError::CorruptAsset);
+                    }
+                }
+                let (consumed, n) = decoder.decode(&input[start..end], &mut row[produced..chunk]);
+                start += consumed;
+                produced += n;
+            }
+
+            // Part of this chunk inside `visible`
+            if row_y >= i32::from(visible.y) {
+                let chunk_x = x + i32::from(column);
+                let from = (i32::from(visible.x) - chunk_x).clamp(0, chunk as i32);
+                let to = (i32::from(visible.right()) - chunk_x).clamp(0, chunk as i32);
+                if from < to {
+                    let span = &row[from as usize..to as usize];
+                    lcd.draw_span(((chunk_x + from) as u16, row_y as u16), span)
+                        .await;
+                }
+            }
+            column += chunk.as_u16();
+        }
+    }
+
+    Ok(())
+}
+
+/// Streaming decoder of a picture kind.
+trait Decoder {
+    fn width(&self) -> u16;
+    fn height(&self) -> u16;
+    fn decode(&mut self, input: &[u8], out: &mut [u16]) -> (usize, usize);
+}
+
+impl Decoder for QoiDecoder {
+    fn width(&self) -> u16 {
+        self.width()
+    }
+
+    fn height(&self) -> u16 {
+        self.height()
+    }
+
+    fn decode(&mut self, input: &[u8], out: &mut [u16]) -> (usize, usize) {
+        self.decode(input, out)
+    }
+}
+
+impl Decoder for SpriteDecoder {
+    fn width(&self) -> u16 {
+        self.width()
+    }
+
+    fn height(&self) -> u16 {
+        self.height()
+    }
+
+    fn decode(&mut self, input: &[u8], out: &mut [u16]) -> (usize, usize) {
+        self.decode(input, out)
+    }
+}
+
+/// BMP rows are stored uncompressed, so only the visible part of each is
+/// read.
+async fn draw_bmp<C: timer::GeneralInstance4Channel>(
+    lcd: &mut LCD<C>, flash: &mut W25Qxx, entry: &AssetEntry, (x, y): (i32, i32), visible: Rect,
+) -> Result<(), AssetError> {
+    let mut header = [0u8; BMP_HEADER_MAX];
+    let n = AssetReader::new(entry).read(flash, &mut header).await;
+    let bmp = Bmp::parse_header(&header[..n], entry.len.as_usize())
+        .map_err(|_| AssetError::CorruptAsset)?;
+    if bmp.width() != entry.width || bmp.height() != entry.height {
+        return Err(AssetError::CorruptAsset);
+    }
+
+    // Offset of the visible part inside the picture
+    let dx = (i32::from(visible.x) - x) as u16;
+    let dy = (i32::from(visible.y) - y) as u16;
+    let w = visible.w.as_usize();
+    let len = bmp.span_len(dx, w);
+
+    let mut src = [0u8; WIDTH as usize * 4];
+    let mut row = [0u16; WIDTH as usize];
+    for j in 0..visible.h {
+        let offset = bmp.pixel_offset(dx, dy + j);
+        flash
+            .buffer_read(&mut src[..len], entry.addr + offset as u32)
+            .await;
+        bmp.convert(dx, &src[..len], &mut row[..w]);
+        lcd.draw_span((visible.x, visible.y + j), &row[..w]).await;
+    }
+
+    Ok(())
+}
+
+async fn show_error<C: timer::GeneralInstance4Channel>(
+    lcd: &mut LCD<C>, err: AssetError, theme: &Theme,
+) {
+    let message = match err {
+        AssetError::BadMagic => "No asset bundle in flash",
+        AssetError::UnsupportedVersion => "Unsupported asset bundle version",
+        AssetError::CorruptToc | AssetError::CorruptAsset | AssetError::UnknownKind => {
+            "Asset bundle is corrupt"
+        }
+        AssetError::NotFound => "No images in asset bundle",
+    };
+
+    lcd.set_brightness(100);
+    let screen = Rect::new(0, 0, WIDTH, HEIGHT);
+    lcd.fill_rect(screen, theme.warning).await;
+    let layout = theme.text().align(HAlign::Center, VAlign::Middle);
+    lcd.show_text(
+        screen,
+        message,
+        theme.bg,
+        theme.warning,
+        &layout,
+        CharMode::NonOverlay,
+    )
+    .await;
+}
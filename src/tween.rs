@@ -0,0 +1,216 @@
+//! Values moved smoothly from one end to the other over time.
+//!
+//! A [`Tween`] says where a property starts and ends, how long it takes and
+//! along which [`Easing`] curve; [`Tween::run`] hands out the value for every
+//! frame, at most [`Tween::fps`] times a second. Helpers drive the backlight
+//! and properties of a widget tree:
+//!
+//! ```ignore
+//! const FILL: Tween<u16> = Tween::new(0, 100).duration_ms(600).easing(Easing::Bounce);
+//!
+//! tween::widgets(&mut lcd, &mut screen, &FILL, |widgets, value| {
+//!     if let Widget::ProgressBar(bar) = &mut widgets[0] {
+//!         bar.set_value(value);
+//!     }
+//! })
+//! .await;
+//! ```
+
+use crate::{
+    lcd::{LCD, blend},
+    ui::{self, Widget},
+};
+use embassy_stm32::timer;
+use embassy_time::{Duration, Instant, Timer};
+
+/// Frame rate of a [`Tween`] unless set otherwise.
+pub const TWEEN_FPS: u32 = 50;
+
+/// Progress along a tween in fixed point: 0 at the start, this at the end.
+pub const TWEEN_ONE: u16 = 1 << 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum Easing {
+    /// Constant speed
+    Linear,
+    /// Speeds up from the start and slows down to the end, cubic
+    EaseInOut,
+    /// Falls to the end and bounces off it three times, each lower
+    Bounce,
+}
+
+impl Easing {
+    /// Eased progress for linear progress `t`, both out of [`TWEEN_ONE`].
+    pub fn apply(self, t: u16) -> u16 {
+        let one = u64::from(TWEEN_ONE);
+        let t = u64::from(t.min(TWEEN_ONE));
+        let eased = match self {
+            Easing::Linear => t,
+            Easing::EaseInOut if t < one / 2 => 4 * t * t * t / (one * one),
+            Easing::EaseInOut => {
+                let rest = 2 * (one - t);
+                one - rest * rest * rest / (2 * one * one)
+            }
+            Easing::Bounce => bounce(t as i32) as u64,
+        };
+        (eased as u16).min(TWEEN_ONE)
+    }
+}
+
+/// The usual bounce-out curve: a parabola of 7.5625 x² for the fall, then
+/// three smaller ones touching the end at 8/11, 10/11 and 1.
+fn bounce(t: i32) -> i32 {
+    let one = i32::from(TWEEN_ONE);
+    let parabola = |x: i32, top: i32| 121 * x * x / (16 * one) + top;
+    if 11 * t < 4 * one {
+        parabola(t, 0)
+    } else if 11 * t < 8 * one {
+        parabola(t - 6 * one / 11, one * 3 / 4)
+    } else if 11 * t < 10 * one {
+        parabola(t - 9 * one / 11, one * 15 / 16)
+    } else {
+        parabola(t - 21 * one / 22, one * 63 / 64)
+    }
+}
+
+/// A property that can be tweened.
+pub trait Lerp: Copy {
+    /// The value `t` out of [`TWEEN_ONE`] of the way from `from` to `to`.
+    fn lerp(from: Self, to: Self, t: u16) -> Self;
+}
+
+fn mix(from: i32, to: i32, t: u16) -> i32 {
+    from + (to - from) * i32::from(t) / i32::from(TWEEN_ONE)
+}
+
+impl Lerp for u8 {
+    fn lerp(from: Self, to: Self, t: u16) -> Self {
+        mix(from.into(), to.into(), t) as u8
+    }
+}
+
+impl Lerp for u16 {
+    fn lerp(from: Self, to: Self, t: u16) -> Self {
+        mix(from.into(), to.into(), t) as u16
+    }
+}
+
+impl Lerp for i16 {
+    fn lerp(from: Self, to: Self, t: u16) -> Self {
+        mix(from.into(), to.into(), t) as i16
+    }
+}
+
+/// A position, moved in a straight line.
+impl Lerp for (u16, u16) {
+    fn lerp(from: Self, to: Self, t: u16) -> Self {
+        (u16::lerp(from.0, to.0, t), u16::lerp(from.1, to.1, t))
+    }
+}
+
+/// An RGB565 color, tweened channel by channel rather than as a number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub struct Rgb565(pub u16);
+
+impl Lerp for Rgb565 {
+    fn lerp(from: Self, to: Self, t: u16) -> Self {
+        let alpha = u32::from(t) * 255 / u32::from(TWEEN_ONE);
+        Rgb565(blend(to.0, from.0, alpha as u8))
+    }
+}
+
+/// A property moving from `from` to `to` in `duration_ms`.
+#[derive(Clone, Copy)]
+pub struct Tween<T> {
+    pub from: T,
+    pub to: T,
+    pub duration_ms: u64,
+    pub easing: Easing,
+    /// Most frames per second; a frame that takes longer just makes the
+    /// next value jump ahead
+    pub fps: u32,
+}
+
+impl<T> Tween<T> {
+    pub const fn new(from: T, to: T) -> Self {
+        Self {
+            from,
+            to,
+            duration_ms: 300,
+            easing: Easing::EaseInOut,
+            fps: TWEEN_FPS,
+        }
+    }
+
+    pub const fn duration_ms(mut self, duration_ms: u64) -> Self {
+        self.duration_ms = duration_ms;
+        self
+    }
+
+    pub const fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub const fn fps(mut self, fps: u32) -> Self {
+        self.fps = fps;
+        self
+    }
+}
+
+impl<T: Lerp> Tween<T> {
+    /// The same motion backwards, e.g. to fade back out.
+    pub fn reversed(&self) -> Self {
+        Self {
+            from: self.to,
+            to: self.from,
+            ..*self
+        }
+    }
+
+    /// The value `elapsed` after the start, `to` from the end on.
+    pub fn value_at(&self, elapsed: Duration) -> T {
+        let elapsed = elapsed.as_millis();
+        if elapsed >= self.duration_ms {
+            return self.to;
+        }
+        let t = elapsed * u64::from(TWEEN_ONE) / self.duration_ms;
+        T::lerp(self.from, self.to, self.easing.apply(t as u16))
+    }
+
+    /// Calls `frame` with the value of every frame until the end, the last
+    /// time with exactly `to`.
+    pub async fn run(&self, mut frame: impl AsyncFnMut(T)) {
+        let period = Duration::from_micros(1_000_000 / u64::from(self.fps.max(1)));
+        let start = Instant::now();
+        loop {
+            let now = Instant::now();
+            let elapsed = now - start;
+            frame(self.value_at(elapsed)).await;
+            if elapsed.as_millis() >= self.duration_ms {
+                break;
+            }
+            Timer::at(now + period).await;
+        }
+    }
+}
+
+/// Moves the backlight percent along `tween`.
+pub async fn backlight<C: timer::GeneralInstance4Channel>(lcd: &mut LCD<C>, tween: &Tween<u8>) {
+    tween.run(async |percent| lcd.set_brightness(percent)).await;
+}
+
+/// Runs `tween` on a widget tree: `set` puts every value into the tree, e.g.
+/// into a progress bar or as the position of an icon, and what changed is
+/// redrawn.
+pub async fn widgets<'a, C: timer::GeneralInstance4Channel, T: Lerp>(
+    lcd: &mut LCD<C>, widgets: &mut [Widget<'a>], tween: &Tween<T>,
+    mut set: impl FnMut(&mut [Widget<'a>], T),
+) {
+    tween
+        .run(async |value| {
+            set(widgets, value);
+            ui::render(lcd, widgets).await;
+        })
+        .await;
+}
@@ -0,0 +1,314 @@
+//! Retained-mode widgets on top of [`LCD`].
+//!
+//! A screen is a tree of [`Widget`]s: [`Panel`]s hold their children, the
+//! other widgets are leaves. Every widget knows its bounds and remembers
+//! whether it changed since it was last drawn, so [`render`] only redraws
+//! what is dirty. A panel that redraws paints over its children, so they are
+//! all redrawn with it.
+//!
+//! Widgets are updated through the tree, e.g. by matching on
+//! [`Panel::children_mut`]:
+//!
+//! ```ignore
+//! if let Widget::Label(label) = &mut root.children_mut()[0] {
+//!     label.set_text("Done");
+//! }
+//! ui::render(&mut lcd, core::slice::from_mut(&mut root)).await;
+//! ```
+//!
+//! Colors and fonts come from a [`Theme`]; [`set_theme`] applies one to a
+//! whole tree, so switching themes at runtime redraws everything.
+
+pub mod button;
+pub mod chart;
+pub mod gauge;
+pub mod icon;
+pub mod keyboard;
+pub mod label;
+pub mod menu;
+pub mod panel;
+pub mod progress;
+pub mod status;
+pub mod theme;
+
+pub use button::{Button, ButtonState};
+pub use chart::{Chart, ChartScroll, YRange};
+pub use gauge::ArcGauge;
+pub use icon::{Icon, IconImage};
+pub use keyboard::{Keyboard, KeyboardEvent, KeyboardPage};
+pub use label::Label;
+pub use menu::{Menu, MenuEvent, MenuItem};
+pub use panel::Panel;
+pub use progress::ProgressBar;
+pub use status::{StatusBar, StatusState};
+pub use theme::Theme;
+
+use crate::lcd::{LCD, Rect, paint::Paint};
+use core::slice::IterMut;
+use embassy_stm32::timer;
+
+/// Deepest nesting of panels [`render`] descends into, counting the top
+/// level. Children of deeper panels are not drawn.
+pub const UI_MAX_DEPTH: usize = 4;
+
+/// Abstract navigation input, mapped from whatever the board has: GPIO
+/// buttons, a rotary encoder or keys received over UART.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum Nav {
+    Up,
+    Down,
+    Select,
+    Back,
+}
+
+/// A node of the tree.
+///
+/// [`render`] can draw every variant, so the drawing code of each ends up in
+/// any image that renders a tree. Heavier widgets are only variants with
+/// their cargo feature enabled: `ui-chart`, `ui-menu`, `ui-status-bar` and
+/// `ui-keyboard`. Without it they can still be drawn on their own, like a
+/// [`StatusBar`] by [`status::run_status_bar`].
+pub enum Widget<'a> {
+    Label(Label),
+    ProgressBar(ProgressBar),
+    ArcGauge(ArcGauge),
+    Icon(Icon<'a>),
+    Button(Button<'a>),
+    #[cfg(feature = "ui-chart")]
+    Chart(&'a mut Chart),
+    #[cfg(feature = "ui-menu")]
+    Menu(Menu<'a>),
+    #[cfg(feature = "ui-keyboard")]
+    Keyboard(Keyboard),
+    #[cfg(feature = "ui-status-bar")]
+    StatusBar(StatusBar),
+    Panel(Panel<'a>),
+}
+
+impl Widget<'_> {
+    pub fn bounds(&self) -> Rect {
+        match self {
+            Widget::Label(label) => label.bounds(),
+            Widget::ProgressBar(bar) => bar.bounds(),
+            Widget::ArcGauge(gauge) => gauge.bounds(),
+            Widget::Icon(icon) => icon.bounds(),
+            Widget::Button(button) => button.bounds(),
+            #[cfg(feature = "ui-chart")]
+            Widget::Chart(chart) => chart.bounds(),
+            #[cfg(feature = "ui-menu")]
+            Widget::Menu(menu) => menu.bounds(),
+            #[cfg(feature = "ui-keyboard")]
+            Widget::Keyboard(keyboard) => keyboard.bounds(),
+            #[cfg(feature = "ui-status-bar")]
+            Widget::StatusBar(bar) => bar.bounds(),
+            Widget::Panel(panel) => panel.bounds(),
+        }
+    }
+
+    /// Whether the widget itself changed since it was last drawn, not
+    /// counting the children of a panel.
+    pub fn is_dirty(&self) -> bool {
+        match self {
+            Widget::Label(label) => label.is_dirty(),
+            Widget::ProgressBar(bar) => bar.is_dirty(),
+            Widget::ArcGauge(gauge) => gauge.is_dirty(),
+            Widget::Icon(icon) => icon.is_dirty(),
+            Widget::Button(button) => button.is_dirty(),
+            #[cfg(feature = "ui-chart")]
+            Widget::Chart(chart) => chart.is_dirty(),
+            #[cfg(feature = "ui-menu")]
+            Widget::Menu(menu) => menu.is_dirty(),
+            #[cfg(feature = "ui-keyboard")]
+            Widget::Keyboard(keyboard) => keyboard.is_dirty(),
+            #[cfg(feature = "ui-status-bar")]
+            Widget::StatusBar(bar) => bar.is_dirty(),
+            Widget::Panel(panel) => panel.is_dirty(),
+        }
+    }
+
+    /// Forces a full redraw of the widget and, for a panel, all of its
+    /// children on the next [`render`].
+    pub fn invalidate(&mut self) {
+        match self {
+            Widget::Label(label) => label.invalidate(),
+            Widget::ProgressBar(bar) => bar.invalidate(),
+            Widget::ArcGauge(gauge) => gauge.invalidate(),
+            Widget::Icon(icon) => icon.invalidate(),
+            Widget::Button(button) => button.invalidate(),
+            #[cfg(feature = "ui-chart")]
+            Widget::Chart(chart) => chart.invalidate(),
+            #[cfg(feature = "ui-menu")]
+            Widget::Menu(menu) => menu.invalidate(),
+            #[cfg(feature = "ui-keyboard")]
+            Widget::Keyboard(keyboard) => keyboard.invalidate(),
+            #[cfg(feature = "ui-status-bar")]
+            Widget::StatusBar(bar) => bar.invalidate(),
+            Widget::Panel(panel) => {
+                panel.invalidate();
+                invalidate(panel.children_mut());
+            }
+        }
+    }
+
+    /// Applies `theme` to the widget and, for a panel, all of its children,
+    /// which redraws them on the next [`render`].
+    pub fn set_theme(&mut self, theme: &Theme) {
+        match self {
+            Widget::Label(label) => label.set_theme(theme),
+            Widget::ProgressBar(bar) => bar.set_theme(theme),
+            Widget::ArcGauge(gauge) => gauge.set_theme(theme),
+            Widget::Icon(icon) => icon.set_theme(theme),
+            Widget::Button(button) => button.set_theme(theme),
+            #[cfg(feature = "ui-chart")]
+            Widget::Chart(chart) => chart.set_theme(theme),
+            #[cfg(feature = "ui-menu")]
+            Widget::Menu(menu) => menu.set_theme(theme),
+            #[cfg(feature = "ui-keyboard")]
+            Widget::Keyboard(keyboard) => keyboard.set_theme(theme),
+            #[cfg(feature = "ui-status-bar")]
+            Widget::StatusBar(bar) => bar.set_theme(theme),
+            Widget::Panel(panel) => {
+                panel.set_theme(theme);
+                set_theme(panel.children_mut(), theme);
+            }
+        }
+    }
+
+    async fn draw_on<C: timer::GeneralInstance4Channel>(
+        &mut self, lcd: &mut LCD<C>, backdrop: Option<&Backdrop>,
+    ) {
+        match self {
+            Widget::Label(label) => label.draw_on(lcd, backdrop).await,
+            Widget::ProgressBar(bar) => bar.draw(lcd).await,
+            Widget::ArcGauge(gauge) => gauge.draw(lcd).await,
+            Widget::Icon(icon) => icon.draw_on(lcd, backdrop).await,
+            Widget::Button(button) => button.draw(lcd).await,
+            #[cfg(feature = "ui-chart")]
+            Widget::Chart(chart) => chart.draw(lcd).await,
+            #[cfg(feature = "ui-menu")]
+            Widget::Menu(menu) => menu.draw(lcd).await,
+            #[cfg(feature = "ui-keyboard")]
+            Widget::Keyboard(keyboard) => keyboard.draw(lcd).await,
+            #[cfg(feature = "ui-status-bar")]
+            Widget::StatusBar(bar) => bar.draw(lcd).await,
+            Widget::Panel(panel) => panel.draw(lcd).await,
+        }
+    }
+}
+
+/// Background of the innermost panel, which widgets without a background
+/// color of their own are cleared to.
+#[derive(Clone, Copy)]
+struct Backdrop {
+    rect: Rect,
+    paint: Paint,
+}
+
+impl Backdrop {
+    /// Repaints the part of the backdrop under `rect`.
+    async fn clear<C: timer::GeneralInstance4Channel>(&self, lcd: &mut LCD<C>, rect: Rect) {
+        lcd.fill_paint_clipped(self.rect, rect, &self.paint).await;
+    }
+}
+
+/// Siblings of one level of the tree still to be visited.
+struct Level<'r, 'a> {
+    widgets: IterMut<'r, Widget<'a>>,
+    /// The parent panel was redrawn, so all of these have to be too
+    force: bool,
+    backdrop: Option<Backdrop>,
+}
+
+/// Draws every widget of the tree that changed since it was last drawn.
+///
+/// The tree is walked depth first without recursion, parents before their
+/// children, so later siblings are drawn over earlier ones.
+pub async fn render<C: timer::GeneralInstance4Channel>(
+    lcd: &mut LCD<C>, widgets: &mut [Widget<'_>],
+) {
+    let mut stack: [Option<Level>; UI_MAX_DEPTH] = [const { None }; UI_MAX_DEPTH];
+    stack[0] = Some(Level {
+        widgets: widgets.iter_mut(),
+        force: false,
+        backdrop: None,
+    });
+    let mut depth = 1;
+
+    while depth > 0 {
+        let Some(level) = &mut stack[depth - 1] else {
+            break;
+        };
+        let Some(widget) = level.widgets.next() else {
+            stack[depth - 1] = None;
+            depth -= 1;
+            continue;
+        };
+        let redraw = level.force || widget.is_dirty();
+        let backdrop = level.backdrop;
+
+        if level.force {
+            // Partial updates assume the rest is still on the panel
+            widget.invalidate();
+        }
+        if redraw {
+            widget.draw_on(lcd, backdrop.as_ref()).await;
+        }
+        if let Widget::Panel(panel) = widget {
+            if depth < UI_MAX_DEPTH {
+                stack[depth] = Some(Level {
+                    backdrop: Some(Backdrop {
+                        rect: panel.bounds(),
+                        paint: panel.paint(),
+                    }),
+                    widgets: panel.children_mut().iter_mut(),
+                    force: redraw,
+                });
+                depth += 1;
+            }
+        }
+    }
+}
+
+/// Forces a full redraw of every widget in the tree on the next [`render`].
+pub fn invalidate(widgets: &mut [Widget<'_>]) {
+    for widget in widgets {
+        widget.invalidate();
+    }
+}
+
+/// Applies `theme` to every widget in the tree, switching the look of a
+/// whole screen on the next [`render`].
+pub fn set_theme(widgets: &mut [Widget<'_>], theme: &Theme) {
+    for widget in widgets {
+        widget.set_theme(theme);
+    }
+}
+
+/// Draws a border `width` pixels thick just inside `rect`.
+async fn draw_frame<C: timer::GeneralInstance4Channel>(
+    lcd: &mut LCD<C>, rect: Rect, width: u16, color: u16,
+) {
+    let width = width.min(rect.w / 2).min(rect.h / 2);
+    let paint = Paint::Solid(color);
+    let inner_h = rect.h - 2 * width;
+    for side in [
+        Rect::new(rect.x, rect.y, rect.w, width),
+        Rect::new(rect.x, rect.bottom() - width, rect.w, width),
+        Rect::new(rect.x, rect.y + width, width, inner_h),
+        Rect::new(rect.right() - width, rect.y + width, width, inner_h),
+    ] {
+        lcd.fill_paint(side, &paint).await;
+    }
+}
+
+/// `rect` shrunk by `by` pixels on every side.
+fn inset(rect: Rect, by: u16) -> Rect {
+    let by_x = by.min(rect.w / 2);
+    let by_y = by.min(rect.h / 2);
+    Rect::new(
+        rect.x + by_x,
+        rect.y + by_y,
+        rect.w - 2 * by_x,
+        rect.h - 2 * by_y,
+    )
+}
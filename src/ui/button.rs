@@ -0,0 +1,119 @@
+//! Push button with a text label.
+
+use super::{Theme, draw_frame, inset};
+use crate::lcd::{
+    CharMode, Color, LCD, Rect,
+    layout::{HAlign, TextLayout, VAlign},
+};
+use embassy_stm32::timer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum ButtonState {
+    Normal,
+    /// Selected for input, drawn with a thicker border in the accent color
+    Focused,
+    /// Drawn filled with the accent color
+    Pressed,
+    /// Drawn with a gray label
+    Disabled,
+}
+
+/// Bordered box with its label centered in it.
+pub struct Button<'a> {
+    rect: Rect,
+    label: &'a str,
+    layout: TextLayout,
+    state: ButtonState,
+    fc: u16,
+    bc: u16,
+    accent: u16,
+    dirty: bool,
+}
+
+impl<'a> Button<'a> {
+    /// The alignment of `layout` is replaced by centering.
+    pub const fn new(rect: Rect, label: &'a str, layout: TextLayout) -> Self {
+        Self {
+            rect,
+            label,
+            layout: layout.align(HAlign::Center, VAlign::Middle),
+            state: ButtonState::Normal,
+            fc: Color::Black as u16,
+            bc: Color::White as u16,
+            accent: Color::Blue as u16,
+            dirty: true,
+        }
+    }
+
+    pub const fn colors(mut self, fc: u16, bc: u16, accent: u16) -> Self {
+        self.fc = fc;
+        self.bc = bc;
+        self.accent = accent;
+        self
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Takes the colors and fonts of `theme`, keeping the centering.
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.layout = theme.fonts(self.layout);
+        self.fc = theme.fg;
+        self.bc = theme.bg;
+        self.accent = theme.accent;
+        self.dirty = true;
+    }
+
+    pub fn state(&self) -> ButtonState {
+        self.state
+    }
+
+    pub fn set_state(&mut self, state: ButtonState) {
+        if state != self.state {
+            self.state = state;
+            self.dirty = true;
+        }
+    }
+
+    pub fn set_label(&mut self, label: &'a str) {
+        if label != self.label {
+            self.label = label;
+            self.dirty = true;
+        }
+    }
+
+    pub async fn draw<C: timer::GeneralInstance4Channel>(&mut self, lcd: &mut LCD<C>) {
+        self.dirty = false;
+        let (border, border_color, fc, bc) = match self.state {
+            ButtonState::Normal => (1, self.fc, self.fc, self.bc),
+            ButtonState::Focused => (2, self.accent, self.fc, self.bc),
+            ButtonState::Pressed => (1, self.accent, self.bc, self.accent),
+            ButtonState::Disabled => (1, Color::Gray as u16, Color::Gray as u16, self.bc),
+        };
+        // Always clear two pixels, so that losing focus erases the thick
+        // border
+        draw_frame(lcd, self.rect, border, border_color).await;
+        if border < 2 {
+            draw_frame(lcd, inset(self.rect, 1), 1, bc).await;
+        }
+        let inner = inset(self.rect, 2);
+        lcd.show_text(
+            inner,
+            self.label,
+            fc,
+            bc,
+            &self.layout,
+            CharMode::NonOverlay,
+        )
+        .await;
+    }
+}
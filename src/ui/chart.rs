@@ -0,0 +1,412 @@
+//! Scrolling line chart of live samples.
+
+use super::Theme;
+use crate::lcd::{Color, FRAME_LINES, HEIGHT, HW_SCROLL, LCD, Rect, ScrollAxis, WIDTH};
+use as_what::{AsU16, AsU32, AsUsize};
+use embassy_stm32::timer;
+
+pub const CHART_MAX_SERIES: usize = 3;
+
+/// Most samples a series keeps, one per pixel along the time axis.
+pub const CHART_MAX_SAMPLES: usize = if WIDTH > HEIGHT { WIDTH } else { HEIGHT } as usize;
+
+/// Slots cleared ahead of the newest sample in [`ChartScroll::Sweep`].
+const SWEEP_GAP: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum YRange {
+    /// From the lowest to the highest sample kept, redrawing the whole
+    /// chart whenever that changes
+    Auto,
+    /// Samples outside the range are drawn at its edges
+    Fixed(i16, i16),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum ChartScroll {
+    /// A cursor sweeps left to right and wraps around, overwriting the
+    /// oldest samples with a small gap ahead of it. Only the new column is
+    /// drawn per sample.
+    Sweep,
+    /// The plot moves one column to the left per sample, with the newest
+    /// sample at the right edge. The whole chart is drawn per sample.
+    Shift,
+    /// The controller's vertical scroll moves the plot, so only the new
+    /// line is drawn per sample.
+    ///
+    /// Time runs along the axis of [`HW_SCROLL`]: in the horizontal
+    /// direction to the right as with the other modes, the newest sample at
+    /// the right edge, and in the vertical one down the panel, the newest
+    /// sample at the bottom and values growing to the right. The controller
+    /// scrolls whole lines, so the chart has to span the panel across that
+    /// axis; otherwise, or in the mirrored directions, it falls back to
+    /// [`ChartScroll::Sweep`]. It takes over the scroll area, so only one
+    /// such chart can be on screen.
+    Hardware,
+}
+
+/// Line chart of up to [`CHART_MAX_SERIES`] series sampled together.
+///
+/// Samples are `i16`, so scale readings to fit, e.g. in tenths of a degree.
+/// Each sample takes one pixel along the time axis and connects to the
+/// previous one with a line across the value axis. The chart keeps as many
+/// samples as fit.
+///
+/// Its sample buffer makes it too large to hold by value in a
+/// [`Widget`](super::Widget), which borrows it instead.
+pub struct Chart {
+    rect: Rect,
+    series: usize,
+    colors: [u16; CHART_MAX_SERIES],
+    samples: [[i16; CHART_MAX_SAMPLES]; CHART_MAX_SERIES],
+    /// Ring index of the oldest sample
+    head: usize,
+    len: usize,
+    /// Samples pushed since the last clear, places sweep columns and time
+    /// grid lines
+    count: u32,
+    range: YRange,
+    scroll: ChartScroll,
+    bc: u16,
+    grid: u16,
+    /// Divisions of the value axis, 0 or 1 for no grid lines
+    grid_rows: u16,
+    /// Samples between two time grid lines, 0 for none
+    grid_every: u16,
+    /// Value range on the panel, `None` when everything has to be drawn
+    drawn: Option<(i16, i16)>,
+    /// Samples pushed since the last draw
+    pending: usize,
+}
+
+impl Chart {
+    /// A chart of `series` series, between 1 and [`CHART_MAX_SERIES`].
+    pub const fn new(rect: Rect, series: usize) -> Self {
+        let series = if series == 0 {
+            1
+        } else if series > CHART_MAX_SERIES {
+            CHART_MAX_SERIES
+        } else {
+            series
+        };
+        Self {
+            rect,
+            series,
+            colors: [Color::Blue as u16, Color::Red as u16, Color::Green as u16],
+            samples: [[0; CHART_MAX_SAMPLES]; CHART_MAX_SERIES],
+            head: 0,
+            len: 0,
+            count: 0,
+            range: YRange::Auto,
+            scroll: ChartScroll::Sweep,
+            bc: Color::White as u16,
+            grid: Color::Lgray as u16,
+            grid_rows: 0,
+            grid_every: 0,
+            drawn: None,
+            pending: 0,
+        }
+    }
+
+    pub const fn range(mut self, range: YRange) -> Self {
+        self.range = range;
+        self
+    }
+
+    pub const fn scroll(mut self, scroll: ChartScroll) -> Self {
+        self.scroll = scroll;
+        self
+    }
+
+    pub const fn color(mut self, series: usize, color: u16) -> Self {
+        if series < CHART_MAX_SERIES {
+            self.colors[series] = color;
+        }
+        self
+    }
+
+    pub const fn background(mut self, bc: u16) -> Self {
+        self.bc = bc;
+        self
+    }
+
+    /// Splits the value axis into `rows` divisions, and draws a line across
+    /// it every `every` samples, moving along with them.
+    pub const fn grid(mut self, rows: u16, every: u16, color: u16) -> Self {
+        self.grid_rows = rows;
+        self.grid_every = every;
+        self.grid = color;
+        self
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.drawn.is_none() || self.pending > 0
+    }
+
+    pub fn invalidate(&mut self) {
+        self.drawn = None;
+    }
+
+    /// Background and grid of `theme`; the series keep their colors.
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.bc = theme.bg;
+        self.grid = theme.muted;
+        self.invalidate();
+    }
+
+    /// Number of samples kept.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Most samples the chart keeps, its length along the time axis.
+    pub fn capacity(&self) -> usize {
+        let len = if self.is_vertical() {
+            self.rect.h
+        } else {
+            self.rect.w
+        };
+        len.as_usize().clamp(1, CHART_MAX_SAMPLES)
+    }
+
+    /// Sample `index` of `series`, 0 being the oldest one kept.
+    pub fn sample(&self, series: usize, index: usize) -> Option<i16> {
+        if series >= self.series || index >= self.len {
+            return None;
+        }
+        Some(self.samples[series][(self.head + index) % self.capacity()])
+    }
+
+    /// Appends one sample of every series, dropping the oldest one when the
+    /// chart is full. Missing values are taken as 0.
+    pub fn push(&mut self, values: &[i16]) {
+        let capacity = self.capacity();
+        let slot = if self.len < capacity {
+            self.len += 1;
+            (self.head + self.len - 1) % capacity
+        } else {
+            let slot = self.head;
+            self.head = (self.head + 1) % capacity;
+            slot
+        };
+        for (series, samples) in self.samples[..self.series].iter_mut().enumerate() {
+            samples[slot] = values.get(series).copied().unwrap_or(0);
+        }
+        self.count = self.count.wrapping_add(1);
+        self.pending = (self.pending + 1).min(capacity);
+    }
+
+    /// Drops every sample.
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+        self.count = 0;
+        self.pending = 0;
+        self.drawn = None;
+    }
+
+    /// The scroll mode in use, [`ChartScroll::Hardware`] falling back to
+    /// [`ChartScroll::Sweep`] where it is not available.
+    fn mode(&self) -> ChartScroll {
+        match self.scroll {
+            ChartScroll::Hardware if !self.spans_scroll() => ChartScroll::Sweep,
+            scroll => scroll,
+        }
+    }
+
+    /// Whether the chart spans the panel across the axis of [`HW_SCROLL`]
+    /// and its lines along it lie within the frame memory.
+    fn spans_scroll(&self) -> bool {
+        let (spans, first, lines) = match HW_SCROLL {
+            Some(ScrollAxis::X) => (
+                self.rect.y == 0 && self.rect.h == HEIGHT,
+                self.rect.x,
+                self.rect.w,
+            ),
+            Some(ScrollAxis::Y) => (
+                self.rect.x == 0 && self.rect.w == WIDTH,
+                self.rect.y,
+                self.rect.h,
+            ),
+            None => return false,
+        };
+        spans
+            && first
+                .checked_add(lines)
+                .is_some_and(|end| end <= FRAME_LINES)
+    }
+
+    /// Whether time runs down the panel rather than to the right.
+    fn is_vertical(&self) -> bool {
+        self.mode() == ChartScroll::Hardware && HW_SCROLL == Some(ScrollAxis::Y)
+    }
+
+    /// First frame memory line of the chart and how many it covers, for
+    /// [`ChartScroll::Hardware`].
+    fn scroll_lines(&self) -> (u16, u16) {
+        if self.is_vertical() {
+            (self.rect.y, self.rect.h)
+        } else {
+            (self.rect.x, self.rect.w)
+        }
+    }
+
+    /// Lowest and highest value shown, never the same.
+    fn value_range(&self) -> (i16, i16) {
+        let (lo, hi) = match self.range {
+            YRange::Fixed(lo, hi) => (lo.min(hi), lo.max(hi)),
+            YRange::Auto if self.len == 0 => (0, 1),
+            YRange::Auto => {
+                let mut lo = i16::MAX;
+                let mut hi = i16::MIN;
+                for series in 0..self.series {
+                    for value in (0..self.len).filter_map(|index| self.sample(series, index)) {
+                        lo = lo.min(value);
+                        hi = hi.max(value);
+                    }
+                }
+                (lo, hi)
+            }
+        };
+        match (lo == hi, hi.checked_add(1)) {
+            (true, Some(above)) => (lo, above),
+            (true, None) => (lo - 1, hi),
+            (false, _) => (lo, hi),
+        }
+    }
+
+    /// Serial number of sample `index`, counted from the last clear.
+    fn serial(&self, index: usize) -> u32 {
+        self.count.wrapping_sub((self.len - index).as_u32())
+    }
+
+    /// Position along the time axis of sample `index`.
+    fn slot_of(&self, index: usize) -> usize {
+        let capacity = self.capacity();
+        match self.mode() {
+            ChartScroll::Shift => capacity - self.len + index,
+            _ => (self.serial(index) % capacity.as_u32()).as_usize(),
+        }
+    }
+
+    /// Sample drawn at `slot`, `None` for an empty slot.
+    fn index_at(&self, slot: usize) -> Option<usize> {
+        let capacity = self.capacity();
+        let index = match self.mode() {
+            ChartScroll::Shift => slot.checked_sub(capacity - self.len)?,
+            mode => {
+                let next = (self.count % capacity.as_u32()).as_usize();
+                // The sweep gap ahead of the newest sample stays empty
+                let ahead = (slot + capacity - next) % capacity;
+                if mode == ChartScroll::Sweep && ahead < SWEEP_GAP && capacity > SWEEP_GAP {
+                    return None;
+                }
+                (slot + capacity - self.slot_of(0)) % capacity
+            }
+        };
+        (index < self.len).then_some(index)
+    }
+
+    pub async fn draw<C: timer::GeneralInstance4Channel>(&mut self, lcd: &mut LCD<C>) {
+        let mode = self.mode();
+        let range = self.value_range();
+        let capacity = self.capacity();
+        let full = self.drawn != Some(range)
+            || self.pending >= capacity
+            || (mode == ChartScroll::Shift && self.pending > 0);
+
+        if full {
+            if mode == ChartScroll::Hardware {
+                let (first, lines) = self.scroll_lines();
+                lcd.set_scroll_area(first, lines, FRAME_LINES - first - lines)
+                    .await;
+            }
+            for slot in 0..capacity {
+                self.draw_slot(lcd, slot, self.index_at(slot), range).await;
+            }
+        } else {
+            for index in self.len - self.pending..self.len {
+                let slot = self.slot_of(index);
+                self.draw_slot(lcd, slot, Some(index), range).await;
+                if mode == ChartScroll::Sweep {
+                    for gap in 1..=SWEEP_GAP.min(capacity - 1) {
+                        let slot = (slot + gap) % capacity;
+                        self.draw_slot(lcd, slot, None, range).await;
+                    }
+                }
+            }
+        }
+
+        if mode == ChartScroll::Hardware {
+            // The oldest sample at the start of the chart
+            let top = if self.is_empty() { 0 } else { self.slot_of(0) };
+            lcd.set_scroll_start(self.scroll_lines().0 + top.as_u16())
+                .await;
+        }
+
+        self.pending = 0;
+        self.drawn = Some(range);
+    }
+
+    /// Draws the line across the value axis at `slot` of the time axis.
+    async fn draw_slot<C: timer::GeneralInstance4Channel>(
+        &self, lcd: &mut LCD<C>, slot: usize, index: Option<usize>, (lo, hi): (i16, i16),
+    ) {
+        let vertical = self.is_vertical();
+        let len = if vertical { self.rect.w } else { self.rect.h }
+            .as_usize()
+            .min(CHART_MAX_SAMPLES);
+        if len == 0 {
+            return;
+        }
+
+        let mut line = [self.bc; CHART_MAX_SAMPLES];
+        let line = &mut line[..len];
+        if self.grid_rows > 1 {
+            let rows = self.grid_rows.as_usize();
+            for k in 1..rows {
+                line[k * (len - 1) / rows] = self.grid;
+            }
+        }
+
+        if let Some(index) = index {
+            let every = self.grid_every.as_u32();
+            if every > 0 && self.serial(index) % every == 0 {
+                line.fill(self.grid);
+            }
+
+            // Pixel of `value` along the line: up for horizontal time, right
+            // for vertical time
+            let span = i32::from(hi) - i32::from(lo);
+            let pixel = |value: i16| {
+                let value = i32::from(value.clamp(lo, hi)) - i32::from(lo);
+                let pos = (value * (len.as_u32() as i32 - 1) / span).as_usize();
+                if vertical { pos } else { len - 1 - pos }
+            };
+            for series in 0..self.series {
+                let samples = &self.samples[series];
+                let at = |index: usize| samples[(self.head + index) % self.capacity()];
+                let to = pixel(at(index));
+                let from = if index > 0 { pixel(at(index - 1)) } else { to };
+                line[from.min(to)..=from.max(to)].fill(self.colors[series]);
+            }
+        }
+
+        let offset = slot.as_u16();
+        if vertical {
+            lcd.draw_span((self.rect.x, self.rect.y + offset), line)
+                .await;
+        } else {
+            lcd.draw_column((self.rect.x + offset, self.rect.y), line)
+                .await;
+        }
+    }
+}
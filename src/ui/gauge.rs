@@ -0,0 +1,163 @@
+//! Arc gauge.
+
+use super::Theme;
+use crate::lcd::{Color, LCD, Rect, WIDTH};
+use as_what::{AsU16, AsUsize};
+use embassy_stm32::timer;
+
+/// Ring segment filled clockwise in proportion to `value / max`.
+///
+/// Angles are in degrees, clockwise from 12 o'clock. The default arc opens
+/// at the bottom: it starts at 225 degrees and sweeps 270.
+pub struct ArcGauge {
+    center: (u16, u16),
+    radius: u16,
+    thickness: u16,
+    start: u16,
+    sweep: u16,
+    value: u16,
+    max: u16,
+    active: u16,
+    inactive: u16,
+    bc: u16,
+    dirty: bool,
+}
+
+impl ArcGauge {
+    pub const fn new(center: (u16, u16), radius: u16, max: u16) -> Self {
+        Self {
+            center,
+            radius,
+            thickness: 4,
+            start: 225,
+            sweep: 270,
+            value: 0,
+            max,
+            active: Color::Blue as u16,
+            inactive: Color::Lgray as u16,
+            bc: Color::White as u16,
+            dirty: true,
+        }
+    }
+
+    pub const fn thickness(mut self, thickness: u16) -> Self {
+        self.thickness = thickness;
+        self
+    }
+
+    /// Where the arc starts and how far it goes, at most 360 degrees.
+    pub const fn angles(mut self, start: u16, sweep: u16) -> Self {
+        self.start = start % 360;
+        self.sweep = if sweep > 360 { 360 } else { sweep };
+        self
+    }
+
+    pub const fn colors(mut self, active: u16, inactive: u16, bc: u16) -> Self {
+        self.active = active;
+        self.inactive = inactive;
+        self.bc = bc;
+        self
+    }
+
+    /// The square around the ring.
+    pub fn bounds(&self) -> Rect {
+        let (x, y) = self.center;
+        let size = self.radius * 2 + 1;
+        Rect::new(
+            x.saturating_sub(self.radius),
+            y.saturating_sub(self.radius),
+            size,
+            size,
+        )
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+
+    /// The arc in the accent color on a muted track.
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.active = theme.accent;
+        self.inactive = theme.muted;
+        self.bc = theme.bg;
+        self.dirty = true;
+    }
+
+    pub fn value(&self) -> u16 {
+        self.value
+    }
+
+    /// Sets the value, clamped to the maximum.
+    pub fn set_value(&mut self, value: u16) {
+        let value = value.min(self.max);
+        if value != self.value {
+            self.value = value;
+            self.dirty = true;
+        }
+    }
+
+    /// Draws the whole square around the ring, row by row.
+    pub async fn draw<C: timer::GeneralInstance4Channel>(&mut self, lcd: &mut LCD<C>) {
+        self.dirty = false;
+        let bounds = self.bounds();
+        let (cx, cy) = (i32::from(self.center.0), i32::from(self.center.1));
+        let r = i32::from(self.radius);
+        let inner = (r - i32::from(self.thickness)).max(0);
+        // Half a pixel of tolerance on both edges of the ring
+        let outer_sq = r * r + r;
+        let inner_sq = if inner > 0 { inner * inner - inner } else { -1 };
+        let active_sweep = u32::from(self.sweep) * u32::from(self.value);
+        let max = u32::from(self.max.max(1));
+
+        let mut row = [0u16; WIDTH as usize];
+        let row = &mut row[..bounds.w.as_usize().min(WIDTH.as_usize())];
+        for y in bounds.y..bounds.bottom() {
+            let dy = i32::from(y) - cy;
+            for (i, pixel) in row.iter_mut().enumerate() {
+                let dx = i32::from(bounds.x + i.as_u16()) - cx;
+                let dist_sq = dx * dx + dy * dy;
+                *pixel = self.bc;
+                if dist_sq > outer_sq || dist_sq <= inner_sq {
+                    continue;
+                }
+                let along = (angle(dx, dy) + 360 - self.start) % 360;
+                if along > self.sweep {
+                    continue;
+                }
+                *pixel = if u32::from(along) * max < active_sweep {
+                    self.active
+                } else {
+                    self.inactive
+                };
+            }
+            lcd.draw_span((bounds.x, y), row).await;
+        }
+    }
+}
+
+/// Angle of `(dx, dy)` in whole degrees, clockwise from straight up with
+/// `dy` pointing down, within about a tenth of a degree.
+fn angle(dx: i32, dy: i32) -> u16 {
+    let (ax, ay) = (dx.unsigned_abs() as f32, dy.unsigned_abs() as f32);
+    if ax == 0.0 && ay == 0.0 {
+        return 0;
+    }
+    // atan(z) for z in 0..=1, in degrees
+    let atan = |z: f32| 45.0 * z + z * (1.0 - z) * (14.02 + 3.8 * z);
+    let from_vertical = if ax <= ay {
+        atan(ax / ay)
+    } else {
+        90.0 - atan(ay / ax)
+    };
+    let degrees = match (dx >= 0, dy > 0) {
+        (true, false) => from_vertical,
+        (true, true) => 180.0 - from_vertical,
+        (false, true) => 180.0 + from_vertical,
+        (false, false) => 360.0 - from_vertical,
+    };
+    ((degrees + 0.5) as u16) % 360
+}
@@ -0,0 +1,182 @@
+//! Small pictures that can be swapped or hidden.
+
+use super::{Backdrop, Theme};
+use crate::lcd::{LCD, Rect, paint::Paint, sprite::Sprite};
+use embassy_stm32::timer;
+
+const NONE: Rect = Rect::new(0, 0, 0, 0);
+
+#[derive(Clone, Copy)]
+pub enum IconImage<'a> {
+    /// Big endian RGB565 pixels, as drawn by [`LCD::show_picture`].
+    Picture { data: &'a [u8], size: (u16, u16) },
+    /// Run-length encoded sprite, its transparent pixels showing the
+    /// background.
+    Sprite(Sprite<'a>),
+}
+
+impl IconImage<'_> {
+    fn size(&self) -> (u16, u16) {
+        match self {
+            IconImage::Picture { size, .. } => *size,
+            IconImage::Sprite(sprite) => (sprite.width(), sprite.height()),
+        }
+    }
+}
+
+/// An [`IconImage`] with its top left corner at a fixed position.
+///
+/// A hidden icon, and the transparent pixels of a sprite, show the
+/// background color, or the background of the enclosing panel when there is
+/// none.
+pub struct Icon<'a> {
+    position: (u16, u16),
+    image: IconImage<'a>,
+    bc: Option<u16>,
+    visible: bool,
+    /// Area of what is on the panel, empty while nothing is
+    drawn: Rect,
+    dirty: bool,
+}
+
+impl<'a> Icon<'a> {
+    pub const fn new(position: (u16, u16), image: IconImage<'a>) -> Self {
+        Self {
+            position,
+            image,
+            bc: None,
+            visible: true,
+            drawn: NONE,
+            dirty: true,
+        }
+    }
+
+    pub const fn background(mut self, bc: u16) -> Self {
+        self.bc = Some(bc);
+        self
+    }
+
+    /// Covers the current image and whatever was drawn before it.
+    pub fn bounds(&self) -> Rect {
+        let image = self.image_rect();
+        if self.drawn.is_empty() {
+            return image;
+        }
+        let x = image.x.min(self.drawn.x);
+        let y = image.y.min(self.drawn.y);
+        let right = image.right().max(self.drawn.right());
+        let bottom = image.bottom().max(self.drawn.bottom());
+        Rect::new(x, y, right - x, bottom - y)
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Takes the background of `theme`, if the icon has one of its own.
+    pub fn set_theme(&mut self, theme: &Theme) {
+        if self.bc.is_some() {
+            self.bc = Some(theme.bg);
+        }
+        self.dirty = true;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        if visible != self.visible {
+            self.visible = visible;
+            self.dirty = true;
+        }
+    }
+
+    pub fn set_image(&mut self, image: IconImage<'a>) {
+        self.image = image;
+        self.dirty = true;
+    }
+
+    pub fn position(&self) -> (u16, u16) {
+        self.position
+    }
+
+    /// Moves the icon; the next draw clears the part of the old place the
+    /// image no longer covers.
+    pub fn set_position(&mut self, position: (u16, u16)) {
+        if position != self.position {
+            self.position = position;
+            self.dirty = true;
+        }
+    }
+
+    fn image_rect(&self) -> Rect {
+        let (w, h) = self.image.size();
+        Rect::new(self.position.0, self.position.1, w, h)
+    }
+
+    pub async fn draw<C: timer::GeneralInstance4Channel>(&mut self, lcd: &mut LCD<C>) {
+        self.draw_on(lcd, None).await;
+    }
+
+    pub(super) async fn draw_on<C: timer::GeneralInstance4Channel>(
+        &mut self, lcd: &mut LCD<C>, backdrop: Option<&Backdrop>,
+    ) {
+        self.dirty = false;
+        let (x, y) = self.position;
+        let image = self.image_rect();
+        let opaque = matches!(self.image, IconImage::Picture { .. });
+        // Clear whatever the image does not cover
+        let clear = if self.visible && opaque {
+            uncovered(self.drawn, image)
+        } else {
+            [self.bounds(), NONE, NONE, NONE]
+        };
+        for part in clear {
+            match (self.bc, backdrop) {
+                (Some(bc), _) => lcd.fill_paint(part, &Paint::Solid(bc)).await,
+                (None, Some(backdrop)) => backdrop.clear(lcd, part).await,
+                (None, None) => {}
+            }
+        }
+
+        self.drawn = NONE;
+        if !self.visible {
+            return;
+        }
+        match self.image {
+            IconImage::Picture { data, size } => lcd.show_picture((x, y), size, data).await,
+            IconImage::Sprite(sprite) => lcd.draw_sprite(x, y, &sprite).await,
+        }
+        self.drawn = image;
+    }
+}
+
+/// The parts of `rect` outside `cover`: strips above, below, left and right
+/// of it, some of them empty.
+fn uncovered(rect: Rect, cover: Rect) -> [Rect; 4] {
+    let overlap = rect.intersect(&cover);
+    if overlap.is_empty() {
+        return [rect, NONE, NONE, NONE];
+    }
+    [
+        Rect::new(rect.x, rect.y, rect.w, overlap.y - rect.y),
+        Rect::new(
+            rect.x,
+            overlap.bottom(),
+            rect.w,
+            rect.bottom() - overlap.bottom(),
+        ),
+        Rect::new(rect.x, overlap.y, overlap.x - rect.x, overlap.h),
+        Rect::new(
+            overlap.right(),
+            overlap.y,
+            rect.right() - overlap.right(),
+            overlap.h,
+        ),
+    ]
+}
@@ -0,0 +1,451 @@
+//! On-screen keyboard for entering short ASCII text.
+
+use super::{Nav, Theme, draw_frame};
+use crate::{
+    lcd::{
+        CharMode, Color, LCD, Rect, font::FontSize, number::FmtBuf, paint::Paint, style::GlyphStyle,
+    },
+    settings::{SETTINGS_MAX_VALUE_LEN, SettingsError, SettingsStore},
+    w25qxx::W25Qxx,
+};
+use as_what::{AsU16, AsUsize};
+use core::fmt::Write;
+use embassy_stm32::timer;
+
+/// Longest text the keyboard takes.
+pub const KEYBOARD_MAX_LEN: usize = 32;
+
+const COLS: usize = 10;
+const ROWS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum KeyboardPage {
+    Letters,
+    Digits,
+    Symbols,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum KeyboardEvent {
+    /// The OK key was pressed, [`Keyboard::text`] holds the result
+    Done,
+    /// [`Nav::Back`] was pressed
+    Cancelled,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Char(u8),
+    Shift,
+    Page(KeyboardPage),
+    Space,
+    Backspace,
+    Done,
+}
+
+impl Key {
+    fn label(self, upper: bool) -> &'static str {
+        match self {
+            Key::Char(_) => "",
+            Key::Shift if upper => "AA",
+            Key::Shift => "Aa",
+            Key::Page(KeyboardPage::Letters) => "ab",
+            Key::Page(KeyboardPage::Digits) => "12",
+            Key::Page(KeyboardPage::Symbols) => "#+",
+            Key::Space => "space",
+            Key::Backspace => "<-",
+            Key::Done => "OK",
+        }
+    }
+}
+
+const fn row(chars: &[u8; COLS]) -> [Key; COLS] {
+    let mut keys = [Key::Space; COLS];
+    let mut i = 0;
+    while i < COLS {
+        keys[i] = Key::Char(chars[i]);
+        i += 1;
+    }
+    keys
+}
+
+/// Bottom row of every page. Neighbouring space keys form one wide key.
+const CONTROLS: [Key; COLS] = [
+    Key::Shift,
+    Key::Page(KeyboardPage::Letters),
+    Key::Page(KeyboardPage::Digits),
+    Key::Page(KeyboardPage::Symbols),
+    Key::Space,
+    Key::Space,
+    Key::Space,
+    Key::Space,
+    Key::Backspace,
+    Key::Done,
+];
+
+const LETTERS: [[Key; COLS]; ROWS] = [
+    row(b"qwertyuiop"),
+    row(b"asdfghjkl-"),
+    row(b"zxcvbnm_.@"),
+    CONTROLS,
+];
+
+const DIGITS: [[Key; COLS]; ROWS] = [
+    row(b"1234567890"),
+    row(b"-/:;()$&@\""),
+    row(b".,?!'#%*+="),
+    CONTROLS,
+];
+
+const SYMBOLS: [[Key; COLS]; ROWS] = [
+    row(b"[]{}#%^*+="),
+    row(b"_\\|~<>$&@`"),
+    row(b".,?!'\":;/-"),
+    CONTROLS,
+];
+
+/// A text field over a grid of keys, with pages of letters, digits and
+/// symbols.
+///
+/// [`Nav::Up`] and [`Nav::Down`] step through the keys in reading order,
+/// wrapping around, so a rotary encoder or two buttons reach every key.
+/// [`Nav::Select`] presses the selected key and [`Nav::Back`] cancels.
+///
+/// Moving the selection only redraws the two keys involved.
+pub struct Keyboard {
+    rect: Rect,
+    text: [u8; KEYBOARD_MAX_LEN],
+    len: usize,
+    /// Most characters accepted, at most [`KEYBOARD_MAX_LEN`]
+    max_len: usize,
+    page: KeyboardPage,
+    upper: bool,
+    row: usize,
+    col: usize,
+    fc: u16,
+    bc: u16,
+    accent: u16,
+    font: FontSize,
+    /// Keys to redraw, one bit per cell
+    dirty_keys: u64,
+    dirty_field: bool,
+    full: bool,
+}
+
+impl Keyboard {
+    pub const fn new(rect: Rect, max_len: usize) -> Self {
+        Self {
+            rect,
+            text: [0; KEYBOARD_MAX_LEN],
+            len: 0,
+            max_len: if max_len < KEYBOARD_MAX_LEN {
+                max_len
+            } else {
+                KEYBOARD_MAX_LEN
+            },
+            page: KeyboardPage::Letters,
+            upper: false,
+            row: 0,
+            col: 0,
+            fc: Color::Black as u16,
+            bc: Color::White as u16,
+            accent: Color::Blue as u16,
+            font: FontSize::_6x12,
+            dirty_keys: 0,
+            dirty_field: false,
+            full: true,
+        }
+    }
+
+    pub const fn colors(mut self, fc: u16, bc: u16, accent: u16) -> Self {
+        self.fc = fc;
+        self.bc = bc;
+        self.accent = accent;
+        self
+    }
+
+    pub const fn font(mut self, font: FontSize) -> Self {
+        self.font = font;
+        self
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.full || self.dirty_field || self.dirty_keys != 0
+    }
+
+    pub fn invalidate(&mut self) {
+        self.full = true;
+    }
+
+    /// Takes the colors and the small font of `theme`.
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.fc = theme.fg;
+        self.bc = theme.bg;
+        self.accent = theme.accent;
+        self.font = theme.small_font;
+        self.full = true;
+    }
+
+    pub fn text(&self) -> &str {
+        // Only ASCII is ever stored
+        core::str::from_utf8(&self.text[..self.len]).unwrap_or("")
+    }
+
+    /// Replaces the text, keeping the printable ASCII characters that fit.
+    pub fn set_text(&mut self, text: &str) {
+        self.len = 0;
+        for byte in text
+            .bytes()
+            .filter(|byte| byte.is_ascii_graphic() || *byte == b' ')
+        {
+            if self.len == self.max_len {
+                break;
+            }
+            self.text[self.len] = byte;
+            self.len += 1;
+        }
+        self.dirty_field = true;
+    }
+
+    /// Fills the text in from `key` of the settings store.
+    pub async fn load(
+        &mut self, flash: &mut W25Qxx, store: &SettingsStore, key: &str,
+    ) -> Result<(), SettingsError> {
+        let mut buf = [0u8; SETTINGS_MAX_VALUE_LEN];
+        let len = store.read(flash, key, &mut buf).await?;
+        let text = core::str::from_utf8(&buf[..len]).unwrap_or("");
+        self.set_text(text);
+        Ok(())
+    }
+
+    /// Stores the text under `key` of the settings store.
+    pub async fn save(
+        &self, flash: &mut W25Qxx, store: &mut SettingsStore, key: &str,
+    ) -> Result<(), SettingsError> {
+        store.write(flash, key, self.text().as_bytes()).await
+    }
+
+    pub fn page(&self) -> KeyboardPage {
+        self.page
+    }
+
+    fn keys(&self) -> &'static [[Key; COLS]; ROWS] {
+        match self.page {
+            KeyboardPage::Letters => &LETTERS,
+            KeyboardPage::Digits => &DIGITS,
+            KeyboardPage::Symbols => &SYMBOLS,
+        }
+    }
+
+    /// First and last column of the key at `(row, col)`, wider than one for
+    /// the space bar.
+    fn span(&self, row: usize, col: usize) -> (usize, usize) {
+        let keys = &self.keys()[row];
+        if keys[col] != Key::Space {
+            return (col, col);
+        }
+        let mut first = col;
+        while first > 0 && keys[first - 1] == Key::Space {
+            first -= 1;
+        }
+        let mut last = col;
+        while last + 1 < COLS && keys[last + 1] == Key::Space {
+            last += 1;
+        }
+        (first, last)
+    }
+
+    fn touch(&mut self, row: usize, col: usize) {
+        let (first, last) = self.span(row, col);
+        for col in first..=last {
+            self.dirty_keys |= 1 << (row * COLS + col);
+        }
+    }
+
+    fn select(&mut self, row: usize, col: usize) {
+        self.touch(self.row, self.col);
+        self.row = row;
+        self.col = col;
+        self.touch(row, col);
+    }
+
+    /// The key after the selected one in reading order, or before it.
+    fn step(&self, forward: bool) -> (usize, usize) {
+        let (first, last) = self.span(self.row, self.col);
+        let count = ROWS * COLS;
+        let index = if forward {
+            (self.row * COLS + last + 1) % count
+        } else {
+            (self.row * COLS + first + count - 1) % count
+        };
+        (index / COLS, index % COLS)
+    }
+
+    /// Handles one navigation event, returning whether editing ended.
+    pub fn handle(&mut self, nav: Nav) -> Option<KeyboardEvent> {
+        match nav {
+            Nav::Up | Nav::Down => {
+                let (row, col) = self.step(nav == Nav::Down);
+                self.select(row, col);
+            }
+            Nav::Select => return self.press(self.keys()[self.row][self.col]),
+            Nav::Back => return Some(KeyboardEvent::Cancelled),
+        }
+        None
+    }
+
+    fn press(&mut self, key: Key) -> Option<KeyboardEvent> {
+        match key {
+            Key::Char(ch) => {
+                let ch = if self.upper {
+                    ch.to_ascii_uppercase()
+                } else {
+                    ch
+                };
+                self.push(ch);
+            }
+            Key::Space => self.push(b' '),
+            Key::Backspace => {
+                if self.len > 0 {
+                    self.len -= 1;
+                    self.dirty_field = true;
+                }
+            }
+            Key::Shift => {
+                self.upper = !self.upper;
+                // The letters and the shift key change
+                self.full = true;
+            }
+            Key::Page(page) => {
+                if page != self.page {
+                    self.page = page;
+                    self.full = true;
+                }
+            }
+            Key::Done => return Some(KeyboardEvent::Done),
+        }
+        None
+    }
+
+    fn push(&mut self, ch: u8) {
+        if self.len < self.max_len {
+            self.text[self.len] = ch;
+            self.len += 1;
+            self.dirty_field = true;
+        }
+    }
+
+    /// Height of the text field above the keys: the text inside a one
+    /// pixel frame.
+    fn field_height(&self) -> u16 {
+        self.font.y().as_u16() + 2
+    }
+
+    /// Size of one cell of the grid.
+    fn cell(&self) -> (u16, u16) {
+        let keys_h = self.rect.h.saturating_sub(self.field_height());
+        (self.rect.w / COLS.as_u16(), keys_h / ROWS.as_u16())
+    }
+
+    pub async fn draw<C: timer::GeneralInstance4Channel>(&mut self, lcd: &mut LCD<C>) {
+        let (cell_w, cell_h) = self.cell();
+        if self.full {
+            lcd.fill_paint(self.rect, &Paint::Solid(self.bc)).await;
+            let field = Rect::new(
+                self.rect.x,
+                self.rect.y,
+                self.rect.w,
+                self.field_height().min(self.rect.h),
+            );
+            draw_frame(lcd, field, 1, self.fc).await;
+        }
+
+        if self.full || self.dirty_field {
+            self.draw_field(lcd).await;
+        }
+
+        for row in 0..ROWS {
+            for col in 0..COLS {
+                let bit = 1 << (row * COLS + col);
+                if !self.full && self.dirty_keys & bit == 0 {
+                    continue;
+                }
+                let (first, last) = self.span(row, col);
+                if col != first {
+                    continue;
+                }
+                let rect = Rect::new(
+                    self.rect.x + first.as_u16() * cell_w,
+                    self.rect.y + self.field_height() + row.as_u16() * cell_h,
+                    (last - first + 1).as_u16() * cell_w,
+                    cell_h,
+                );
+                self.draw_key(lcd, row, col, rect).await;
+            }
+        }
+
+        self.full = false;
+        self.dirty_field = false;
+        self.dirty_keys = 0;
+    }
+
+    /// The end of the text that fits, followed by the cursor.
+    async fn draw_field<C: timer::GeneralInstance4Channel>(&self, lcd: &mut LCD<C>) {
+        let char_w = self.font.x().as_u16();
+        let fits = (self.rect.w.saturating_sub(4) / char_w).as_usize();
+        if fits == 0 {
+            return;
+        }
+        let shown = self.len.min(fits - 1);
+        let mut line = [b' '; KEYBOARD_MAX_LEN + 1];
+        let line = &mut line[..fits.min(KEYBOARD_MAX_LEN + 1)];
+        line[..shown].copy_from_slice(&self.text[self.len - shown..self.len]);
+        if shown < line.len() {
+            line[shown] = b'_';
+        }
+        let y = self.rect.y + 1;
+        let text = core::str::from_utf8(line).unwrap_or("");
+        lcd.show_string(
+            (self.rect.x + 2, y),
+            text,
+            self.font,
+            &GlyphStyle::new(self.fc, self.bc),
+        )
+        .await;
+    }
+
+    async fn draw_key<C: timer::GeneralInstance4Channel>(
+        &self, lcd: &mut LCD<C>, row: usize, col: usize, rect: Rect,
+    ) {
+        let key = self.keys()[row][col];
+        let (first, last) = self.span(row, col);
+        let selected = self.row == row && (first..=last).contains(&self.col);
+        let (fc, bc) = if selected {
+            (self.bc, self.accent)
+        } else {
+            (self.fc, self.bc)
+        };
+
+        let mut label = FmtBuf::<8>::new();
+        let _ = match key {
+            Key::Char(ch) if self.upper => label.write_char(ch.to_ascii_uppercase() as char),
+            Key::Char(ch) => label.write_char(ch as char),
+            key => label.write_str(key.label(self.upper)),
+        };
+        let label = label.as_str();
+
+        lcd.fill_paint(rect, &Paint::Solid(bc)).await;
+        let w = label.len().as_u16() * self.font.x().as_u16();
+        let h = self.font.y().as_u16();
+        if w <= rect.w && h <= rect.h {
+            let x = rect.x + (rect.w - w) / 2;
+            let y = rect.y + (rect.h - h) / 2;
+            let style = GlyphStyle::new(fc, bc).mode(CharMode::Overlay);
+            lcd.show_string((x, y), label, self.font, &style).await;
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Text in a fixed box.
+
+use super::{Backdrop, Theme};
+use crate::lcd::{
+    CharMode, Color, LCD, Rect,
+    layout::TextLayout,
+    number::{FmtBuf, Number, NumberFormat},
+};
+use core::fmt::{self, Write};
+use embassy_stm32::timer;
+
+/// Longest text a [`Label`] holds, in bytes. Longer text is cut off at a
+/// character boundary.
+pub const LABEL_MAX_LEN: usize = 32;
+
+/// Text wrapped and aligned inside its bounds by a [`TextLayout`].
+///
+/// Without a background color the label is drawn over the background of
+/// the panel it is in, or over whatever is on the screen outside of a panel.
+pub struct Label {
+    rect: Rect,
+    text: FmtBuf<LABEL_MAX_LEN>,
+    layout: TextLayout,
+    fc: u16,
+    bc: Option<u16>,
+    dirty: bool,
+}
+
+impl Label {
+    pub fn new(rect: Rect, text: &str, layout: TextLayout) -> Self {
+        let mut label = Self {
+            rect,
+            text: FmtBuf::new(),
+            layout,
+            fc: Color::Black as u16,
+            bc: Some(Color::White as u16),
+            dirty: true,
+        };
+        copy_truncated(&mut label.text, text);
+        label
+    }
+
+    pub const fn colors(mut self, fc: u16, bc: u16) -> Self {
+        self.fc = fc;
+        self.bc = Some(bc);
+        self
+    }
+
+    /// Draws the text over the background of the enclosing panel.
+    pub const fn transparent(mut self) -> Self {
+        self.bc = None;
+        self
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+
+    pub fn text(&self) -> &str {
+        self.text.as_str()
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        let mut buf = FmtBuf::new();
+        copy_truncated(&mut buf, text);
+        self.replace(buf);
+    }
+
+    /// Sets the text from `format_args!`, cut off if it does not fit.
+    pub fn set_fmt(&mut self, args: fmt::Arguments) {
+        let mut buf = FmtBuf::new();
+        let mut truncated = Truncated(&mut buf);
+        // Running out of room only cuts the text short
+        let _ = truncated.write_fmt(args);
+        self.replace(buf);
+    }
+
+    pub fn set_number(&mut self, num: impl Into<Number>, format: &NumberFormat) {
+        let mut buf = FmtBuf::new();
+        format.format(num.into(), &mut buf);
+        self.replace(buf);
+    }
+
+    pub fn set_colors(&mut self, fc: u16, bc: u16) {
+        if (self.fc, self.bc) != (fc, Some(bc)) {
+            self.fc = fc;
+            self.bc = Some(bc);
+            self.dirty = true;
+        }
+    }
+
+    /// Takes the colors and fonts of `theme`; a transparent label stays
+    /// transparent.
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.layout = theme.fonts(self.layout);
+        self.fc = theme.fg;
+        if self.bc.is_some() {
+            self.bc = Some(theme.bg);
+        }
+        self.dirty = true;
+    }
+
+    pub async fn draw<C: timer::GeneralInstance4Channel>(&mut self, lcd: &mut LCD<C>) {
+        self.draw_on(lcd, None).await;
+    }
+
+    pub(super) async fn draw_on<C: timer::GeneralInstance4Channel>(
+        &mut self, lcd: &mut LCD<C>, backdrop: Option<&Backdrop>,
+    ) {
+        self.dirty = false;
+        match self.bc {
+            Some(bc) => {
+                lcd.show_text(
+                    self.rect,
+                    self.text.as_str(),
+                    self.fc,
+                    bc,
+                    &self.layout,
+                    CharMode::NonOverlay,
+                )
+                .await;
+            }
+            None => {
+                if let Some(backdrop) = backdrop {
+                    backdrop.clear(lcd, self.rect).await;
+                }
+                lcd.show_text(
+                    self.rect,
+                    self.text.as_str(),
+                    self.fc,
+                    0,
+                    &self.layout,
+                    CharMode::Overlay,
+                )
+                .await;
+            }
+        }
+    }
+
+    fn replace(&mut self, text: FmtBuf<LABEL_MAX_LEN>) {
+        if text.as_str() != self.text.as_str() {
+            self.text = text;
+            self.dirty = true;
+        }
+    }
+}
+
+fn copy_truncated(buf: &mut FmtBuf<LABEL_MAX_LEN>, text: &str) {
+    let _ = Truncated(buf).write_str(text);
+}
+
+/// Writes as many whole characters as fit, then fails.
+struct Truncated<'a>(&'a mut FmtBuf<LABEL_MAX_LEN>);
+
+impl Write for Truncated<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.0.write_str(s).is_ok() {
+            return Ok(());
+        }
+        for ch in s.chars() {
+            self.0.write_char(ch)?;
+        }
+        Ok(())
+    }
+}
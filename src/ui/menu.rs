@@ -0,0 +1,582 @@
+//! Hierarchical menu driven by [`Nav`] events.
+
+use super::{Nav, Theme};
+use crate::lcd::{
+    CharMode, Color, LCD, Rect,
+    layout::{HAlign, TextLayout, VAlign},
+    number::FmtBuf,
+    paint::Paint,
+};
+use as_what::{AsU16, AsUsize};
+use core::fmt::Write;
+use embassy_stm32::timer;
+
+/// Deepest nesting of submenus, counting the top level.
+pub const MENU_MAX_DEPTH: usize = 4;
+
+/// Width of the scroll bar shown when not every item fits.
+const SCROLLBAR_WIDTH: u16 = 2;
+
+/// Longest value text, such as a number with its unit.
+const VALUE_MAX_LEN: usize = 16;
+
+pub enum MenuItemKind<'a> {
+    /// Opened with [`Nav::Select`], left with [`Nav::Back`]
+    Submenu(&'a mut [MenuItem<'a>]),
+    /// Flipped with [`Nav::Select`]
+    Toggle(bool),
+    /// Edited in place: [`Nav::Select`] starts editing, [`Nav::Up`] and
+    /// [`Nav::Down`] step the value, [`Nav::Select`] keeps it and
+    /// [`Nav::Back`] restores the old one
+    Number {
+        value: i32,
+        min: i32,
+        max: i32,
+        step: i32,
+        unit: &'a str,
+    },
+    /// Reported with [`Nav::Select`]
+    Action,
+}
+
+/// An entry of a [`Menu`], identified by `id` in [`MenuEvent`]s.
+pub struct MenuItem<'a> {
+    id: u16,
+    label: &'a str,
+    kind: MenuItemKind<'a>,
+}
+
+impl<'a> MenuItem<'a> {
+    pub const fn submenu(id: u16, label: &'a str, items: &'a mut [MenuItem<'a>]) -> Self {
+        Self {
+            id,
+            label,
+            kind: MenuItemKind::Submenu(items),
+        }
+    }
+
+    pub const fn toggle(id: u16, label: &'a str, on: bool) -> Self {
+        Self {
+            id,
+            label,
+            kind: MenuItemKind::Toggle(on),
+        }
+    }
+
+    /// A number between `min` and `max`, changed by `step` at a time.
+    pub const fn number(
+        id: u16, label: &'a str, value: i32, (min, max): (i32, i32), step: i32,
+    ) -> Self {
+        Self {
+            id,
+            label,
+            kind: MenuItemKind::Number {
+                value,
+                min,
+                max,
+                step,
+                unit: "",
+            },
+        }
+    }
+
+    pub const fn action(id: u16, label: &'a str) -> Self {
+        Self {
+            id,
+            label,
+            kind: MenuItemKind::Action,
+        }
+    }
+
+    /// Text shown after the value of a number item.
+    pub const fn unit(mut self, unit: &'a str) -> Self {
+        if let MenuItemKind::Number { unit: u, .. } = &mut self.kind {
+            *u = unit;
+        }
+        self
+    }
+
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    pub fn label(&self) -> &'a str {
+        self.label
+    }
+
+    pub fn kind(&self) -> &MenuItemKind<'a> {
+        &self.kind
+    }
+
+    /// State of a toggle item.
+    pub fn is_on(&self) -> Option<bool> {
+        match self.kind {
+            MenuItemKind::Toggle(on) => Some(on),
+            _ => None,
+        }
+    }
+
+    /// Value of a number item.
+    pub fn value(&self) -> Option<i32> {
+        match self.kind {
+            MenuItemKind::Number { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+
+    fn find(&self, id: u16) -> Option<&MenuItem<'a>> {
+        if self.id == id {
+            return Some(self);
+        }
+        match &self.kind {
+            MenuItemKind::Submenu(items) => items.iter().find_map(|item| item.find(id)),
+            _ => None,
+        }
+    }
+
+    fn find_mut(&mut self, id: u16) -> Option<&mut MenuItem<'a>> {
+        if self.id == id {
+            return Some(self);
+        }
+        match &mut self.kind {
+            MenuItemKind::Submenu(items) => items.iter_mut().find_map(|item| item.find_mut(id)),
+            _ => None,
+        }
+    }
+
+    /// What is shown right aligned after the label.
+    fn value_text(&self, editing: bool, buf: &mut FmtBuf<VALUE_MAX_LEN>) {
+        // A value too long for the buffer is shown cut off
+        let _ = match &self.kind {
+            MenuItemKind::Submenu(_) => buf.write_str(">"),
+            MenuItemKind::Toggle(true) => buf.write_str("ON"),
+            MenuItemKind::Toggle(false) => buf.write_str("OFF"),
+            MenuItemKind::Number { value, unit, .. } if editing => {
+                write!(buf, "[{}{}]", value, unit)
+            }
+            MenuItemKind::Number { value, unit, .. } => write!(buf, "{}{}", value, unit),
+            MenuItemKind::Action => Ok(()),
+        };
+    }
+}
+
+/// What the application has to act on after a [`Nav`] event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum MenuEvent {
+    Action(u16),
+    Toggled(u16, bool),
+    /// A number item was edited and the new value kept
+    Changed(u16, i32),
+    /// [`Nav::Back`] at the top level
+    Exit,
+}
+
+/// A list of [`MenuItem`]s under a title bar, one item per line, with the
+/// selected item highlighted in the accent color.
+///
+/// Moving the selection only redraws the two lines involved; scrolling,
+/// entering and leaving submenus redraw the whole menu.
+pub struct Menu<'a> {
+    rect: Rect,
+    title: &'a str,
+    items: &'a mut [MenuItem<'a>],
+    layout: TextLayout,
+    fc: u16,
+    bc: u16,
+    accent: u16,
+    /// Selected item of every open level
+    path: [usize; MENU_MAX_DEPTH],
+    depth: usize,
+    /// First visible item
+    top: usize,
+    /// Value of the number item being edited before editing started
+    editing: Option<i32>,
+    /// Visible lines to redraw, by position on screen
+    dirty_lines: u32,
+    full: bool,
+}
+
+impl<'a> Menu<'a> {
+    pub const fn new(
+        rect: Rect, title: &'a str, items: &'a mut [MenuItem<'a>], layout: TextLayout,
+    ) -> Self {
+        Self {
+            rect,
+            title,
+            items,
+            layout: layout.align(HAlign::Left, VAlign::Middle).ellipsis(true),
+            fc: Color::Black as u16,
+            bc: Color::White as u16,
+            accent: Color::Blue as u16,
+            path: [0; MENU_MAX_DEPTH],
+            depth: 0,
+            top: 0,
+            editing: None,
+            dirty_lines: 0,
+            full: true,
+        }
+    }
+
+    pub const fn colors(mut self, fc: u16, bc: u16, accent: u16) -> Self {
+        self.fc = fc;
+        self.bc = bc;
+        self.accent = accent;
+        self
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.full || self.dirty_lines != 0
+    }
+
+    pub fn invalidate(&mut self) {
+        self.full = true;
+    }
+
+    /// Takes the colors and fonts of `theme`.
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.layout = theme.fonts(self.layout);
+        self.fc = theme.fg;
+        self.bc = theme.bg;
+        self.accent = theme.accent;
+        self.full = true;
+    }
+
+    /// Whether a number item is being edited.
+    pub fn is_editing(&self) -> bool {
+        self.editing.is_some()
+    }
+
+    /// The item with `id` anywhere in the menu.
+    pub fn find(&self, id: u16) -> Option<&MenuItem<'a>> {
+        self.items.iter().find_map(|item| item.find(id))
+    }
+
+    /// Sets a toggle item from outside of the menu.
+    pub fn set_on(&mut self, id: u16, on: bool) {
+        if let Some(MenuItem {
+            kind: MenuItemKind::Toggle(current),
+            ..
+        }) = self.find_mut(id)
+        {
+            *current = on;
+            self.full = true;
+        }
+    }
+
+    /// Sets a number item from outside of the menu, clamped to its range.
+    pub fn set_value(&mut self, id: u16, value: i32) {
+        if let Some(MenuItem {
+            kind:
+                MenuItemKind::Number {
+                    value: current,
+                    min,
+                    max,
+                    ..
+                },
+            ..
+        }) = self.find_mut(id)
+        {
+            *current = value.clamp(*min, (*max).max(*min));
+            self.full = true;
+        }
+    }
+
+    fn find_mut(&mut self, id: u16) -> Option<&mut MenuItem<'a>> {
+        self.items.iter_mut().find_map(|item| item.find_mut(id))
+    }
+
+    /// Items of the open level.
+    fn level(&self) -> &[MenuItem<'a>] {
+        let mut items: &[MenuItem<'a>] = self.items;
+        for &index in &self.path[..self.depth] {
+            match &items[index].kind {
+                MenuItemKind::Submenu(children) => items = children,
+                // Only submenus are ever opened
+                _ => return &[],
+            }
+        }
+        items
+    }
+
+    fn level_mut(&mut self) -> &mut [MenuItem<'a>] {
+        let mut items: &mut [MenuItem<'a>] = self.items;
+        for &index in &self.path[..self.depth] {
+            match &mut items[index].kind {
+                MenuItemKind::Submenu(children) => items = children,
+                _ => return &mut [],
+            }
+        }
+        items
+    }
+
+    fn selected(&self) -> usize {
+        self.path[self.depth]
+    }
+
+    fn line_height(&self) -> u16 {
+        self.layout.line_height().max(1)
+    }
+
+    /// Lines available for items, below the title.
+    fn visible_lines(&self) -> usize {
+        (self.rect.h / self.line_height())
+            .saturating_sub(1)
+            .max(1)
+            .as_usize()
+    }
+
+    /// Marks the line of item `index` for redrawing.
+    fn touch(&mut self, index: usize) {
+        if let Some(line) = index.checked_sub(self.top) {
+            if line < self.visible_lines() {
+                self.dirty_lines |= 1 << line.min(31);
+            }
+        }
+    }
+
+    fn select(&mut self, index: usize) {
+        let old = self.selected();
+        self.path[self.depth] = index;
+        let visible = self.visible_lines();
+        if index < self.top {
+            self.top = index;
+            self.full = true;
+        } else if index >= self.top + visible {
+            self.top = index + 1 - visible;
+            self.full = true;
+        } else {
+            self.touch(old);
+            self.touch(index);
+        }
+    }
+
+    /// Handles one navigation event, returning what the application has to
+    /// act on, if anything.
+    pub fn handle(&mut self, nav: Nav) -> Option<MenuEvent> {
+        let selected = self.selected();
+        if self.level().is_empty() && nav != Nav::Back {
+            return None;
+        }
+
+        if let Some(old) = self.editing {
+            let item = &mut self.level_mut()[selected];
+            let id = item.id;
+            let MenuItemKind::Number {
+                value,
+                min,
+                max,
+                step,
+                ..
+            } = &mut item.kind
+            else {
+                self.editing = None;
+                return None;
+            };
+            let event = match nav {
+                Nav::Up => {
+                    *value = value.saturating_add(*step).min(*max);
+                    None
+                }
+                Nav::Down => {
+                    *value = value.saturating_sub(*step).max(*min);
+                    None
+                }
+                Nav::Select => Some(MenuEvent::Changed(id, *value)),
+                Nav::Back => {
+                    *value = old;
+                    None
+                }
+            };
+            if matches!(nav, Nav::Select | Nav::Back) {
+                self.editing = None;
+            }
+            self.touch(selected);
+            return event;
+        }
+
+        let len = self.level().len();
+        match nav {
+            Nav::Up => {
+                self.select(if selected == 0 { len - 1 } else { selected - 1 });
+                None
+            }
+            Nav::Down => {
+                self.select(if selected + 1 == len { 0 } else { selected + 1 });
+                None
+            }
+            Nav::Select => {
+                let item = &mut self.level_mut()[selected];
+                let id = item.id;
+                match &mut item.kind {
+                    MenuItemKind::Submenu(_) => {
+                        if self.depth + 1 < MENU_MAX_DEPTH {
+                            self.depth += 1;
+                            self.path[self.depth] = 0;
+                            self.top = 0;
+                            self.full = true;
+                        }
+                        None
+                    }
+                    MenuItemKind::Toggle(on) => {
+                        *on = !*on;
+                        let on = *on;
+                        self.touch(selected);
+                        Some(MenuEvent::Toggled(id, on))
+                    }
+                    MenuItemKind::Number { value, .. } => {
+                        self.editing = Some(*value);
+                        self.touch(selected);
+                        None
+                    }
+                    MenuItemKind::Action => Some(MenuEvent::Action(id)),
+                }
+            }
+            Nav::Back => {
+                if self.depth == 0 {
+                    return Some(MenuEvent::Exit);
+                }
+                self.depth -= 1;
+                // Show the submenu just left
+                self.top = self.selected().saturating_sub(self.visible_lines() - 1);
+                self.full = true;
+                None
+            }
+        }
+    }
+
+    pub async fn draw<C: timer::GeneralInstance4Channel>(&mut self, lcd: &mut LCD<C>) {
+        let line_h = self.line_height();
+        let visible = self.visible_lines();
+        let len = self.level().len();
+        let list = Rect::new(
+            self.rect.x,
+            self.rect.y + line_h,
+            self.rect.w,
+            self.rect.h.saturating_sub(line_h),
+        );
+        let scrollbar = len > visible;
+        let line_w = if scrollbar {
+            list.w.saturating_sub(SCROLLBAR_WIDTH)
+        } else {
+            list.w
+        };
+
+        if self.full {
+            let title = match self.depth {
+                0 => self.title,
+                depth => {
+                    let mut items: &[MenuItem<'a>] = self.items;
+                    for &index in &self.path[..depth - 1] {
+                        if let MenuItemKind::Submenu(children) = &items[index].kind {
+                            items = children;
+                        }
+                    }
+                    items[self.path[depth - 1]].label
+                }
+            };
+            let title_rect = Rect::new(self.rect.x, self.rect.y, self.rect.w, line_h);
+            let title_layout = self.layout.align(HAlign::Center, VAlign::Middle);
+            lcd.show_text(
+                title_rect,
+                title,
+                self.bc,
+                self.fc,
+                &title_layout,
+                CharMode::NonOverlay,
+            )
+            .await;
+
+            // Below the last line
+            let used = (visible.min(len).as_u16() * line_h).min(list.h);
+            let rest = Rect::new(list.x, list.y + used, line_w, list.h - used);
+            lcd.fill_paint(rest, &Paint::Solid(self.bc)).await;
+
+            if scrollbar {
+                let track = Rect::new(list.x + line_w, list.y, list.w - line_w, list.h);
+                let thumb_h = (list.h.as_usize() * visible / len).max(1).as_u16();
+                let thumb_y = (list.h - thumb_h).as_usize() * self.top / (len - visible);
+                lcd.fill_paint(track, &Paint::Solid(self.bc)).await;
+                lcd.fill_paint(
+                    Rect::new(track.x, track.y + thumb_y.as_u16(), track.w, thumb_h),
+                    &Paint::Solid(self.fc),
+                )
+                .await;
+            }
+        }
+
+        for line in 0..visible.min(len - self.top.min(len)) {
+            if !self.full && self.dirty_lines & (1 << line.min(31)) == 0 {
+                continue;
+            }
+            let index = self.top + line;
+            let rect = Rect::new(list.x, list.y + line.as_u16() * line_h, line_w, line_h);
+            self.draw_line(lcd, index, rect).await;
+        }
+
+        self.full = false;
+        self.dirty_lines = 0;
+    }
+
+    async fn draw_line<C: timer::GeneralInstance4Channel>(
+        &self, lcd: &mut LCD<C>, index: usize, rect: Rect,
+    ) {
+        let item = &self.level()[index];
+        let (fc, bc) = if index == self.selected() {
+            (self.bc, self.accent)
+        } else {
+            (self.fc, self.bc)
+        };
+
+        let mut value = FmtBuf::<VALUE_MAX_LEN>::new();
+        let editing = self.editing.is_some() && index == self.selected();
+        item.value_text(editing, &mut value);
+
+        // One pixel of margin on both sides
+        let margin = 1.min(rect.w / 2);
+        let value_w = self
+            .layout
+            .text_width(value.as_str())
+            .min(rect.w - 2 * margin);
+        lcd.fill_paint(Rect::new(rect.x, rect.y, margin, rect.h), &Paint::Solid(bc))
+            .await;
+        let label = Rect::new(
+            rect.x + margin,
+            rect.y,
+            rect.w - value_w - 2 * margin,
+            rect.h,
+        );
+        lcd.show_text(
+            label,
+            item.label,
+            fc,
+            bc,
+            &self.layout,
+            CharMode::NonOverlay,
+        )
+        .await;
+        let value_rect = Rect::new(label.right(), rect.y, value_w, rect.h);
+        lcd.show_text(
+            value_rect,
+            value.as_str(),
+            fc,
+            bc,
+            &self.layout,
+            CharMode::NonOverlay,
+        )
+        .await;
+        lcd.fill_paint(
+            Rect::new(
+                value_rect.right(),
+                rect.y,
+                rect.right() - value_rect.right(),
+                rect.h,
+            ),
+            &Paint::Solid(bc),
+        )
+        .await;
+    }
+}
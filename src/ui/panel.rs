@@ -0,0 +1,94 @@
+//! Container of other widgets.
+
+use super::{Theme, Widget, draw_frame};
+use crate::lcd::{Color, LCD, Rect, paint::Paint};
+use embassy_stm32::timer;
+
+/// Rectangle with a background that its children are drawn on.
+///
+/// The children are not clipped to the panel, they are expected to lie
+/// inside it. Children without a background color of their own, like
+/// transparent labels, are cleared to the panel background before they are
+/// redrawn.
+pub struct Panel<'a> {
+    rect: Rect,
+    background: Paint,
+    border: Option<u16>,
+    children: &'a mut [Widget<'a>],
+    dirty: bool,
+}
+
+impl<'a> Panel<'a> {
+    pub const fn new(rect: Rect, children: &'a mut [Widget<'a>]) -> Self {
+        Self {
+            rect,
+            background: Paint::Solid(Color::White as u16),
+            border: None,
+            children,
+            dirty: true,
+        }
+    }
+
+    pub const fn background(mut self, background: Paint) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// One pixel border around the panel.
+    pub const fn border(mut self, color: u16) -> Self {
+        self.border = Some(color);
+        self
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+
+    /// The background paint.
+    pub fn paint(&self) -> Paint {
+        self.background
+    }
+
+    /// Changes the background, which redraws the panel and its children.
+    pub fn set_background(&mut self, background: Paint) {
+        if background != self.background {
+            self.background = background;
+            self.dirty = true;
+        }
+    }
+
+    /// A solid background, without touching the children; see
+    /// [`super::set_theme`] for the whole tree.
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.background = Paint::Solid(theme.bg);
+        if self.border.is_some() {
+            self.border = Some(theme.fg);
+        }
+        self.dirty = true;
+    }
+
+    pub fn children(&self) -> &[Widget<'a>] {
+        self.children
+    }
+
+    pub fn children_mut(&mut self) -> &mut [Widget<'a>] {
+        self.children
+    }
+
+    /// Draws the panel itself, without its children.
+    pub async fn draw<C: timer::GeneralInstance4Channel>(&mut self, lcd: &mut LCD<C>) {
+        self.dirty = false;
+        lcd.fill_paint(self.rect, &self.background).await;
+        if let Some(color) = self.border {
+            draw_frame(lcd, self.rect, 1, color).await;
+        }
+    }
+}
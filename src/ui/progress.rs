@@ -0,0 +1,138 @@
+//! Horizontal progress bar.
+
+use super::{Theme, draw_frame, inset};
+use crate::lcd::{Color, LCD, Rect, paint::Paint};
+use embassy_stm32::timer;
+
+/// Bar filled from the left in proportion to `value / max`.
+///
+/// After the first draw only the part between the old and the new end of
+/// the fill is redrawn. The fill paint is laid out over the whole bar, so a
+/// gradient shows how far along the bar is.
+pub struct ProgressBar {
+    rect: Rect,
+    value: u16,
+    max: u16,
+    fill: Paint,
+    track: u16,
+    border: Option<u16>,
+    /// Width of the fill on the panel, `None` before the first draw
+    drawn: Option<u16>,
+    dirty: bool,
+}
+
+impl ProgressBar {
+    pub const fn new(rect: Rect, max: u16) -> Self {
+        Self {
+            rect,
+            value: 0,
+            max,
+            fill: Paint::Solid(Color::Blue as u16),
+            track: Color::Lgray as u16,
+            border: None,
+            drawn: None,
+            dirty: true,
+        }
+    }
+
+    pub const fn fill(mut self, fill: Paint) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    pub const fn track(mut self, track: u16) -> Self {
+        self.track = track;
+        self
+    }
+
+    /// One pixel border around the bar.
+    pub const fn border(mut self, color: u16) -> Self {
+        self.border = Some(color);
+        self
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn invalidate(&mut self) {
+        self.drawn = None;
+        self.dirty = true;
+    }
+
+    pub fn value(&self) -> u16 {
+        self.value
+    }
+
+    pub fn max(&self) -> u16 {
+        self.max
+    }
+
+    /// Sets the value, clamped to the maximum.
+    pub fn set_value(&mut self, value: u16) {
+        let value = value.min(self.max);
+        if value != self.value {
+            self.value = value;
+            self.dirty = true;
+        }
+    }
+
+    pub fn set_fill(&mut self, fill: Paint) {
+        if fill != self.fill {
+            self.fill = fill;
+            self.invalidate();
+        }
+    }
+
+    /// A solid fill in the accent color on a muted track.
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.fill = Paint::Solid(theme.accent);
+        self.track = theme.muted;
+        if self.border.is_some() {
+            self.border = Some(theme.fg);
+        }
+        self.invalidate();
+    }
+
+    /// Area inside the border.
+    fn inner(&self) -> Rect {
+        match self.border {
+            Some(_) => inset(self.rect, 1),
+            None => self.rect,
+        }
+    }
+
+    pub async fn draw<C: timer::GeneralInstance4Channel>(&mut self, lcd: &mut LCD<C>) {
+        self.dirty = false;
+        let inner = self.inner();
+        let width = match self.max {
+            0 => 0,
+            max => (u32::from(inner.w) * u32::from(self.value) / u32::from(max)) as u16,
+        };
+
+        let (from, to) = match self.drawn {
+            Some(drawn) => (drawn.min(width), drawn.max(width)),
+            None => {
+                if let Some(color) = self.border {
+                    draw_frame(lcd, self.rect, 1, color).await;
+                }
+                (0, inner.w)
+            }
+        };
+        let fill = Rect::new(inner.x + from, inner.y, width.saturating_sub(from), inner.h);
+        lcd.fill_paint_clipped(inner, fill, &self.fill).await;
+        let track = Rect::new(
+            inner.x + width.max(from),
+            inner.y,
+            to.saturating_sub(width),
+            inner.h,
+        );
+        lcd.fill_paint(track, &Paint::Solid(self.track)).await;
+
+        self.drawn = Some(width);
+    }
+}
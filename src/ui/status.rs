@@ -0,0 +1,349 @@
+//! Status bar along the top of the panel.
+
+use super::Theme;
+use crate::{
+    lcd::{
+        Color, LCD, Rect, blend, font::FontSize, number::FmtBuf, paint::Paint, shared::SharedLcd,
+        style::GlyphStyle,
+    },
+    rtc::Rtc,
+    w25qxx::FlashHealth,
+};
+use as_what::AsU16;
+use core::{
+    fmt::Write,
+    sync::atomic::{AtomicU8, AtomicU32, Ordering},
+};
+use embassy_futures::select::select;
+use embassy_stm32::timer;
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, signal::Signal};
+use embassy_time::{Duration, Instant, Ticker, Timer};
+
+/// Height of a status bar drawn in [`FontSize::_6x12`], the small font of
+/// the built-in themes.
+pub const STATUS_BAR_HEIGHT: u16 = 12;
+
+/// How long the UART indicator stays lit after activity.
+const UART_BLINK_MS: u64 = 100;
+
+/// Time of day when no clock has been reported.
+const NO_TIME: u32 = u32::MAX;
+
+/// Bars of the backlight indicator, each standing for an equal share of
+/// the brightness.
+const BACKLIGHT_BARS: u16 = 4;
+
+/// Parts of the bar to redraw.
+const TIME: u8 = 1 << 0;
+const FLASH: u8 = 1 << 1;
+const UART: u8 = 1 << 2;
+const BACKLIGHT: u8 = 1 << 3;
+const ALL: u8 = TIME | FLASH | UART | BACKLIGHT;
+
+/// What the status bar shows, reported by whichever task knows it.
+///
+/// Every setter wakes [`run_status_bar`], so the state can live in a
+/// `static` shared by all tasks.
+pub struct StatusState {
+    /// Seconds since midnight, [`NO_TIME`] before the first report
+    time: AtomicU32,
+    flash: AtomicU8,
+    /// Counts UART activity, only changes matter
+    uart: AtomicU32,
+    brightness: AtomicU8,
+    changed: Signal<CriticalSectionRawMutex, ()>,
+}
+
+impl Default for StatusState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StatusState {
+    pub const fn new() -> Self {
+        Self {
+            time: AtomicU32::new(NO_TIME),
+            flash: AtomicU8::new(FlashHealth::Unknown as u8),
+            uart: AtomicU32::new(0),
+            brightness: AtomicU8::new(100),
+            changed: Signal::new(),
+        }
+    }
+
+    /// Time of day, e.g. read from the RTC once a second by [`run_clock`].
+    pub fn set_time(&self, hour: u8, minute: u8, second: u8) {
+        let seconds = (u32::from(hour) * 60 + u32::from(minute)) * 60 + u32::from(second);
+        self.time.store(seconds % (24 * 3600), Ordering::Relaxed);
+        self.changed.signal(());
+    }
+
+    pub fn set_flash(&self, health: FlashHealth) {
+        self.flash.store(health.into(), Ordering::Relaxed);
+        self.changed.signal(());
+    }
+
+    /// Lights the UART indicator briefly, call it for every transfer.
+    pub fn uart_activity(&self) {
+        self.uart.fetch_add(1, Ordering::Relaxed);
+        self.changed.signal(());
+    }
+
+    /// Backlight percent, as passed to [`LCD::set_brightness`].
+    pub fn set_brightness(&self, percent: u8) {
+        self.brightness.store(percent.min(100), Ordering::Relaxed);
+        self.changed.signal(());
+    }
+}
+
+/// Clock on the left, flash health, UART activity and backlight level on
+/// the right.
+///
+/// Only the parts that changed are redrawn, so it can be drawn by a task of
+/// its own, see [`run_status_bar`], while other tasks draw the rest of the
+/// screen.
+pub struct StatusBar {
+    rect: Rect,
+    fc: u16,
+    bc: u16,
+    ok: u16,
+    warning: u16,
+    font: FontSize,
+    time: u32,
+    flash: FlashHealth,
+    uart: u32,
+    /// When the UART indicator goes dark again, `None` while it is dark
+    uart_lit: Option<Instant>,
+    brightness: u8,
+    dirty: u8,
+}
+
+impl StatusBar {
+    /// A bar `rect.w` wide; [`STATUS_BAR_HEIGHT`] is the height the text
+    /// fits in.
+    pub const fn new(rect: Rect) -> Self {
+        Self {
+            rect,
+            fc: Color::White as u16,
+            bc: Color::Darkblue as u16,
+            ok: Color::Green as u16,
+            warning: Color::Red as u16,
+            font: FontSize::_6x12,
+            time: NO_TIME,
+            flash: FlashHealth::Unknown,
+            uart: 0,
+            uart_lit: None,
+            brightness: 100,
+            dirty: ALL,
+        }
+    }
+
+    /// `ok` marks a healthy flash and UART activity, `warning` a flash that
+    /// needs attention.
+    pub const fn colors(mut self, fc: u16, bc: u16, ok: u16, warning: u16) -> Self {
+        self.fc = fc;
+        self.bc = bc;
+        self.ok = ok;
+        self.warning = warning;
+        self
+    }
+
+    pub const fn font(mut self, font: FontSize) -> Self {
+        self.font = font;
+        self
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty != 0
+    }
+
+    pub fn invalidate(&mut self) {
+        self.dirty = ALL;
+    }
+
+    /// Background text in the small font on an accent bar, with the ok and
+    /// warning colors of `theme`.
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.fc = theme.bg;
+        self.bc = theme.accent;
+        self.ok = theme.ok;
+        self.warning = theme.warning;
+        self.font = theme.small_font;
+        self.dirty = ALL;
+    }
+
+    /// Takes over what changed in `state`, and darkens the UART indicator
+    /// once its blink is over.
+    pub fn update(&mut self, state: &StatusState) {
+        let now = Instant::now();
+
+        let time = state.time.load(Ordering::Relaxed);
+        if time != self.time {
+            self.time = time;
+            self.dirty |= TIME;
+        }
+
+        let flash = FlashHealth::try_from(state.flash.load(Ordering::Relaxed))
+            .unwrap_or(FlashHealth::Unknown);
+        if flash != self.flash {
+            self.flash = flash;
+            self.dirty |= FLASH;
+        }
+
+        let uart = state.uart.load(Ordering::Relaxed);
+        if uart != self.uart {
+            self.uart = uart;
+            if self.uart_lit.is_none() {
+                self.dirty |= UART;
+            }
+            self.uart_lit = Some(now + Duration::from_millis(UART_BLINK_MS));
+        } else if self.uart_lit.is_some_and(|until| until <= now) {
+            self.uart_lit = None;
+            self.dirty |= UART;
+        }
+
+        let brightness = state.brightness.load(Ordering::Relaxed);
+        if brightness != self.brightness {
+            self.brightness = brightness;
+            self.dirty |= BACKLIGHT;
+        }
+    }
+
+    /// When the UART indicator has to go dark, if it is lit.
+    pub fn blink_ends(&self) -> Option<Instant> {
+        self.uart_lit
+    }
+
+    /// Places of the indicators, from the right edge: backlight, UART,
+    /// flash.
+    fn indicators(&self) -> (Rect, Rect, Rect) {
+        let h = self.rect.h;
+        let backlight_w = BACKLIGHT_BARS * 3;
+        let backlight = Rect::new(
+            self.rect.right().saturating_sub(backlight_w + 2),
+            self.rect.y,
+            backlight_w,
+            h,
+        );
+        let uart = Rect::new(backlight.x.saturating_sub(9), self.rect.y, 7, h);
+        let flash = Rect::new(uart.x.saturating_sub(10), self.rect.y, 8, h);
+        (backlight, uart, flash)
+    }
+
+    pub async fn draw<C: timer::GeneralInstance4Channel>(&mut self, lcd: &mut LCD<C>) {
+        let dirty = self.dirty;
+        self.dirty = 0;
+        if dirty == ALL {
+            lcd.fill_paint(self.rect, &Paint::Solid(self.bc)).await;
+        }
+        let (backlight, uart, flash) = self.indicators();
+        // Indicators that are off
+        let dim = blend(self.fc, self.bc, 96);
+
+        if dirty & TIME != 0 {
+            let mut text = FmtBuf::<8>::new();
+            let _ = match self.time {
+                NO_TIME => text.write_str("--:--:--"),
+                time => write!(
+                    text,
+                    "{:02}:{:02}:{:02}",
+                    time / 3600,
+                    time / 60 % 60,
+                    time % 60
+                ),
+            };
+            let y = self.rect.y + self.rect.h.saturating_sub(self.font.y().as_u16()) / 2;
+            lcd.show_string(
+                (self.rect.x + 2, y),
+                text.as_str(),
+                self.font,
+                &GlyphStyle::new(self.fc, self.bc),
+            )
+            .await;
+        }
+
+        if dirty & FLASH != 0 {
+            let color = match self.flash {
+                FlashHealth::Unknown => dim,
+                FlashHealth::Ok => self.ok,
+                FlashHealth::Busy | FlashHealth::Protected => Color::Yellow as u16,
+                FlashHealth::UnknownChip | FlashHealth::Missing => self.warning,
+            };
+            lcd.fill_paint(flash, &Paint::Solid(self.bc)).await;
+            // A chip: the body with three pins on either side
+            let top = flash.y + flash.h.saturating_sub(8) / 2;
+            lcd.fill_paint(Rect::new(flash.x + 2, top, 4, 8), &Paint::Solid(color))
+                .await;
+            for pin in 0..3 {
+                let y = top + 1 + pin * 3;
+                lcd.fill_paint(Rect::new(flash.x, y, 1, 1), &Paint::Solid(color))
+                    .await;
+                lcd.fill_paint(Rect::new(flash.x + 7, y, 1, 1), &Paint::Solid(color))
+                    .await;
+            }
+        }
+
+        if dirty & UART != 0 {
+            let color = if self.uart_lit.is_some() {
+                self.ok
+            } else {
+                dim
+            };
+            let top = uart.y + uart.h.saturating_sub(uart.w) / 2;
+            lcd.fill_paint(uart, &Paint::Solid(self.bc)).await;
+            lcd.fill_paint(
+                Rect::new(uart.x + 1, top + 1, uart.w - 2, uart.w - 2),
+                &Paint::Solid(color),
+            )
+            .await;
+        }
+
+        if dirty & BACKLIGHT != 0 {
+            lcd.fill_paint(backlight, &Paint::Solid(self.bc)).await;
+            let lit = (u16::from(self.brightness) * BACKLIGHT_BARS).div_ceil(100);
+            let bottom = backlight.y + backlight.h.saturating_sub(2);
+            for bar in 0..BACKLIGHT_BARS {
+                let h = (3 + 2 * bar).min(bottom - backlight.y);
+                let color = if bar < lit { self.fc } else { dim };
+                lcd.fill_paint(
+                    Rect::new(backlight.x + bar * 3, bottom - h, 2, h),
+                    &Paint::Solid(color),
+                )
+                .await;
+            }
+        }
+    }
+}
+
+/// Keeps `bar` up to date with `state` forever, drawing only the parts that
+/// changed and holding the LCD only while it does.
+pub async fn run_status_bar<C: timer::GeneralInstance4Channel>(
+    bar: &mut StatusBar, state: &StatusState, lcd: &SharedLcd<C>,
+) -> ! {
+    loop {
+        bar.update(state);
+        if bar.is_dirty() {
+            bar.draw(&mut *lcd.lock().await).await;
+        }
+        match bar.blink_ends() {
+            Some(at) => {
+                select(state.changed.wait(), Timer::at(at)).await;
+            }
+            None => state.changed.wait().await,
+        }
+    }
+}
+
+/// Reports the time of day of `rtc` to `state` once a second, forever.
+pub async fn run_clock(rtc: &Rtc, state: &StatusState) -> ! {
+    let mut ticker = Ticker::every(Duration::from_secs(1));
+    loop {
+        let (hour, minute, second) = rtc.time_of_day();
+        state.set_time(hour, minute, second);
+        ticker.next().await;
+    }
+}
@@ -0,0 +1,74 @@
+//! Colors and fonts shared by the widgets of a screen.
+
+use crate::lcd::{
+    Color,
+    font::{ChineseFontSize, FontSize},
+    layout::TextLayout,
+};
+
+/// What the widgets are drawn with, applied with their `theme` builder when
+/// they are created or with [`super::set_theme`] at runtime.
+#[derive(Clone, Copy)]
+pub struct Theme {
+    /// Text and outlines
+    pub fg: u16,
+    pub bg: u16,
+    /// Selection, focus and filled parts like the bar of a progress bar
+    pub accent: u16,
+    /// Things that need attention
+    pub warning: u16,
+    /// Things that are fine: a healthy device, activity
+    pub ok: u16,
+    /// Parts in the background: empty tracks, grid lines
+    pub muted: u16,
+    pub font: FontSize,
+    pub chinese_font: ChineseFontSize,
+    /// Text of dense widgets like the status bar and the keyboard
+    pub small_font: FontSize,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::LIGHT
+    }
+}
+
+impl Theme {
+    /// Light text on black.
+    pub const DARK: Self = Self {
+        fg: Color::White as u16,
+        bg: Color::Black as u16,
+        accent: Color::Lightblue as u16,
+        warning: Color::Brred as u16,
+        ok: Color::Green as u16,
+        muted: Color::Gray as u16,
+        font: FontSize::_8x16,
+        chinese_font: ChineseFontSize::_16x16,
+        small_font: FontSize::_6x12,
+    };
+    /// Dark text on white, what the widgets use without a theme.
+    pub const LIGHT: Self = Self {
+        fg: Color::Black as u16,
+        bg: Color::White as u16,
+        accent: Color::Blue as u16,
+        warning: Color::Red as u16,
+        ok: Color::Green as u16,
+        muted: Color::Lgray as u16,
+        font: FontSize::_8x16,
+        chinese_font: ChineseFontSize::_16x16,
+        small_font: FontSize::_6x12,
+    };
+
+    /// Layout of plain text in the fonts of the theme.
+    pub const fn text(&self) -> TextLayout {
+        TextLayout::new(self.font, self.chinese_font)
+    }
+
+    /// `layout` with its fonts replaced by those of the theme, keeping the
+    /// alignment and spacing.
+    pub const fn fonts(&self, mut layout: TextLayout) -> TextLayout {
+        layout.font = self.font;
+        layout.chinese_font = self.chinese_font;
+        layout
+    }
+}
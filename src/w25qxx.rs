@@ -1,5 +1,5 @@
 use embassy_stm32::{gpio::Output, mode::Async, spi::Spi};
-use num_enum::TryFromPrimitive;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 // Page size constants
 pub const SPI_FLASH_PAGE_SIZE: usize = 256;
@@ -25,6 +25,8 @@ pub const W25X_JEDEC_DEVICE_ID: u8 = 0x9F;
 
 // Status flags
 pub const WIP_FLAG: u8 = 0x01; // Write In Progress (WIP) flag
+pub const BP_FLAGS: u8 = 0x1C; // Block Protect (BP2..BP0) flags
+pub const SRP_FLAG: u8 = 0x80; // Status Register Protect (SRP0) flag
 
 pub const DUMMY_BYTE: u8 = 0xFF;
 
@@ -38,6 +40,40 @@ pub enum W25QxxID {
     W25Q80 = 0xEF4014,
 }
 
+/// What the ID and status register tell about the chip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format, IntoPrimitive, TryFromPrimitive)]
+#[repr(u8)]
+pub enum FlashHealth {
+    /// Not checked yet
+    Unknown,
+    /// A known chip, idle and writable
+    Ok,
+    /// An erase or program is in progress
+    Busy,
+    /// Some blocks or the status register are write protected
+    Protected,
+    /// The JEDEC ID is not one of [`W25QxxID`]
+    UnknownChip,
+    /// No chip answers, the ID reads as all zeros or all ones
+    Missing,
+}
+
+impl FlashHealth {
+    pub fn from_id_status(id: u32, status: u8) -> Self {
+        if id == 0 || id == 0xFFFFFF {
+            FlashHealth::Missing
+        } else if W25QxxID::try_from_primitive(id).is_err() {
+            FlashHealth::UnknownChip
+        } else if status & WIP_FLAG != 0 {
+            FlashHealth::Busy
+        } else if status & (BP_FLAGS | SRP_FLAG) != 0 {
+            FlashHealth::Protected
+        } else {
+            FlashHealth::Ok
+        }
+    }
+}
+
 pub struct W25Qxx {
     spi: Spi<'static, Async>,
     cs: Output<'static>,
@@ -79,6 +115,24 @@ impl W25Qxx {
         id
     }
 
+    /// Reads status register 1.
+    pub async fn read_status(&mut self) -> u8 {
+        self.cs.set_low();
+
+        let data = &mut [W25X_READ_STATUS_REG, DUMMY_BYTE];
+        self.spi.transfer_in_place(data).await.unwrap();
+
+        self.cs.set_high();
+
+        data[1]
+    }
+
+    pub async fn health(&mut self) -> FlashHealth {
+        let id = self.read_id().await;
+        let status = self.read_status().await;
+        FlashHealth::from_id_status(id, status)
+    }
+
     pub async fn sector_erase(&mut self, sector_addr: u32) {
         self.write_enable().await;
         self.wait_for_write_end().await;
@@ -0,0 +1,153 @@
+//! Encodes a sequence of PNG frames as a delta rectangle animation, see
+//! `lcd::animation` for the format.
+
+use crate::image::{Image, load_png, rgb565};
+use anyhow::{Context, bail};
+use clap::Parser;
+use std::{fs, path::PathBuf};
+
+const MAGIC: &[u8; 4] = b"RANM";
+/// Unchanged rows that may be redrawn to save a rectangle header.
+const MAX_ROW_GAP: usize = 2;
+
+#[derive(Parser)]
+pub struct Args {
+    /// Input PNG frames, in playing order
+    #[arg(required = true)]
+    frames: Vec<PathBuf>,
+
+    /// Output animation file
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Delay of every frame in milliseconds
+    #[arg(long, default_value_t = 100)]
+    delay: u16,
+}
+
+/// Changed area of a frame, in pixels of the animation.
+struct Rect {
+    x: usize,
+    y: usize,
+    w: usize,
+    h: usize,
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+    let mut frames = Vec::new();
+    for path in &args.frames {
+        let image = load_png(path)?;
+        frames.push(pixels(&image));
+        let first = &args.frames[0];
+        let (w, h) = (image.width as usize, image.height as usize);
+        if frames.len() == 1 {
+            if w > usize::from(u16::MAX) || h > usize::from(u16::MAX) {
+                bail!("image is too large");
+            }
+        } else if frames[0].len() != h || frames[0][0].len() != w {
+            bail!(
+                "{} is not the same size as {}",
+                path.display(),
+                first.display()
+            );
+        }
+    }
+    if frames.len() > usize::from(u16::MAX) {
+        bail!("too many frames");
+    }
+
+    let (width, height) = (frames[0][0].len(), frames[0].len());
+    let mut out = MAGIC.to_vec();
+    out.extend((width as u16).to_le_bytes());
+    out.extend((height as u16).to_le_bytes());
+    out.extend((frames.len() as u16).to_le_bytes());
+
+    let mut changed = 0;
+    for (i, frame) in frames.iter().enumerate() {
+        let rects = match i {
+            0 => vec![Rect {
+                x: 0,
+                y: 0,
+                w: width,
+                h: height,
+            }],
+            _ => diff(&frames[i - 1], frame),
+        };
+        out.extend(args.delay.to_le_bytes());
+        out.extend((rects.len() as u16).to_le_bytes());
+        for rect in &rects {
+            for value in [rect.x, rect.y, rect.w, rect.h] {
+                out.extend((value as u16).to_le_bytes());
+            }
+            for row in &frame[rect.y..rect.y + rect.h] {
+                for color in &row[rect.x..rect.x + rect.w] {
+                    out.extend(color.to_le_bytes());
+                }
+            }
+            changed += rect.w * rect.h;
+        }
+    }
+
+    fs::write(&args.output, &out).with_context(|| format!("write {}", args.output.display()))?;
+
+    let raw = width * height * 2 * frames.len();
+    eprintln!(
+        "{}x{}, {} frames, {} pixels drawn: {} bytes, {} bytes as raw RGB565 frames",
+        width,
+        height,
+        frames.len(),
+        changed,
+        out.len(),
+        raw
+    );
+    Ok(())
+}
+
+fn pixels(image: &Image) -> Vec<Vec<u16>> {
+    (0..image.height)
+        .map(|y| {
+            (0..image.width)
+                .map(|x| rgb565(image.pixel(x, y)))
+                .collect()
+        })
+        .collect()
+}
+
+/// Rectangles covering every pixel that differs between `prev` and `next`,
+/// one per band of changed rows.
+fn diff(prev: &[Vec<u16>], next: &[Vec<u16>]) -> Vec<Rect> {
+    // Changed columns of every row, as `start..end`
+    let spans: Vec<Option<(usize, usize)>> = prev
+        .iter()
+        .zip(next)
+        .map(|(a, b)| {
+            let start = a.iter().zip(b).position(|(p, n)| p != n)?;
+            let end = a.len() - a.iter().zip(b).rev().position(|(p, n)| p != n)?;
+            Some((start, end))
+        })
+        .collect();
+
+    let mut rects: Vec<Rect> = Vec::new();
+    let mut last_row = None;
+    for (y, span) in spans.iter().enumerate() {
+        let Some((start, end)) = *span else {
+            continue;
+        };
+        match (rects.last_mut(), last_row) {
+            (Some(rect), Some(last)) if y - last <= MAX_ROW_GAP + 1 => {
+                let right = (rect.x + rect.w).max(end);
+                rect.x = rect.x.min(start);
+                rect.w = right - rect.x;
+                rect.h = y + 1 - rect.y;
+            }
+            _ => rects.push(Rect {
+                x: start,
+                y,
+                w: end - start,
+                h: 1,
+            }),
+        }
+        last_row = Some(y);
+    }
+    rects
+}
@@ -0,0 +1,121 @@
+//! Generates `lcd::font::AlphaFont` tables.
+//!
+//! Every glyph is rendered into a fixed `width x height` cell, with the
+//! baseline placed so that the font's ascent and descent fill the cell, and
+//! coverage quantized to `bpp` bits per pixel.
+
+use ab_glyph::{Font, FontVec, PxScale, ScaleFont, point};
+use anyhow::{Context, bail};
+use clap::Parser;
+use std::{fmt::Write as _, fs, path::PathBuf};
+
+#[derive(Parser)]
+pub struct Args {
+    /// Font file (.ttf / .otf)
+    font: PathBuf,
+
+    /// Name of the generated constant
+    #[arg(long)]
+    name: String,
+
+    /// Cell width in pixels
+    #[arg(long)]
+    width: u8,
+
+    /// Cell height in pixels
+    #[arg(long)]
+    height: u8,
+
+    /// Bits of alpha per pixel: 1, 2 or 4
+    #[arg(long, default_value_t = 4)]
+    bpp: u8,
+
+    /// Characters to include, defaults to printable ASCII
+    #[arg(long)]
+    chars: Option<String>,
+
+    /// Output file, defaults to stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+    if ![1, 2, 4].contains(&args.bpp) {
+        bail!("bpp must be 1, 2 or 4, got {}", args.bpp);
+    }
+    if args.width == 0 || args.width > 32 {
+        bail!("width must be between 1 and 32, got {}", args.width);
+    }
+
+    let data = fs::read(&args.font).with_context(|| format!("read {}", args.font.display()))?;
+    let font = FontVec::try_from_vec(data).context("parse font")?;
+
+    let chars: Vec<char> = match &args.chars {
+        Some(chars) => chars.chars().collect(),
+        None => (' '..='~').collect(),
+    };
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "/// Anti-aliased font with {}x{} pixels and {}-bit alpha",
+        args.width, args.height, args.bpp
+    )?;
+    writeln!(out, "pub const {}: AlphaFont = AlphaFont {{", args.name)?;
+    writeln!(out, "    width: {},", args.width)?;
+    writeln!(out, "    height: {},", args.height)?;
+    writeln!(out, "    bpp: Bpp::_{},", args.bpp)?;
+    writeln!(out, "    glyphs: &[")?;
+    for ch in chars {
+        let msk = rasterize(&font, ch, args.width, args.height, args.bpp);
+        writeln!(out, "        AlphaGlyph {{")?;
+        writeln!(out, "            index: {ch:?},")?;
+        writeln!(out, "            msk: &[")?;
+        for chunk in msk.chunks(12) {
+            let bytes: Vec<String> = chunk.iter().map(|b| format!("0x{b:02X}")).collect();
+            writeln!(out, "                {},", bytes.join(", "))?;
+        }
+        writeln!(out, "            ],")?;
+        writeln!(out, "        }},")?;
+    }
+    writeln!(out, "    ],")?;
+    writeln!(out, "}};")?;
+
+    match &args.output {
+        Some(path) => fs::write(path, out).with_context(|| format!("write {}", path.display()))?,
+        None => print!("{out}"),
+    }
+    Ok(())
+}
+
+fn rasterize(font: &FontVec, ch: char, width: u8, height: u8, bpp: u8) -> Vec<u8> {
+    let (width, height) = (usize::from(width), usize::from(height));
+    let max = (1u16 << bpp) - 1;
+    let row_bytes = (width * usize::from(bpp)).div_ceil(8);
+    let mut msk = vec![0u8; row_bytes * height];
+
+    // Scale so that ascent + descent fills the cell height
+    let unscaled = font.as_scaled(PxScale::from(height as f32));
+    let factor = height as f32 / (unscaled.ascent() - unscaled.descent());
+    let scaled = font.as_scaled(PxScale::from(height as f32 * factor));
+
+    let id = font.glyph_id(ch);
+    let advance = scaled.h_advance(id);
+    let x = ((width as f32 - advance) / 2.0).max(0.0);
+    let glyph = id.with_scale_and_position(scaled.scale(), point(x, scaled.ascent()));
+
+    if let Some(outlined) = font.outline_glyph(glyph) {
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i32 + gx as i32;
+            let py = bounds.min.y as i32 + gy as i32;
+            if px < 0 || py < 0 || px as usize >= width || py as usize >= height {
+                return;
+            }
+            let alpha = (coverage.clamp(0.0, 1.0) * f32::from(max)).round() as u8;
+            let bit = px as usize * usize::from(bpp);
+            msk[py as usize * row_bytes + bit / 8] |= alpha << (bit % 8);
+        });
+    }
+    msk
+}
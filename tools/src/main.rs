@@ -0,0 +1,41 @@
+//! Host side tooling for stm32f103-tft-board-boe-suite.
+
+mod anim;
+mod font;
+mod image;
+mod pack;
+mod qoi;
+mod sprite;
+
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Encode PNG frames as a delta rectangle animation for `Animator`
+    Anim(anim::Args),
+    /// Rasterize a TrueType/OpenType font into an `AlphaFont` table
+    Font(font::Args),
+    /// Compress a PNG into a QOI image for `LCD::show_qoi`
+    Qoi(qoi::Args),
+    /// Encode a PNG as a run-length encoded sprite for `LCD::draw_sprite`
+    Sprite(sprite::Args),
+    /// Pack a directory into an asset bundle for the external flash
+    Pack(pack::Args),
+}
+
+fn main() -> anyhow::Result<()> {
+    match Cli::parse().command {
+        Command::Anim(args) => anim::run(args),
+        Command::Font(args) => font::run(args),
+        Command::Qoi(args) => qoi::run(args),
+        Command::Sprite(args) => sprite::run(args),
+        Command::Pack(args) => pack::run(args),
+    }
+}
@@ -0,0 +1,279 @@
+//! Packs a directory into an asset bundle for the external flash, see the
+//! `asset` module of the firmware for the format.
+
+use crate::image::{load_png, rgb565};
+use anyhow::{Context, bail};
+use clap::Parser;
+use std::{collections::HashMap, fs, path::PathBuf};
+
+const MAGIC: &[u8; 4] = b"BOEA";
+const VERSION: u16 = 1;
+const HEADER_SIZE: usize = 16;
+const ENTRY_SIZE: usize = 24;
+/// Asset data starts on 4 byte boundaries.
+const ALIGN: usize = 4;
+
+/// Same values as `asset::AssetKind` on the device.
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+    Raw = 0,
+    Rgb565 = 1,
+    Bmp = 2,
+    Qoi = 3,
+    Sprite = 4,
+    Animation = 5,
+}
+
+#[derive(Parser)]
+pub struct Args {
+    /// Directory holding the assets. Files are named by their stem, PNGs are
+    /// converted to raw RGB565, `.bmp`, `.qoi`, `.spr` and `.anm` are stored
+    /// as is and anything else is stored as raw bytes
+    input: PathBuf,
+
+    /// Output bundle image
+    output: PathBuf,
+}
+
+struct Asset {
+    name: String,
+    kind: Kind,
+    data: Vec<u8>,
+    width: u16,
+    height: u16,
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+    let mut paths = fs::read_dir(&args.input)
+        .with_context(|| format!("read {}", args.input.display()))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.retain(|p| p.is_file());
+    paths.sort();
+
+    let mut assets = Vec::new();
+    let mut hashes = HashMap::new();
+    for path in paths {
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .with_context(|| format!("bad file name {}", path.display()))?
+            .to_owned();
+        let asset = load(&path, name).with_context(|| format!("load {}", path.display()))?;
+        if let Some(other) = hashes.insert(name_hash(&asset.name), asset.name.clone()) {
+            bail!("{} and {} have the same name hash", other, asset.name);
+        }
+        assets.push(asset);
+    }
+    if assets.len() > usize::from(u16::MAX) {
+        bail!("too many assets");
+    }
+
+    let bundle = build(&assets)?;
+    fs::write(&args.output, &bundle).with_context(|| format!("write {}", args.output.display()))?;
+
+    for asset in &assets {
+        eprintln!(
+            "{:<16} {:?} {}x{}, {} bytes",
+            asset.name,
+            asset.kind,
+            asset.width,
+            asset.height,
+            asset.data.len()
+        );
+    }
+    eprintln!("{} assets, {} bytes", assets.len(), bundle.len());
+    Ok(())
+}
+
+fn load(path: &std::path::Path, name: String) -> anyhow::Result<Asset> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+
+    if extension == "png" {
+        let image = load_png(path)?;
+        if image.width > u32::from(u16::MAX) || image.height > u32::from(u16::MAX) {
+            bail!("image is too large");
+        }
+        let data = (0..image.height)
+            .flat_map(|y| (0..image.width).map(move |x| (x, y)))
+            .flat_map(|(x, y)| rgb565(image.pixel(x, y)).to_be_bytes())
+            .collect();
+        return Ok(Asset {
+            name,
+            kind: Kind::Rgb565,
+            data,
+            width: image.width as u16,
+            height: image.height as u16,
+        });
+    }
+
+    let data = fs::read(path)?;
+    let (kind, width, height) = match extension.as_str() {
+        "bmp" => {
+            let width = u32_le(&data, 18)?;
+            let height = (u32_le(&data, 22)? as i32).unsigned_abs();
+            (Kind::Bmp, width, height)
+        }
+        "qoi" => (Kind::Qoi, u32_be(&data, 4)?, u32_be(&data, 8)?),
+        "spr" => (
+            Kind::Sprite,
+            u32::from(u16_le(&data, 4)?),
+            u32::from(u16_le(&data, 6)?),
+        ),
+        "anm" => (
+            Kind::Animation,
+            u32::from(u16_le(&data, 4)?),
+            u32::from(u16_le(&data, 6)?),
+        ),
+        _ => (Kind::Raw, 0, 0),
+    };
+    Ok(Asset {
+        name,
+        kind,
+        data,
+        width: u16::try_from(width).context("image is too large")?,
+        height: u16::try_from(height).context("image is too large")?,
+    })
+}
+
+fn build(assets: &[Asset]) -> anyhow::Result<Vec<u8>> {
+    let mut toc = Vec::with_capacity(assets.len() * ENTRY_SIZE);
+    let mut data = Vec::new();
+    let data_start = HEADER_SIZE + assets.len() * ENTRY_SIZE;
+
+    for asset in assets {
+        data.resize(data.len().next_multiple_of(ALIGN), 0xff);
+        let offset = u32::try_from(data_start + data.len()).context("bundle is too large")?;
+        let len = u32::try_from(asset.data.len()).context("asset is too large")?;
+
+        toc.extend(name_hash(&asset.name).to_le_bytes());
+        toc.extend([asset.kind as u8, 0, 0, 0]);
+        toc.extend(offset.to_le_bytes());
+        toc.extend(len.to_le_bytes());
+        toc.extend(crc32(&asset.data).to_le_bytes());
+        toc.extend(asset.width.to_le_bytes());
+        toc.extend(asset.height.to_le_bytes());
+        data.extend(&asset.data);
+    }
+
+    let size = u32::try_from(data_start + data.len()).context("bundle is too large")?;
+    let mut bundle = MAGIC.to_vec();
+    bundle.extend(VERSION.to_le_bytes());
+    bundle.extend((assets.len() as u16).to_le_bytes());
+    bundle.extend(crc32(&toc).to_le_bytes());
+    bundle.extend(size.to_le_bytes());
+    bundle.extend(toc);
+    bundle.extend(data);
+    Ok(bundle)
+}
+
+/// Same as `asset::name_hash` on the device.
+fn name_hash(name: &str) -> u32 {
+    name.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// CRC-32 (IEEE 802.3), same as `asset::crc32_update` on the device.
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |mut crc, &byte| {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+        crc
+    })
+}
+
+fn u16_le(data: &[u8], offset: usize) -> anyhow::Result<u16> {
+    match data.get(offset..offset + 2) {
+        Some(b) => Ok(u16::from_le_bytes([b[0], b[1]])),
+        None => bail!("file is truncated"),
+    }
+}
+
+fn u32_le(data: &[u8], offset: usize) -> anyhow::Result<u32> {
+    match data.get(offset..offset + 4) {
+        Some(b) => Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+        None => bail!("file is truncated"),
+    }
+}
+
+fn u32_be(data: &[u8], offset: usize) -> anyhow::Result<u32> {
+    match data.get(offset..offset + 4) {
+        Some(b) => Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]])),
+        None => bail!("file is truncated"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_hash_vectors() {
+        assert_eq!(name_hash(""), 0x811c_9dc5);
+        assert_eq!(name_hash("a"), 0xe40c_292c);
+        assert_eq!(name_hash("foobar"), 0xbf9c_f968);
+    }
+
+    #[test]
+    fn crc32_vectors() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(
+            crc32(b"The quick brown fox jumps over the lazy dog"),
+            0x414f_a339
+        );
+    }
+
+    #[test]
+    fn bundle_layout() {
+        let assets = [
+            Asset {
+                name: "logo".into(),
+                kind: Kind::Rgb565,
+                data: vec![0xf8, 0x00, 0x07, 0xe0, 0x00, 0x1f],
+                width: 3,
+                height: 1,
+            },
+            Asset {
+                name: "notes".into(),
+                kind: Kind::Raw,
+                data: b"hello".to_vec(),
+                width: 0,
+                height: 0,
+            },
+        ];
+        let bundle = build(&assets).unwrap();
+
+        assert_eq!(&bundle[..4], MAGIC);
+        assert_eq!(u16_le(&bundle, 4).unwrap(), VERSION);
+        assert_eq!(u16_le(&bundle, 6).unwrap(), 2);
+        let toc = &bundle[HEADER_SIZE..HEADER_SIZE + 2 * ENTRY_SIZE];
+        assert_eq!(u32_le(&bundle, 8).unwrap(), crc32(toc));
+        assert_eq!(u32_le(&bundle, 12).unwrap() as usize, bundle.len());
+
+        for (i, asset) in assets.iter().enumerate() {
+            let entry = &toc[i * ENTRY_SIZE..];
+            let offset = u32_le(entry, 8).unwrap() as usize;
+            let len = u32_le(entry, 12).unwrap() as usize;
+            assert_eq!(u32_le(entry, 0).unwrap(), name_hash(&asset.name));
+            assert_eq!(entry[4], asset.kind as u8);
+            assert_eq!(offset % ALIGN, 0);
+            assert!(offset >= HEADER_SIZE + toc.len());
+            assert_eq!(&bundle[offset..offset + len], &asset.data[..]);
+            assert_eq!(u32_le(entry, 16).unwrap(), crc32(&asset.data));
+            assert_eq!(u16_le(entry, 20).unwrap(), asset.width);
+            assert_eq!(u16_le(entry, 22).unwrap(), asset.height);
+        }
+    }
+}
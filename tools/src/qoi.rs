@@ -0,0 +1,63 @@
+//! Converts PNG images to QOI for `LCD::show_qoi`.
+
+use crate::image::{Image, load_png};
+use anyhow::Context;
+use clap::Parser;
+use std::{fs, path::PathBuf};
+
+#[derive(Parser)]
+pub struct Args {
+    /// Input PNG
+    input: PathBuf,
+
+    /// Output QOI file
+    output: PathBuf,
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+    let image = load_png(&args.input)?;
+    let encoded = encode(&image)?;
+    fs::write(&args.output, &encoded)
+        .with_context(|| format!("write {}", args.output.display()))?;
+
+    let raw = image.width as usize * image.height as usize * 2;
+    eprintln!(
+        "{}x{}: {} bytes, {} bytes as raw RGB565 ({:.0}%)",
+        image.width,
+        image.height,
+        encoded.len(),
+        raw,
+        encoded.len() as f64 * 100.0 / raw as f64
+    );
+    Ok(())
+}
+
+fn encode(image: &Image) -> anyhow::Result<Vec<u8>> {
+    qoi::encode_to_vec(&image.rgba, image.width, image.height).context("encode QOI")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let rgba = (0..6 * 5)
+            .flat_map(|i: u32| [i as u8 * 8, 0x80, 0xff - i as u8, 0xff])
+            .collect::<Vec<_>>();
+        let image = Image {
+            width: 6,
+            height: 5,
+            rgba,
+        };
+
+        let encoded = encode(&image).unwrap();
+        // Where `pack` and `LCD::show_qoi` read the size from
+        assert_eq!(&encoded[..4], b"qoif");
+        assert_eq!(&encoded[4..12], &[0, 0, 0, 6, 0, 0, 0, 5]);
+
+        let (header, decoded) = qoi::decode_to_vec(&encoded).unwrap();
+        assert_eq!((header.width, header.height), (6, 5));
+        assert_eq!(decoded, image.rgba);
+    }
+}
@@ -0,0 +1,236 @@
+//! Encodes PNG images as run-length encoded sprites, see `lcd::sprite` for
+//! the format.
+
+use crate::image::{Image, load_png, rgb565};
+use anyhow::{Context, bail};
+use clap::Parser;
+use std::{fs, path::PathBuf};
+
+const MAGIC: &[u8; 4] = b"RSPR";
+const MAX_TRANSPARENT: usize = 128;
+const MAX_OPAQUE: usize = 64;
+/// Shortest stretch of equal colors worth a repeat run.
+const MIN_REPEAT: usize = 3;
+
+#[derive(Parser)]
+pub struct Args {
+    /// Input PNG
+    input: PathBuf,
+
+    /// Output sprite file
+    output: PathBuf,
+
+    /// Treat this color as transparent instead of using the alpha channel,
+    /// as RRGGBB hex
+    #[arg(long, value_parser = parse_color)]
+    key: Option<[u8; 3]>,
+}
+
+fn parse_color(s: &str) -> Result<[u8; 3], String> {
+    let s = s.trim_start_matches('#');
+    let value = u32::from_str_radix(s, 16).map_err(|e| e.to_string())?;
+    if s.len() != 6 {
+        return Err("expected RRGGBB".into());
+    }
+    Ok([(value >> 16) as u8, (value >> 8) as u8, value as u8])
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+    let image = load_png(&args.input)?;
+    if image.width > u32::from(u16::MAX) || image.height > u32::from(u16::MAX) {
+        bail!("image is too large");
+    }
+
+    let encoded = encode(&image, args.key);
+    fs::write(&args.output, &encoded)
+        .with_context(|| format!("write {}", args.output.display()))?;
+
+    let raw = image.width as usize * image.height as usize * 2;
+    eprintln!(
+        "{}x{}: {} bytes, {} bytes as raw RGB565",
+        image.width,
+        image.height,
+        encoded.len(),
+        raw
+    );
+    Ok(())
+}
+
+fn encode(image: &Image, key: Option<[u8; 3]>) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    out.extend((image.width as u16).to_le_bytes());
+    out.extend((image.height as u16).to_le_bytes());
+
+    for y in 0..image.height {
+        // `None` marks a transparent pixel
+        let row: Vec<Option<u16>> = (0..image.width)
+            .map(|x| {
+                let px = image.pixel(x, y);
+                let transparent = match key {
+                    Some(key) => px[..3] == key,
+                    None => px[3] < 128,
+                };
+                (!transparent).then(|| rgb565(px))
+            })
+            .collect();
+
+        let mut x = 0;
+        while x < row.len() {
+            let end = span_end(&row, x);
+            match row[x] {
+                None => {
+                    let mut n = end - x;
+                    while n > 0 {
+                        let chunk = n.min(MAX_TRANSPARENT);
+                        out.push((chunk - 1) as u8);
+                        n -= chunk;
+                    }
+                }
+                Some(_) => {
+                    let colors: Vec<u16> = row[x..end].iter().map(|c| c.unwrap()).collect();
+                    encode_opaque(&colors, &mut out);
+                }
+            }
+            x = end;
+        }
+    }
+    out
+}
+
+/// End of the run of pixels with the same transparency as `row[start]`.
+fn span_end(row: &[Option<u16>], start: usize) -> usize {
+    let opaque = row[start].is_some();
+    row[start..]
+        .iter()
+        .position(|c| c.is_some() != opaque)
+        .map_or(row.len(), |n| start + n)
+}
+
+fn encode_opaque(colors: &[u16], out: &mut Vec<u8>) {
+    let mut literal: Vec<u16> = Vec::new();
+    let mut i = 0;
+    while i < colors.len() {
+        let same = colors[i..].iter().take_while(|&&c| c == colors[i]).count();
+        if same >= MIN_REPEAT {
+            flush_literal(&mut literal, out);
+            let n = same.min(MAX_OPAQUE);
+            out.push(0xC0 | (n - 1) as u8);
+            out.extend(colors[i].to_le_bytes());
+            i += n;
+        } else {
+            literal.push(colors[i]);
+            if literal.len() == MAX_OPAQUE {
+                flush_literal(&mut literal, out);
+            }
+            i += 1;
+        }
+    }
+    flush_literal(&mut literal, out);
+}
+
+fn flush_literal(literal: &mut Vec<u16>, out: &mut Vec<u8>) {
+    if literal.is_empty() {
+        return;
+    }
+    out.push(0x80 | (literal.len() - 1) as u8);
+    for color in literal.drain(..) {
+        out.extend(color.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decodes a sprite the way `lcd::sprite` does, `None` for transparent
+    /// pixels.
+    fn decode(data: &[u8]) -> (u16, u16, Vec<Option<u16>>) {
+        assert_eq!(&data[..4], MAGIC);
+        let width = u16::from_le_bytes([data[4], data[5]]);
+        let height = u16::from_le_bytes([data[6], data[7]]);
+        let color = |i: usize| Some(u16::from_le_bytes([data[i], data[i + 1]]));
+
+        let mut pixels = Vec::new();
+        let mut i = 8;
+        while i < data.len() {
+            let header = data[i];
+            let n = usize::from(header & 0x3f) + 1;
+            i += 1;
+            match header >> 6 {
+                0 | 1 => pixels.extend(vec![None; usize::from(header) + 1]),
+                2 => {
+                    pixels.extend((0..n).map(|k| color(i + 2 * k)));
+                    i += 2 * n;
+                }
+                _ => {
+                    pixels.extend(vec![color(i); n]);
+                    i += 2;
+                }
+            }
+        }
+        (width, height, pixels)
+    }
+
+    fn image(width: u32, height: u32, pixel: impl Fn(u32, u32) -> [u8; 4]) -> Image {
+        let rgba = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .flat_map(|(x, y)| pixel(x, y))
+            .collect();
+        Image {
+            width,
+            height,
+            rgba,
+        }
+    }
+
+    #[test]
+    fn known_row() {
+        // Transparent, three reds, green, blue
+        let image = image(6, 1, |x, _| match x {
+            0 => [0, 0, 0, 0],
+            1..=3 => [0xff, 0, 0, 0xff],
+            4 => [0, 0xff, 0, 0xff],
+            _ => [0, 0, 0xff, 0xff],
+        });
+        let mut expected = b"RSPR\x06\x00\x01\x00".to_vec();
+        // One transparent pixel
+        expected.push(0x00);
+        // Three times red
+        expected.extend([0xc2, 0x00, 0xf8]);
+        // Green and blue
+        expected.extend([0x81, 0xe0, 0x07, 0x1f, 0x00]);
+        assert_eq!(encode(&image, None), expected);
+    }
+
+    #[test]
+    fn round_trip() {
+        // Long runs that need splitting, repeats, literals and a color key
+        let key = [0x12, 0x34, 0x56];
+        let image = image(300, 4, |x, y| match (x + y * 7) % 150 {
+            0..=9 => [key[0], key[1], key[2], 0xff],
+            10..=79 => [0x40, 0x80, 0xc0, 0xff],
+            n => [n as u8, (n * 3) as u8, y as u8, 0xff],
+        });
+
+        let (width, height, pixels) = decode(&encode(&image, Some(key)));
+        assert_eq!((width, height), (300, 4));
+        let expected: Vec<_> = (0..4)
+            .flat_map(|y| (0..300).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let px = image.pixel(x, y);
+                (px[..3] != key).then(|| rgb565(px))
+            })
+            .collect();
+        assert_eq!(pixels, expected);
+    }
+
+    #[test]
+    fn alpha_transparency() {
+        let image = image(200, 1, |x, _| {
+            [0xff, 0xff, 0xff, if x < 130 { 0 } else { 0xff }]
+        });
+        let (_, _, pixels) = decode(&encode(&image, None));
+        assert_eq!(pixels.iter().filter(|p| p.is_none()).count(), 130);
+        assert!(pixels[130..].iter().all(|&p| p == Some(0xffff)));
+    }
+}